@@ -7,6 +7,40 @@ use serde::{Deserialize, Serialize};
 
 use crate::{models, types::*};
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[allow(clippy::large_enum_variant)]
+pub enum EditMergedImageResponse {
+    /// Successful operation
+    Status200_SuccessfulOperation
+    (models::SuccessResponse)
+    ,
+    /// Bad Request
+    Status400_BadRequest
+    (models::ErrorResponse)
+    ,
+    /// Internal Server Error
+    Status500_InternalServerError
+    (models::ErrorResponse)
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[allow(clippy::large_enum_variant)]
+pub enum InspectMergedImageResponse {
+    /// Successful operation
+    Status200_SuccessfulOperation
+    (models::SuccessResponse)
+    ,
+    /// Bad Request
+    Status400_BadRequest
+    (models::ErrorResponse)
+    ,
+    /// Internal Server Error
+    Status500_InternalServerError
+    (models::ErrorResponse)
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[must_use]
 #[allow(clippy::large_enum_variant)]
@@ -37,6 +71,57 @@ pub enum UpdateMergedImageResponse {
     (models::ErrorResponse)
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[allow(clippy::large_enum_variant)]
+pub enum UploadAtlasResponse {
+    /// Successful operation
+    Status200_SuccessfulOperation
+    (models::SuccessResponse)
+    ,
+    /// Bad Request
+    Status400_BadRequest
+    (models::ErrorResponse)
+    ,
+    /// Internal Server Error
+    Status500_InternalServerError
+    (models::ErrorResponse)
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[allow(clippy::large_enum_variant)]
+pub enum UploadCubemapResponse {
+    /// Successful operation
+    Status200_SuccessfulOperation
+    (models::SuccessResponse)
+    ,
+    /// Bad Request
+    Status400_BadRequest
+    (models::ErrorResponse)
+    ,
+    /// Internal Server Error
+    Status500_InternalServerError
+    (models::ErrorResponse)
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[must_use]
+#[allow(clippy::large_enum_variant)]
+pub enum UploadFlipbookResponse {
+    /// Successful operation
+    Status200_SuccessfulOperation
+    (models::SuccessResponse)
+    ,
+    /// Bad Request
+    Status400_BadRequest
+    (models::ErrorResponse)
+    ,
+    /// Internal Server Error
+    Status500_InternalServerError
+    (models::ErrorResponse)
+}
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[must_use]
 #[allow(clippy::large_enum_variant)]
@@ -78,6 +163,30 @@ pub enum UploadMergedImageResponse {
 #[async_trait]
 #[allow(clippy::ptr_arg)]
 pub trait Default<E: std::fmt::Debug + Send + Sync + 'static = ()>: super::ErrorHandler<E> {
+    /// 束ねたファイルに対して画像の挿入・削除・並べ替えを行う.
+    ///
+    /// EditMergedImage - PATCH /api/v1/merged-images
+    async fn edit_merged_image(
+    &self,
+    
+    method: &Method,
+    host: &Host,
+    cookies: &CookieJar,
+    body: Multipart,
+    ) -> Result<EditMergedImageResponse, E>;
+
+    /// 束ねたファイルの構造を調べる.
+    ///
+    /// InspectMergedImage - POST /api/v1/merged-images/inspect
+    async fn inspect_merged_image(
+    &self,
+    
+    method: &Method,
+    host: &Host,
+    cookies: &CookieJar,
+    body: Multipart,
+    ) -> Result<InspectMergedImageResponse, E>;
+
     /// 疎通確認.
     ///
     /// Ping - GET /api/v1/ping
@@ -101,6 +210,42 @@ pub trait Default<E: std::fmt::Debug + Send + Sync + 'static = ()>: super::Error
     body: Multipart,
     ) -> Result<UpdateMergedImageResponse, E>;
 
+    /// 複数の画像をアトラステクスチャに詰め込んでDDS形式に変換し、ストレージにアップロードする.
+    ///
+    /// UploadAtlas - POST /api/v1/atlases
+    async fn upload_atlas(
+    &self,
+    
+    method: &Method,
+    host: &Host,
+    cookies: &CookieJar,
+    body: Multipart,
+    ) -> Result<UploadAtlasResponse, E>;
+
+    /// 6面の画像からキューブマップのDDSを作成し、ストレージにアップロードする.
+    ///
+    /// UploadCubemap - POST /api/v1/cubemaps
+    async fn upload_cubemap(
+    &self,
+    
+    method: &Method,
+    host: &Host,
+    cookies: &CookieJar,
+    body: Multipart,
+    ) -> Result<UploadCubemapResponse, E>;
+
+    /// アニメーション画像または連番画像をスプライトシートにしてDDS形式に変換し、ストレージにアップロードする.
+    ///
+    /// UploadFlipbook - POST /api/v1/flipbooks
+    async fn upload_flipbook(
+    &self,
+    
+    method: &Method,
+    host: &Host,
+    cookies: &CookieJar,
+    body: Multipart,
+    ) -> Result<UploadFlipbookResponse, E>;
+
     /// １枚の画像をDDS形式に変換し、ストレージにアップロードする.
     ///
     /// UploadImage - POST /api/v1/images
@@ -1,9 +1,9 @@
 use axum::{
-    body::Bytes,
+    body::{Body, Bytes},
     extract::{DefaultBodyLimit, Path},
     http::StatusCode,
     response::Response,
-    routing::put,
+    routing::get,
     Router,
 };
 use dotenvy::dotenv;
@@ -62,6 +62,32 @@ async fn upload_file(
         .unwrap())
 }
 
+/// アップロード済みのファイルを取得するエンドポイント
+/// GET /upload/{filename}
+async fn download_file(Path(filename): Path<String>) -> Result<Response<Body>, StatusCode> {
+    info!("Received download request for file: {}", filename);
+
+    let storage_dir = env::var("MOCK_STORAGE_DIR").unwrap_or_else(|_| "./mock-storage".to_string());
+    let file_path = PathBuf::from(&storage_dir).join(&filename);
+
+    let data = fs::read(&file_path).await.map_err(|e| {
+        error!("Failed to read file {}: {}", file_path.display(), e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    info!(
+        "File read successfully: {} (size: {} bytes)",
+        file_path.display(),
+        data.len()
+    );
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/octet-stream")
+        .body(Body::from(data))
+        .unwrap())
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().expect(".env file not found");
@@ -94,7 +120,7 @@ async fn main() {
 
     // ルーターを構築
     let app = Router::new()
-        .route("/upload/{filename}", put(upload_file))
+        .route("/upload/{filename}", get(download_file).put(upload_file))
         .layer(DefaultBodyLimit::max(body_limit));
 
     // サーバーを起動
@@ -0,0 +1,101 @@
+use std::str::FromStr;
+
+use crate::model::{CompressionOptions, MipmapOptions, TextureFormat};
+
+/// 各エンドポイントで共通の変換の設定のフィールドを集める
+///
+/// multipart の読み取りで [`ConversionFields::accept`] に渡し、全て読み終えてから [`ConversionFields::parse`] でまとめて解釈する
+#[derive(Debug, Default)]
+pub struct ConversionFields {
+    /// ミップマップと上下反転のフィールドも受け付けるか
+    mipmaps_enabled: bool,
+    format: Option<String>,
+    quality: Option<String>,
+    color_space: Option<String>,
+    dithering: bool,
+    /// mipmaps=false が指定されたか（指定しなければミップマップを生成する）
+    no_mipmaps: bool,
+    min_mip_size: Option<String>,
+    mip_filter: Option<String>,
+    flip_y: bool,
+}
+
+/// 解釈した変換の設定
+#[derive(Debug, Default)]
+pub struct ConversionFieldValues {
+    /// 変換後のフォーマット
+    pub format: TextureFormat,
+    /// 圧縮の設定
+    pub compression: CompressionOptions,
+    /// ミップマップの設定（mipmaps=false の場合は None）
+    pub mipmaps: Option<MipmapOptions>,
+    /// 上下反転するか
+    pub flip_y: bool,
+}
+
+impl ConversionFields {
+    /// format, quality, colorSpace, dithering を受け付ける
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// mipmaps, minMipSize, mipFilter, flipY も受け付ける
+    pub fn with_mipmaps(mut self) -> Self {
+        self.mipmaps_enabled = true;
+        self
+    }
+
+    /// 変換の設定のフィールドなら値を覚えて true を返す（それ以外のフィールドなら false）
+    pub fn accept(&mut self, name: &str, data: &[u8]) -> bool {
+        let mipmap_field = matches!(name, "mipmaps" | "minMipSize" | "mipFilter" | "flipY");
+        let known = matches!(name, "format" | "quality" | "colorSpace" | "dithering")
+            || (self.mipmaps_enabled && mipmap_field);
+        if !known {
+            return false;
+        }
+
+        if let Ok(s) = String::from_utf8(data.to_vec()) {
+            match name {
+                "format" => self.format = Some(s),
+                "quality" => self.quality = Some(s),
+                "colorSpace" => self.color_space = Some(s),
+                "dithering" => self.dithering = s.trim().eq_ignore_ascii_case("true"),
+                "mipmaps" => self.no_mipmaps = s.trim().eq_ignore_ascii_case("false"),
+                "minMipSize" => self.min_mip_size = Some(s),
+                "mipFilter" => self.mip_filter = Some(s),
+                "flipY" => self.flip_y = s.trim().eq_ignore_ascii_case("true"),
+                _ => unreachable!(),
+            }
+        }
+        true
+    }
+
+    /// 集めたフィールドを解釈する（不正な値ならその理由を返す）
+    pub fn parse(&self) -> Result<ConversionFieldValues, String> {
+        let format = self
+            .format
+            .as_deref()
+            .map(TextureFormat::from_str)
+            .transpose()
+            .map_err(|e| e.to_string())?
+            .unwrap_or_default();
+        let compression = CompressionOptions::parse(
+            self.quality.as_deref(),
+            self.color_space.as_deref(),
+            self.dithering,
+        )
+        .map_err(|e| e.to_string())?;
+        // NOTE: mipmaps を受け付けないエンドポイントではミップマップを生成しない
+        //       minMipSize と mipFilter は mipmaps=false の場合は使わない
+        let mipmaps = (self.mipmaps_enabled && !self.no_mipmaps)
+            .then(|| MipmapOptions::parse(self.min_mip_size.as_deref(), self.mip_filter.as_deref()))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok(ConversionFieldValues {
+            format,
+            compression,
+            mipmaps,
+            flip_y: self.flip_y,
+        })
+    }
+}
@@ -0,0 +1,30 @@
+use serde_json::{json, Value};
+
+use crate::model::FixedDimensions;
+
+/// 縦横のピクセル数の補正結果を返す形式にする
+pub fn fixed_dimensions_json(fixed: &FixedDimensions) -> Value {
+    json!({
+        "strategy": fixed.strategy.name(),
+        "originalWidth": fixed.original_width,
+        "originalHeight": fixed.original_height,
+        "width": fixed.width,
+        "height": fixed.height,
+    })
+}
+
+/// 補正した画像だけを、何枚目の画像かと合わせて返す形式にする（補正した画像がなければ None）
+pub fn fixed_dimensions_json_of(fixed_dimensions: &[Option<FixedDimensions>]) -> Option<Value> {
+    let entries: Vec<Value> = fixed_dimensions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, fixed)| {
+            fixed.as_ref().map(|fixed| {
+                let mut value = fixed_dimensions_json(fixed);
+                value["index"] = json!(index);
+                value
+            })
+        })
+        .collect();
+    (!entries.is_empty()).then_some(Value::Array(entries))
+}
@@ -0,0 +1,231 @@
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum_extra::extract::{CookieJar, Host};
+use generated::apis;
+use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
+use http::Method;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::handler::conversion_fields::{ConversionFieldValues, ConversionFields};
+use crate::handler::dimension_fix::fixed_dimensions_json_of;
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{DimensionFix, EditScript, EntrySource, OutputEncoding};
+use crate::service::{EditMergedImageOptions, EditMergedImageService, ServiceError};
+
+/// 束ねたファイルに対して画像の挿入・削除・並べ替えを行う
+pub async fn handle(
+    _method: &Method,
+    _host: &Host,
+    _cookies: &CookieJar,
+    mut body: Multipart,
+    service: &dyn EditMergedImageService,
+) -> Result<apis::default::EditMergedImageResponse, ()> {
+    info!("edit_merged_image() called");
+
+    let mut presigned_url: Option<String> = None;
+    let mut download_url: Option<String> = None;
+    let mut operations: Option<String> = None;
+    let mut files: Vec<Vec<u8>> = Vec::new();
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new().with_mipmaps();
+    let mut dimension_fix: Option<String> = None;
+    let mut pad_color: Option<String> = None;
+
+    while let Ok(Some(field)) = body.next_field().await {
+        let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
+        if let Ok(data) = field.bytes().await {
+            match name.as_str() {
+                "presignedUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        presigned_url = Some(s);
+                    }
+                }
+                "downloadUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        download_url = Some(s);
+                    }
+                }
+                "operations" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        operations = Some(s);
+                    }
+                }
+                "files" => {
+                    info!("file received: {} bytes", data.len());
+                    files.push(data.to_vec());
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                "dimensionFix" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        dimension_fix = Some(s);
+                    }
+                }
+                "padColor" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        pad_color = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
+                _ => {
+                    warn!("Unknown field: {}", name);
+                }
+            }
+        } else {
+            warn!("Failed to parse body to bytes");
+        }
+    }
+
+    let missing_field = if presigned_url.is_none() {
+        Some("presignedUrl is required")
+    } else if download_url.is_none() {
+        Some("downloadUrl is required")
+    } else if operations.is_none() {
+        Some("operations is required")
+    } else {
+        None
+    };
+    if let Some(missing_field) = missing_field {
+        return Ok(
+            apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(
+                missing_field,
+            )),
+        );
+    }
+
+    let script = match EditScript::from_str(&operations.unwrap()) {
+        Ok(script) => script,
+        Err(e) => {
+            info!("Invalid operations: {}", e);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let encoding = match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let ConversionFieldValues {
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    } = match conversion_fields.parse() {
+        Ok(conversion) => conversion,
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(&e)),
+            );
+        }
+    };
+
+    let dimension_fix = match dimension_fix
+        .map(|s| DimensionFix::parse(&s, pad_color.as_deref()))
+        .transpose()
+    {
+        Ok(dimension_fix) => dimension_fix,
+        Err(e) => {
+            info!("Invalid dimensionFix: {}", e);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let presigned_url = presigned_url.unwrap();
+    let download_url = download_url.unwrap();
+    let options = EditMergedImageOptions {
+        encoding,
+        dimension_fix,
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    };
+
+    // NOTE: 実処理
+    let data = match service
+        .execute(&download_url, &presigned_url, &script, &files, &options)
+        .await
+    {
+        Ok(result) => {
+            let sources: Vec<Value> = result.sources.iter().map(source_json).collect();
+            let mut data = json!({ "sources": sources });
+            if let Some(fixed_dimensions) = fixed_dimensions_json_of(&result.fixed_dimensions) {
+                data["fixedDimensions"] = fixed_dimensions;
+            }
+            Some(Nullable::from(Object(data)))
+        }
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(bad_request(&msg)),
+            );
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(
+                apis::default::EditMergedImageResponse::Status400_BadRequest(unsupported_format(
+                    &e,
+                )),
+            );
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::EditMergedImageResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
+
+    Ok(
+        apis::default::EditMergedImageResponse::Status200_SuccessfulOperation(
+            models::SuccessResponse {
+                message: success_message::SUCCESS.to_string(),
+                data,
+            },
+        ),
+    )
+}
+
+/// 編集後の画像の出どころを返す形式にする
+fn source_json(source: &EntrySource) -> Value {
+    match *source {
+        EntrySource::Existing(index) => json!({ "source": "existing", "index": index }),
+        EntrySource::Inserted(index) => json!({ "source": "inserted", "index": index }),
+    }
+}
@@ -0,0 +1,200 @@
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum_extra::extract::{CookieJar, Host};
+use generated::apis;
+use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
+use http::Method;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::OutputEncoding;
+use crate::service::{InspectMergedImageService, InspectTarget, MergedFileReport, ServiceError};
+
+/// 束ねたファイルの構造を調べる
+pub async fn handle(
+    _method: &Method,
+    _host: &Host,
+    _cookies: &CookieJar,
+    mut body: Multipart,
+    service: &dyn InspectMergedImageService,
+) -> Result<apis::default::InspectMergedImageResponse, ()> {
+    info!("inspect_merged_image() called");
+
+    let mut download_url: Option<String> = None;
+    let mut file_data: Option<Vec<u8>> = None;
+    let mut encoding: Option<String> = None;
+
+    while let Ok(Some(field)) = body.next_field().await {
+        let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
+        if let Ok(data) = field.bytes().await {
+            match name.as_str() {
+                "downloadUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        download_url = Some(s);
+                    }
+                }
+                "file" => {
+                    info!("file received: {} bytes", data.len());
+                    file_data = Some(data.to_vec());
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                _ => {
+                    warn!("Unknown field: {}", name);
+                }
+            }
+        } else {
+            warn!("Failed to parse body to bytes");
+        }
+    }
+
+    let target = match (&download_url, &file_data) {
+        (Some(download_url), None) => InspectTarget::Url(download_url),
+        (None, Some(file_data)) => InspectTarget::Data(file_data),
+        (download_url, _) => {
+            let missing_field = if download_url.is_none() {
+                "downloadUrl or file is required"
+            } else {
+                "downloadUrl and file cannot be used together"
+            };
+            return Ok(
+                apis::default::InspectMergedImageResponse::Status400_BadRequest(bad_request(
+                    missing_field,
+                )),
+            );
+        }
+    };
+
+    let encoding = match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(
+                apis::default::InspectMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    // NOTE: 実処理
+    let data = match service.execute(target, encoding).await {
+        Ok(report) => Some(Nullable::from(Object(report_json(&report)))),
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(
+                apis::default::InspectMergedImageResponse::Status400_BadRequest(bad_request(&msg)),
+            );
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(
+                apis::default::InspectMergedImageResponse::Status400_BadRequest(
+                    unsupported_format(&e),
+                ),
+            );
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::InspectMergedImageResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
+
+    Ok(
+        apis::default::InspectMergedImageResponse::Status200_SuccessfulOperation(
+            models::SuccessResponse {
+                message: success_message::SUCCESS.to_string(),
+                data,
+            },
+        ),
+    )
+}
+
+/// 調べた結果を返す形式にする
+fn report_json(report: &MergedFileReport) -> Value {
+    let entries: Vec<Value> = report
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let mut value = json!({
+                "index": index,
+                "size": entry.size,
+            });
+            if let Some(offset) = entry.offset {
+                value["offset"] = json!(offset);
+            }
+            match &entry.dds {
+                Some(Ok(header)) => {
+                    value["dds"] = json!({
+                        "width": header.width,
+                        "height": header.height,
+                        "mipCount": header.mip_count,
+                        "format": header.format.name(),
+                        "unityTextureFormat": header.format.unity_texture_format(),
+                    });
+                }
+                Some(Err(e)) => value["ddsError"] = json!(e.to_string()),
+                None => {}
+            }
+            if let Some(descriptor) = &entry.descriptor {
+                value["descriptor"] = json!({
+                    "width": descriptor.width,
+                    "height": descriptor.height,
+                    "format": descriptor.format,
+                    "mipCount": descriptor.mip_count,
+                });
+            }
+            if let Some(checksum) = entry.checksum {
+                value["checksum"] = json!(checksum.expected);
+                value["actualChecksum"] = json!(checksum.actual);
+                value["checksumValid"] = json!(checksum.is_valid());
+            }
+            if let Some(error) = &entry.error {
+                value["error"] = json!(error.to_string());
+            }
+            value
+        })
+        .collect();
+
+    let mut data = json!({
+        "formatVersion": report.version.number(),
+        "flags": report.flags,
+        "entryCount": report.entry_count,
+        "totalSize": report.total_size,
+        "headroom": report.headroom,
+        "valid": report.is_valid(),
+        "entries": entries,
+        "errors": report.errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+    });
+    if let Some(metadata) = &report.metadata {
+        data["metadata"] = json!(metadata);
+    }
+    if let Some(file_checksum) = report.file_checksum {
+        data["fileChecksum"] = json!(file_checksum.expected);
+        data["actualFileChecksum"] = json!(file_checksum.actual);
+        data["fileChecksumValid"] = json!(file_checksum.is_valid());
+    }
+    data
+}
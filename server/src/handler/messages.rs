@@ -12,6 +12,9 @@ pub mod error_code {
     /// 無効な入力エラーコード
     pub const INVALID_INPUT: &str = "INVALID_INPUT";
 
+    /// 許可されていない形式の画像が入力されたエラーコード
+    pub const UNSUPPORTED_FORMAT: &str = "UNSUPPORTED_FORMAT";
+
     /// インフラストラクチャーエラーコード
     pub const INFRASTRUCTURE_FAILED: &str = "INFRASTRUCTURE_FAILED";
 }
@@ -5,11 +5,23 @@ use generated::apis;
 use http::Method;
 use std::sync::Arc;
 
-use crate::service::{UploadMergedImageService, UploadSingleImageService};
+use crate::service::{
+    EditMergedImageService, InspectMergedImageService, UpdateMergedImageService,
+    UploadAtlasService, UploadCubemapService, UploadFlipbookService, UploadMergedImageService,
+    UploadSingleImageService,
+};
 
+mod conversion_fields;
+mod dimension_fix;
+mod edit_merged_image;
+mod inspect_merged_image;
 mod messages;
 mod ping;
+mod responses;
 mod update_merged_image;
+mod upload_atlas;
+mod upload_cubemap;
+mod upload_flipbook;
 mod upload_image;
 mod upload_merged_image;
 
@@ -18,16 +30,35 @@ mod upload_merged_image;
 pub struct ServerImpl {
     upload_image_service: Arc<dyn UploadSingleImageService>,
     upload_merged_image_service: Arc<dyn UploadMergedImageService>,
+    update_merged_image_service: Arc<dyn UpdateMergedImageService>,
+    edit_merged_image_service: Arc<dyn EditMergedImageService>,
+    inspect_merged_image_service: Arc<dyn InspectMergedImageService>,
+    upload_atlas_service: Arc<dyn UploadAtlasService>,
+    upload_flipbook_service: Arc<dyn UploadFlipbookService>,
+    upload_cubemap_service: Arc<dyn UploadCubemapService>,
 }
 
 impl ServerImpl {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         upload_image_service: Arc<dyn UploadSingleImageService>,
         upload_merged_image_service: Arc<dyn UploadMergedImageService>,
+        update_merged_image_service: Arc<dyn UpdateMergedImageService>,
+        edit_merged_image_service: Arc<dyn EditMergedImageService>,
+        inspect_merged_image_service: Arc<dyn InspectMergedImageService>,
+        upload_atlas_service: Arc<dyn UploadAtlasService>,
+        upload_flipbook_service: Arc<dyn UploadFlipbookService>,
+        upload_cubemap_service: Arc<dyn UploadCubemapService>,
     ) -> Self {
         Self {
             upload_image_service,
             upload_merged_image_service,
+            update_merged_image_service,
+            edit_merged_image_service,
+            inspect_merged_image_service,
+            upload_atlas_service,
+            upload_flipbook_service,
+            upload_cubemap_service,
         }
     }
 }
@@ -94,7 +125,104 @@ impl apis::default::Default<()> for ServerImpl {
         cookies: &CookieJar,
         body: Multipart,
     ) -> Result<apis::default::UpdateMergedImageResponse, ()> {
-        update_merged_image::handle(method, host, cookies, body).await
+        update_merged_image::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.update_merged_image_service.as_ref(),
+        )
+        .await
+    }
+
+    /// 束ねたファイルに対して画像の挿入・削除・並べ替えを行う
+    async fn edit_merged_image(
+        &self,
+        method: &Method,
+        host: &Host,
+        cookies: &CookieJar,
+        body: Multipart,
+    ) -> Result<apis::default::EditMergedImageResponse, ()> {
+        edit_merged_image::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.edit_merged_image_service.as_ref(),
+        )
+        .await
+    }
+
+    /// 束ねたファイルの構造を調べる
+    async fn inspect_merged_image(
+        &self,
+        method: &Method,
+        host: &Host,
+        cookies: &CookieJar,
+        body: Multipart,
+    ) -> Result<apis::default::InspectMergedImageResponse, ()> {
+        inspect_merged_image::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.inspect_merged_image_service.as_ref(),
+        )
+        .await
+    }
+
+    /// 複数の画像をアトラステクスチャに詰め込んでDDS形式に変換し、ストレージにアップロードする
+    async fn upload_atlas(
+        &self,
+        method: &Method,
+        host: &Host,
+        cookies: &CookieJar,
+        body: Multipart,
+    ) -> Result<apis::default::UploadAtlasResponse, ()> {
+        upload_atlas::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.upload_atlas_service.as_ref(),
+        )
+        .await
+    }
+
+    /// アニメーション画像または連番画像をスプライトシートにしてDDS形式に変換し、ストレージにアップロードする
+    async fn upload_flipbook(
+        &self,
+        method: &Method,
+        host: &Host,
+        cookies: &CookieJar,
+        body: Multipart,
+    ) -> Result<apis::default::UploadFlipbookResponse, ()> {
+        upload_flipbook::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.upload_flipbook_service.as_ref(),
+        )
+        .await
+    }
+
+    /// 6面の画像からキューブマップのDDSを作成し、ストレージにアップロードする
+    async fn upload_cubemap(
+        &self,
+        method: &Method,
+        host: &Host,
+        cookies: &CookieJar,
+        body: Multipart,
+    ) -> Result<apis::default::UploadCubemapResponse, ()> {
+        upload_cubemap::handle(
+            method,
+            host,
+            cookies,
+            body,
+            self.upload_cubemap_service.as_ref(),
+        )
+        .await
     }
 }
 
@@ -0,0 +1,29 @@
+use generated::models;
+use generated::types::{Nullable, Object};
+use serde_json::{json, Value};
+
+use crate::handler::messages::{error_code, error_message};
+use crate::model::error::UnsupportedFormatError;
+
+/// 不正な入力のエラーレスポンスを、理由のメッセージを details にして作る
+pub fn bad_request(details: &str) -> models::ErrorResponse {
+    models::ErrorResponse {
+        message: error_message::BAD_REQUEST.to_string(),
+        error_code: error_code::INVALID_INPUT.to_string(),
+        details: Some(Nullable::from(Object(Value::String(details.to_string())))),
+    }
+}
+
+/// 対応していないフォーマットのエラーレスポンスを、検出したフォーマットと許可されているフォーマットを details にして作る
+pub fn unsupported_format(e: &UnsupportedFormatError) -> models::ErrorResponse {
+    models::ErrorResponse {
+        message: error_message::BAD_REQUEST.to_string(),
+        error_code: error_code::UNSUPPORTED_FORMAT.to_string(),
+        details: Some(Nullable::from(Object(json!({
+            "message": e.to_string(),
+            "index": e.index,
+            "detected": e.detected,
+            "allowed": e.allowed,
+        })))),
+    }
+}
@@ -1,26 +1,48 @@
+use std::str::FromStr;
+
 use axum::extract::Multipart;
 use axum_extra::extract::{CookieJar, Host};
 use generated::apis;
 use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
 use http::Method;
-use log::info;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::handler::conversion_fields::{ConversionFieldValues, ConversionFields};
+use crate::handler::dimension_fix::fixed_dimensions_json;
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{DimensionFix, OutputEncoding};
+use crate::service::{
+    MergedImageReplacement, ServiceError, UpdateMergedImageOptions, UpdateMergedImageService,
+};
 
 /// 複数画像を束ねたファイルの指定枚目だけ更新する
+///
+/// index と file を複数組指定した場合は、全て差し替えてから1回だけアップロードする
 pub async fn handle(
     _method: &Method,
     _host: &Host,
     _cookies: &CookieJar,
     mut body: Multipart,
+    service: &dyn UpdateMergedImageService,
 ) -> Result<apis::default::UpdateMergedImageResponse, ()> {
     info!("update_merged_image() called");
 
     let mut presigned_url: Option<String> = None;
-    let mut index: Option<i32> = None;
-    let mut metadata: Option<String> = None;
-    let mut _file_data: Option<Vec<u8>> = None;
+    let mut download_url: Option<String> = None;
+    let mut indices: Vec<i32> = Vec::new();
+    let mut files: Vec<Vec<u8>> = Vec::new();
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new().with_mipmaps();
+    let mut dimension_fix: Option<String> = None;
+    let mut pad_color: Option<String> = None;
 
     while let Ok(Some(field)) = body.next_field().await {
         let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
         if let Ok(data) = field.bytes().await {
             match name.as_str() {
                 "presignedUrl" => {
@@ -28,49 +50,219 @@ pub async fn handle(
                         presigned_url = Some(s);
                     }
                 }
-                "index" => {
+                "downloadUrl" => {
                     if let Ok(s) = String::from_utf8(data.to_vec()) {
-                        if let Ok(i) = s.parse::<i32>() {
-                            index = Some(i);
+                        download_url = Some(s);
+                    }
+                }
+                "index" => {
+                    // NOTE: 読み飛ばすと残りの組がずれるので、整数でなければすぐにエラーにする
+                    match std::str::from_utf8(&data)
+                        .ok()
+                        .and_then(|s| s.trim().parse::<i32>().ok())
+                    {
+                        Some(i) => indices.push(i),
+                        None => {
+                            info!("Invalid index: {:?}", String::from_utf8_lossy(&data));
+                            return Ok(
+                                apis::default::UpdateMergedImageResponse::Status400_BadRequest(
+                                    bad_request("index must be an integer"),
+                                ),
+                            );
                         }
                     }
                 }
                 "metadata" => {
+                    // NOTE: 更新時のメタデータは現状利用しない
+                    info!("metadata received: {} bytes", data.len());
+                }
+                "file" => {
+                    info!("file received: {} bytes", data.len());
+                    files.push(data.to_vec());
+                }
+                "encoding" => {
                     if let Ok(s) = String::from_utf8(data.to_vec()) {
-                        metadata = Some(s);
+                        encoding = Some(s);
                     }
                 }
-                "file" => {
-                    _file_data = Some(data.to_vec());
+                "dimensionFix" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        dimension_fix = Some(s);
+                    }
                 }
+                "padColor" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        pad_color = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
                 _ => {
-                    log::warn!("Unknown field: {}", name);
+                    warn!("Unknown field: {}", name);
                 }
             }
+        } else {
+            warn!("Failed to parse body to bytes");
         }
     }
 
-    if presigned_url.is_none() || index.is_none() || metadata.is_none() {
+    let missing_field = if presigned_url.is_none() {
+        Some("presignedUrl is required")
+    } else if download_url.is_none() {
+        Some("downloadUrl is required")
+    } else if indices.is_empty() {
+        Some("index is required")
+    } else if files.is_empty() {
+        Some("file is required")
+    } else if indices.len() != files.len() {
+        Some("index and file must be specified in pairs")
+    } else {
+        None
+    };
+    if let Some(missing_field) = missing_field {
         return Ok(
-            apis::default::UpdateMergedImageResponse::Status400_BadRequest(models::ErrorResponse {
-                message: "Bad Request".to_string(),
-                error_code: "INVALID_INPUT".to_string(),
-                details: None,
-            }),
+            apis::default::UpdateMergedImageResponse::Status400_BadRequest(bad_request(
+                missing_field,
+            )),
         );
     }
 
-    let _presigned_url = presigned_url.unwrap();
-    let _index = index.unwrap();
-    let _file_data = _file_data.unwrap_or_default();
+    let encoding = match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let ConversionFieldValues {
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    } = match conversion_fields.parse() {
+        Ok(conversion) => conversion,
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status400_BadRequest(bad_request(&e)),
+            );
+        }
+    };
+
+    let dimension_fix = match dimension_fix
+        .map(|s| DimensionFix::parse(&s, pad_color.as_deref()))
+        .transpose()
+    {
+        Ok(dimension_fix) => dimension_fix,
+        Err(e) => {
+            info!("Invalid dimensionFix: {}", e);
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let presigned_url = presigned_url.unwrap();
+    let download_url = download_url.unwrap();
+    let options = UpdateMergedImageOptions {
+        encoding,
+        dimension_fix,
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    };
 
-    // TODO
+    // NOTE: 実処理
+    // 1組だけなら従来通り、複数組ならまとめて差し替える
+    let result = if files.len() == 1 {
+        service
+            .execute(
+                &download_url,
+                &presigned_url,
+                indices[0],
+                &files[0],
+                &options,
+            )
+            .await
+            .map(|result| {
+                result.fixed_dimensions.map(|fixed| {
+                    json!({
+                        "fixedDimensions": fixed_dimensions_json(&fixed),
+                    })
+                })
+            })
+    } else {
+        let replacements: Vec<MergedImageReplacement> = indices
+            .into_iter()
+            .zip(files)
+            .map(|(index, image)| MergedImageReplacement { index, image })
+            .collect();
+        service
+            .execute_batch(&download_url, &presigned_url, &replacements, &options)
+            .await
+            .map(|result| {
+                // NOTE: 補正した画像だけを、束ねたファイルの指定枚目と合わせて返す
+                let fixed_dimensions: Vec<Value> = replacements
+                    .iter()
+                    .zip(&result.fixed_dimensions)
+                    .filter_map(|(replacement, fixed)| {
+                        fixed.as_ref().map(|fixed| {
+                            let mut value = fixed_dimensions_json(fixed);
+                            value["index"] = json!(replacement.index);
+                            value
+                        })
+                    })
+                    .collect();
+                (!fixed_dimensions.is_empty())
+                    .then(|| json!({ "fixedDimensions": fixed_dimensions }))
+            })
+    };
+    let data = match result {
+        Ok(data) => data.map(|data| Nullable::from(Object(data))),
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status400_BadRequest(bad_request(&msg)),
+            );
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status400_BadRequest(unsupported_format(
+                    &e,
+                )),
+            );
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::UpdateMergedImageResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
 
     Ok(
         apis::default::UpdateMergedImageResponse::Status200_SuccessfulOperation(
             models::SuccessResponse {
-                message: "success".to_string(),
-                data: None,
+                message: success_message::SUCCESS.to_string(),
+                data,
             },
         ),
     )
@@ -0,0 +1,192 @@
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum_extra::extract::{CookieJar, Host};
+use generated::apis;
+use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
+use http::Method;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::handler::conversion_fields::ConversionFields;
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{AtlasLayout, OutputEncoding};
+use crate::service::{ServiceError, UploadAtlasOptions, UploadAtlasService};
+
+/// 複数の画像をアトラステクスチャに詰め込んでDDS形式に変換し、ストレージにアップロードする
+pub async fn handle(
+    _method: &Method,
+    _host: &Host,
+    _cookies: &CookieJar,
+    mut body: Multipart,
+    service: &dyn UploadAtlasService,
+) -> Result<apis::default::UploadAtlasResponse, ()> {
+    info!("upload_atlas() called");
+
+    let mut presigned_url: Option<String> = None;
+    let mut files: Vec<Vec<u8>> = Vec::new();
+    let mut max_size: Option<String> = None;
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new();
+
+    while let Ok(Some(field)) = body.next_field().await {
+        let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
+        if let Ok(data) = field.bytes().await {
+            match name.as_str() {
+                "presignedUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        presigned_url = Some(s);
+                    }
+                }
+                "files" => {
+                    info!("file received: {} bytes", data.len());
+                    files.push(data.to_vec());
+                }
+                "maxSize" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        max_size = Some(s);
+                    }
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
+                _ => {
+                    warn!("Unknown field: {}", name);
+                }
+            }
+        } else {
+            warn!("Failed to parse body to bytes");
+        }
+    }
+
+    let missing_field = if presigned_url.is_none() {
+        Some("presignedUrl is required")
+    } else if files.is_empty() {
+        Some("files is required")
+    } else {
+        None
+    };
+    if let Some(missing_field) = missing_field {
+        return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+            bad_request(missing_field),
+        ));
+    }
+
+    let mut options = UploadAtlasOptions::default();
+    if let Some(s) = max_size {
+        match s.trim().parse::<u32>() {
+            Ok(max_size) => options.max_size = max_size,
+            Err(_) => {
+                return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+                    bad_request("maxSize must be a positive integer"),
+                ));
+            }
+        }
+    }
+
+    match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => options.encoding = encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+                bad_request(&e.to_string()),
+            ));
+        }
+    }
+
+    match conversion_fields.parse() {
+        Ok(conversion) => {
+            options.format = conversion.format;
+            options.compression = conversion.compression;
+        }
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+                bad_request(&e),
+            ));
+        }
+    }
+
+    let presigned_url = presigned_url.unwrap();
+
+    // NOTE: 実処理
+    let data = match service.execute(&presigned_url, &files, &options).await {
+        Ok(result) => Some(Nullable::from(Object(layout_json(&result.layout)))),
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+                bad_request(&msg),
+            ));
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(apis::default::UploadAtlasResponse::Status400_BadRequest(
+                unsupported_format(&e),
+            ));
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::UploadAtlasResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
+
+    Ok(
+        apis::default::UploadAtlasResponse::Status200_SuccessfulOperation(
+            models::SuccessResponse {
+                message: success_message::SUCCESS.to_string(),
+                data,
+            },
+        ),
+    )
+}
+
+/// 各アトラスのサイズと各画像の位置を返す形式にする
+fn layout_json(layout: &AtlasLayout) -> Value {
+    let atlases: Vec<Value> = layout
+        .sizes
+        .iter()
+        .enumerate()
+        .map(|(index, (width, height))| {
+            json!({
+                "index": index,
+                "width": width,
+                "height": height,
+            })
+        })
+        .collect();
+    let rects: Vec<Value> = layout
+        .rects
+        .iter()
+        .enumerate()
+        .map(|(index, rect)| {
+            json!({
+                "index": index,
+                "atlas": rect.atlas,
+                "x": rect.x,
+                "y": rect.y,
+                "width": rect.width,
+                "height": rect.height,
+            })
+        })
+        .collect();
+    json!({ "atlases": atlases, "rects": rects })
+}
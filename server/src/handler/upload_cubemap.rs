@@ -0,0 +1,154 @@
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum_extra::extract::{CookieJar, Host};
+use generated::apis;
+use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
+use http::Method;
+use log::{info, warn};
+use serde_json::json;
+
+use crate::handler::conversion_fields::ConversionFields;
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::cubemap::CUBEMAP_FACES;
+use crate::model::OutputEncoding;
+use crate::service::{ServiceError, UploadCubemapOptions, UploadCubemapService};
+
+/// 6面の画像からキューブマップのDDSを作成し、ストレージにアップロードする
+pub async fn handle(
+    _method: &Method,
+    _host: &Host,
+    _cookies: &CookieJar,
+    mut body: Multipart,
+    service: &dyn UploadCubemapService,
+) -> Result<apis::default::UploadCubemapResponse, ()> {
+    info!("upload_cubemap() called");
+
+    let mut presigned_url: Option<String> = None;
+    let mut faces: [Option<Vec<u8>>; 6] = Default::default();
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new();
+
+    while let Ok(Some(field)) = body.next_field().await {
+        let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
+        if let Ok(data) = field.bytes().await {
+            match name.as_str() {
+                "presignedUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        presigned_url = Some(s);
+                    }
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
+                _ => match CUBEMAP_FACES.iter().position(|face| *face == name) {
+                    Some(index) => {
+                        info!("face {} received: {} bytes", name, data.len());
+                        faces[index] = Some(data.to_vec());
+                    }
+                    None => {
+                        warn!("Unknown field: {}", name);
+                    }
+                },
+            }
+        } else {
+            warn!("Failed to parse body to bytes");
+        }
+    }
+
+    let missing_field = if presigned_url.is_none() {
+        Some("presignedUrl is required".to_string())
+    } else {
+        CUBEMAP_FACES
+            .iter()
+            .zip(&faces)
+            .find(|(_, data)| data.is_none())
+            .map(|(face, _)| format!("{} is required", face))
+    };
+    if let Some(missing_field) = missing_field {
+        return Ok(apis::default::UploadCubemapResponse::Status400_BadRequest(
+            bad_request(&missing_field),
+        ));
+    }
+
+    let mut options = UploadCubemapOptions::default();
+    match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => options.encoding = encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(apis::default::UploadCubemapResponse::Status400_BadRequest(
+                bad_request(&e.to_string()),
+            ));
+        }
+    }
+
+    match conversion_fields.parse() {
+        Ok(conversion) => {
+            options.format = conversion.format;
+            options.compression = conversion.compression;
+        }
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(apis::default::UploadCubemapResponse::Status400_BadRequest(
+                bad_request(&e),
+            ));
+        }
+    }
+
+    let presigned_url = presigned_url.unwrap();
+    // NOTE: 全ての面が揃っている
+    let faces: Vec<Vec<u8>> = faces.into_iter().flatten().collect();
+
+    // NOTE: 実処理
+    let data = match service.execute(&presigned_url, &faces, &options).await {
+        Ok(result) => Some(Nullable::from(Object(json!({
+            "faceSize": result.header.width,
+            "mipCount": result.header.mip_count,
+            "format": result.header.format.name(),
+        })))),
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(apis::default::UploadCubemapResponse::Status400_BadRequest(
+                bad_request(&msg),
+            ));
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(apis::default::UploadCubemapResponse::Status400_BadRequest(
+                unsupported_format(&e),
+            ));
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::UploadCubemapResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
+
+    Ok(
+        apis::default::UploadCubemapResponse::Status200_SuccessfulOperation(
+            models::SuccessResponse {
+                message: success_message::SUCCESS.to_string(),
+                data,
+            },
+        ),
+    )
+}
@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use axum::extract::Multipart;
+use axum_extra::extract::{CookieJar, Host};
+use generated::apis;
+use generated::models;
+use generated::types::Nullable;
+use generated::types::Object;
+use http::Method;
+use log::{info, warn};
+use serde_json::{json, Value};
+
+use crate::handler::conversion_fields::ConversionFields;
+use crate::handler::messages::{error_code, error_message, success_message};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{FlipbookLayout, OutputEncoding};
+use crate::service::{FlipbookSource, ServiceError, UploadFlipbookOptions, UploadFlipbookService};
+
+/// アニメーション画像または連番画像をスプライトシートにしてDDS形式に変換し、ストレージにアップロードする
+pub async fn handle(
+    _method: &Method,
+    _host: &Host,
+    _cookies: &CookieJar,
+    mut body: Multipart,
+    service: &dyn UploadFlipbookService,
+) -> Result<apis::default::UploadFlipbookResponse, ()> {
+    info!("upload_flipbook() called");
+
+    let mut presigned_url: Option<String> = None;
+    let mut file_data: Option<Vec<u8>> = None;
+    let mut files: Vec<Vec<u8>> = Vec::new();
+    let mut frame_durations: Option<String> = None;
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new();
+
+    while let Ok(Some(field)) = body.next_field().await {
+        let name = field.name().unwrap_or("").to_string();
+        info!("field name: {}", name);
+        if let Ok(data) = field.bytes().await {
+            match name.as_str() {
+                "presignedUrl" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        presigned_url = Some(s);
+                    }
+                }
+                "file" => {
+                    info!("file received: {} bytes", data.len());
+                    file_data = Some(data.to_vec());
+                }
+                "files" => {
+                    info!("file received: {} bytes", data.len());
+                    files.push(data.to_vec());
+                }
+                "frameDurations" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        frame_durations = Some(s);
+                    }
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
+                _ => {
+                    warn!("Unknown field: {}", name);
+                }
+            }
+        } else {
+            warn!("Failed to parse body to bytes");
+        }
+    }
+
+    let missing_field = if presigned_url.is_none() {
+        Some("presignedUrl is required")
+    } else if file_data.is_none() && files.is_empty() {
+        Some("file or files is required")
+    } else if file_data.is_some() && !files.is_empty() {
+        Some("file and files cannot be used together")
+    } else {
+        None
+    };
+    if let Some(missing_field) = missing_field {
+        return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+            bad_request(missing_field),
+        ));
+    }
+
+    let mut options = UploadFlipbookOptions::default();
+    if let Some(s) = frame_durations {
+        match s
+            .split(',')
+            .map(|duration| duration.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(durations) => options.frame_durations = Some(durations),
+            Err(_) => {
+                return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+                    bad_request("frameDurations must be comma-separated non-negative integers"),
+                ));
+            }
+        }
+    }
+
+    match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => options.encoding = encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+                bad_request(&e.to_string()),
+            ));
+        }
+    }
+
+    match conversion_fields.parse() {
+        Ok(conversion) => {
+            options.format = conversion.format;
+            options.compression = conversion.compression;
+        }
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+                bad_request(&e),
+            ));
+        }
+    }
+
+    let presigned_url = presigned_url.unwrap();
+    let source = match &file_data {
+        Some(file_data) => FlipbookSource::Animation(file_data),
+        None => FlipbookSource::Frames(&files),
+    };
+
+    // NOTE: 実処理
+    let data = match service.execute(&presigned_url, source, &options).await {
+        Ok(result) => Some(Nullable::from(Object(layout_json(&result.layout)))),
+        Err(ServiceError::Validation(msg)) => {
+            info!("Validation error: {}", msg);
+            return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+                bad_request(&msg),
+            ));
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(apis::default::UploadFlipbookResponse::Status400_BadRequest(
+                unsupported_format(&e),
+            ));
+        }
+        Err(ServiceError::Infrastructure(e)) => {
+            info!("Infrastructure error: {}", e);
+            let msg: Option<Nullable<Object>> = Some(Nullable::from(
+                Object::from_str(&e.to_string())
+                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            ));
+            return Ok(
+                apis::default::UploadFlipbookResponse::Status500_InternalServerError(
+                    models::ErrorResponse {
+                        message: error_message::INTERNAL_SERVER_ERROR.to_string(),
+                        error_code: error_code::INFRASTRUCTURE_FAILED.to_string(),
+                        details: msg,
+                    },
+                ),
+            );
+        }
+    };
+
+    Ok(
+        apis::default::UploadFlipbookResponse::Status200_SuccessfulOperation(
+            models::SuccessResponse {
+                message: success_message::SUCCESS.to_string(),
+                data,
+            },
+        ),
+    )
+}
+
+/// 格子の配置と各フレームの表示時間を返す形式にする
+fn layout_json(layout: &FlipbookLayout) -> Value {
+    json!({
+        "frameCount": layout.frame_count,
+        "columns": layout.columns,
+        "rows": layout.rows,
+        "frameWidth": layout.frame_width,
+        "frameHeight": layout.frame_height,
+        "cellWidth": layout.cell_width,
+        "cellHeight": layout.cell_height,
+        "sheetWidth": layout.sheet_width(),
+        "sheetHeight": layout.sheet_height(),
+        "durations": layout.durations,
+    })
+}
@@ -6,10 +6,15 @@ use generated::types::Nullable;
 use generated::types::Object;
 use http::Method;
 use log::{info, warn};
+use serde_json::{json, Value};
 use std::str::FromStr;
 
+use crate::handler::conversion_fields::{ConversionFieldValues, ConversionFields};
+use crate::handler::dimension_fix::fixed_dimensions_json;
 use crate::handler::messages::{error_code, error_message, success_message};
-use crate::service::{ServiceError, UploadSingleImageService};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{DimensionFix, OutputEncoding};
+use crate::service::{ServiceError, UploadSingleImageOptions, UploadSingleImageService};
 
 /// １枚の画像をDDS形式に変換し、ストレージにアップロードする
 pub async fn handle(
@@ -23,6 +28,10 @@ pub async fn handle(
 
     let mut presigned_url: Option<String> = None;
     let mut file_data: Option<Vec<u8>> = None;
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new().with_mipmaps();
+    let mut dimension_fix: Option<String> = None;
+    let mut pad_color: Option<String> = None;
 
     while let Ok(Some(field)) = body.next_field().await {
         let name = field.name().unwrap_or("").to_string();
@@ -38,6 +47,22 @@ pub async fn handle(
                     info!("file: {}", data.len());
                     file_data = Some(data.to_vec());
                 }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                "dimensionFix" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        dimension_fix = Some(s);
+                    }
+                }
+                "padColor" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        pad_color = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
                 _ => {
                     warn!("Unknown field: {}", name);
                 }
@@ -57,24 +82,77 @@ pub async fn handle(
         ));
     }
 
+    let encoding = match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(apis::default::UploadImageResponse::Status400_BadRequest(
+                bad_request(&e.to_string()),
+            ));
+        }
+    };
+
+    let ConversionFieldValues {
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    } = match conversion_fields.parse() {
+        Ok(conversion) => conversion,
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(apis::default::UploadImageResponse::Status400_BadRequest(
+                bad_request(&e),
+            ));
+        }
+    };
+
+    let dimension_fix = match dimension_fix
+        .map(|s| DimensionFix::parse(&s, pad_color.as_deref()))
+        .transpose()
+    {
+        Ok(dimension_fix) => dimension_fix,
+        Err(e) => {
+            info!("Invalid dimensionFix: {}", e);
+            return Ok(apis::default::UploadImageResponse::Status400_BadRequest(
+                bad_request(&e.to_string()),
+            ));
+        }
+    };
+
     let presigned_url = presigned_url.unwrap();
     let file_data = file_data.unwrap();
+    let options = UploadSingleImageOptions {
+        encoding,
+        dimension_fix,
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    };
 
     // NOTE: 実処理
-    match service.execute(&presigned_url, &file_data).await {
-        Ok(_) => {}
+    let data = match service.execute(&presigned_url, &file_data, &options).await {
+        Ok(result) => {
+            let mut data = serde_json::Map::new();
+            if let Some(fixed) = &result.fixed_dimensions {
+                data.insert("fixedDimensions".to_string(), fixed_dimensions_json(fixed));
+            }
+            if let Some(mip_count) = result.mip_count {
+                data.insert("mipCount".to_string(), json!(mip_count));
+            }
+            (!data.is_empty()).then(|| Nullable::from(Object(Value::Object(data))))
+        }
         Err(ServiceError::Validation(msg)) => {
             info!("Validation error: {}", msg);
-            let msg: Option<Nullable<Object>> = Some(Nullable::from(
-                Object::from_str(&msg)
-                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
+            return Ok(apis::default::UploadImageResponse::Status400_BadRequest(
+                bad_request(&msg),
             ));
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
             return Ok(apis::default::UploadImageResponse::Status400_BadRequest(
-                models::ErrorResponse {
-                    message: error_message::BAD_REQUEST.to_string(),
-                    error_code: error_code::INVALID_INPUT.to_string(),
-                    details: msg,
-                },
+                unsupported_format(&e),
             ));
         }
         Err(ServiceError::Infrastructure(e)) => {
@@ -93,13 +171,13 @@ pub async fn handle(
                 ),
             );
         }
-    }
+    };
 
     Ok(
         apis::default::UploadImageResponse::Status200_SuccessfulOperation(
             models::SuccessResponse {
                 message: success_message::SUCCESS.to_string(),
-                data: None,
+                data,
             },
         ),
     )
@@ -8,9 +8,17 @@ use generated::types::Nullable;
 use generated::types::Object;
 use http::Method;
 use log::{info, warn};
+use serde_json::{json, Value};
 
+use crate::handler::conversion_fields::{ConversionFieldValues, ConversionFields};
+use crate::handler::dimension_fix::fixed_dimensions_json_of;
 use crate::handler::messages::{error_code, error_message, success_message};
-use crate::service::{ServiceError, UploadMergedImageService};
+use crate::handler::responses::{bad_request, unsupported_format};
+use crate::model::{DimensionFix, FormatVersion, ImageMetadata, OutputEncoding};
+use crate::service::{
+    MergedFileChecksums, ServiceError, UploadMergedImageOptions, UploadMergedImageResult,
+    UploadMergedImageService, UploadSplitMergedImageResult,
+};
 
 /// 複数枚の画像をDDS形式に変換し、1ファイルにまとめ、ストレージにアップロードする
 pub async fn handle(
@@ -23,7 +31,19 @@ pub async fn handle(
     info!("upload_merged_image() called");
 
     let mut presigned_url: Option<String> = None;
+    let mut presigned_urls: Vec<String> = Vec::new();
     let mut files: Vec<Vec<u8>> = Vec::new();
+    let mut format_version: Option<String> = None;
+    let mut descriptors = false;
+    let mut checksums = false;
+    let mut dedup = false;
+    let mut texture_array = false;
+    let mut downscale_to_fit = false;
+    let mut metadata: Option<String> = None;
+    let mut encoding: Option<String> = None;
+    let mut conversion_fields = ConversionFields::new().with_mipmaps();
+    let mut dimension_fix: Option<String> = None;
+    let mut pad_color: Option<String> = None;
 
     while let Ok(Some(field)) = body.next_field().await {
         let name = field.name().unwrap_or("").to_string();
@@ -35,10 +55,66 @@ pub async fn handle(
                         presigned_url = Some(s);
                     }
                 }
+                "presignedUrls" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        presigned_urls.push(s);
+                    }
+                }
                 "files" => {
                     info!("file received: {} bytes", data.len());
                     files.push(data.to_vec());
                 }
+                "formatVersion" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        format_version = Some(s);
+                    }
+                }
+                "metadata" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        metadata = Some(s);
+                    }
+                }
+                "descriptors" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        descriptors = s.trim().eq_ignore_ascii_case("true");
+                    }
+                }
+                "checksums" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        checksums = s.trim().eq_ignore_ascii_case("true");
+                    }
+                }
+                "dedup" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        dedup = s.trim().eq_ignore_ascii_case("true");
+                    }
+                }
+                "textureArray" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        texture_array = s.trim().eq_ignore_ascii_case("true");
+                    }
+                }
+                "downscaleToFit" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        downscale_to_fit = s.trim().eq_ignore_ascii_case("true");
+                    }
+                }
+                "encoding" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        encoding = Some(s);
+                    }
+                }
+                "dimensionFix" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        dimension_fix = Some(s);
+                    }
+                }
+                "padColor" => {
+                    if let Ok(s) = String::from_utf8(data.to_vec()) {
+                        pad_color = Some(s);
+                    }
+                }
+                _ if conversion_fields.accept(&name, &data) => {}
                 _ => {
                     warn!("Unknown field: {}", name);
                 }
@@ -48,45 +124,139 @@ pub async fn handle(
         }
     }
 
-    if presigned_url.is_none() {
+    let missing_url = match (&presigned_url, presigned_urls.is_empty()) {
+        (None, true) => Some("presignedUrl or presignedUrls is required"),
+        (Some(_), false) => Some("presignedUrl and presignedUrls cannot be used together"),
+        _ => None,
+    };
+    if let Some(missing_url) = missing_url {
         return Ok(
-            apis::default::UploadMergedImageResponse::Status400_BadRequest(models::ErrorResponse {
-                message: error_message::BAD_REQUEST.to_string(),
-                error_code: error_code::INVALID_INPUT.to_string(),
-                details: Some(Nullable::from(Object::from_str("presignedUrl is required").unwrap())),
-            }),
+            apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                missing_url,
+            )),
         );
     }
 
     if files.is_empty() {
         return Ok(
-            apis::default::UploadMergedImageResponse::Status400_BadRequest(models::ErrorResponse {
-                message: error_message::BAD_REQUEST.to_string(),
-                error_code: error_code::INVALID_INPUT.to_string(),
-                details: Some(Nullable::from(Object::from_str("files are required").unwrap())),
-            }),
+            apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                "files are required",
+            )),
         );
     }
 
-    let presigned_url = presigned_url.unwrap();
+    let format_version = match format_version {
+        None => FormatVersion::default(),
+        Some(s) => match s.trim().parse::<i32>().map(FormatVersion::try_from) {
+            Ok(Ok(version)) => version,
+            _ => {
+                return Ok(
+                    apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                        "formatVersion must be 1 or 2",
+                    )),
+                );
+            }
+        },
+    };
+
+    let metadata = match metadata.map(|s| ImageMetadata::from_str(&s)).transpose() {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            info!("Invalid metadata: {}", e);
+            return Ok(
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let encoding = match encoding.map(|s| OutputEncoding::from_str(&s)).transpose() {
+        Ok(encoding) => encoding.unwrap_or_default(),
+        Err(e) => {
+            info!("Invalid encoding: {}", e);
+            return Ok(
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let ConversionFieldValues {
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    } = match conversion_fields.parse() {
+        Ok(conversion) => conversion,
+        Err(e) => {
+            info!("Invalid conversion options: {}", e);
+            return Ok(
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(&e)),
+            );
+        }
+    };
+
+    let dimension_fix = match dimension_fix
+        .map(|s| DimensionFix::parse(&s, pad_color.as_deref()))
+        .transpose()
+    {
+        Ok(dimension_fix) => dimension_fix,
+        Err(e) => {
+            info!("Invalid dimensionFix: {}", e);
+            return Ok(
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(
+                    &e.to_string(),
+                )),
+            );
+        }
+    };
+
+    let options = UploadMergedImageOptions {
+        format_version,
+        descriptors,
+        metadata,
+        checksums,
+        dedup,
+        texture_array,
+        encoding,
+        downscale_to_fit,
+        dimension_fix,
+        format,
+        compression,
+        mipmaps,
+        flip_y,
+    };
 
     // NOTE: 実処理
-    match service.execute(&presigned_url, &files).await {
-        Ok(_) => {}
+    // presignedUrls が指定された場合は 10 MB に収まるように複数ファイルに分割する
+    let result = match presigned_url {
+        Some(presigned_url) => {
+            service
+                .execute(&presigned_url, &files, &options)
+                .await
+                .map(|result| merged_result_json(&result))
+        }
+        None => service
+            .execute_split(&presigned_urls, &files, &options)
+            .await
+            .map(|result| Some(split_result_json(&result))),
+    };
+    let data = match result {
+        Ok(data) => data.map(|data| Nullable::from(Object(data))),
         Err(ServiceError::Validation(msg)) => {
             info!("Validation error: {}", msg);
-            let msg: Option<Nullable<Object>> = Some(Nullable::from(
-                Object::from_str(&msg)
-                    .unwrap_or(Object::from_str("failed to parse message").unwrap()),
-            ));
             return Ok(
-                apis::default::UploadMergedImageResponse::Status400_BadRequest(
-                    models::ErrorResponse {
-                        message: error_message::BAD_REQUEST.to_string(),
-                        error_code: error_code::INVALID_INPUT.to_string(),
-                        details: msg,
-                    },
-                ),
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(bad_request(&msg)),
+            );
+        }
+        Err(ServiceError::UnsupportedFormat(e)) => {
+            info!("Unsupported format: {}", e);
+            return Ok(
+                apis::default::UploadMergedImageResponse::Status400_BadRequest(unsupported_format(
+                    &e,
+                )),
             );
         }
         Err(ServiceError::Infrastructure(e)) => {
@@ -105,14 +275,112 @@ pub async fn handle(
                 ),
             );
         }
-    }
+    };
 
     Ok(
         apis::default::UploadMergedImageResponse::Status200_SuccessfulOperation(
             models::SuccessResponse {
                 message: success_message::SUCCESS.to_string(),
-                data: None,
+                data,
             },
         ),
     )
 }
+
+/// チェックサムを Udon 側での検証用に返す形式にする
+fn checksums_json(checksums: &MergedFileChecksums) -> Value {
+    json!({
+        "entries": checksums.entries,
+        "file": checksums.file,
+    })
+}
+
+/// チェックサムや縮小、補正、テクスチャ配列、ミップマップの結果を返す形式にする（返すものがなければ None）
+fn merged_result_json(result: &UploadMergedImageResult) -> Option<Value> {
+    let mut data = serde_json::Map::new();
+    if let Some(checksums) = &result.checksums {
+        data.insert("checksums".to_string(), checksums_json(checksums));
+    }
+    if let Some(dimensions) = &result.dimensions {
+        let dimensions = dimensions
+            .iter()
+            .map(|entry| {
+                json!({
+                    "width": entry.width,
+                    "height": entry.height,
+                    "originalWidth": entry.original_width,
+                    "originalHeight": entry.original_height,
+                    "downscaled": entry.is_downscaled(),
+                })
+            })
+            .collect();
+        data.insert("dimensions".to_string(), Value::Array(dimensions));
+    }
+    if let Some(fixed_dimensions) = fixed_dimensions_json_of(&result.fixed_dimensions) {
+        data.insert("fixedDimensions".to_string(), fixed_dimensions);
+    }
+    if let Some(header) = &result.texture_array {
+        data.insert(
+            "textureArray".to_string(),
+            json!({
+                "width": header.width,
+                "height": header.height,
+                "arraySize": header.array_size,
+                "mipCount": header.mip_count,
+                "format": header.format.name(),
+            }),
+        );
+    }
+    if let Some(mip_counts) = &result.mip_counts {
+        data.insert("mipCounts".to_string(), json!(mip_counts));
+    }
+    (!data.is_empty()).then_some(Value::Object(data))
+}
+
+/// 分割結果を、どの画像がどのファイルの何枚目に入ったかが分かる形式にする
+fn split_result_json(result: &UploadSplitMergedImageResult) -> Value {
+    let files: Vec<Value> = result
+        .files
+        .iter()
+        .enumerate()
+        .map(|(file_index, file)| {
+            let mut value = json!({
+                "presignedUrlIndex": file_index,
+                "indices": file.range.clone().collect::<Vec<_>>(),
+            });
+            if let Some(checksums) = &file.checksums {
+                value["checksums"] = checksums_json(checksums);
+            }
+            value
+        })
+        .collect();
+    let placements: Vec<Value> = result
+        .files
+        .iter()
+        .enumerate()
+        .flat_map(|(file_index, file)| {
+            file.range
+                .clone()
+                .enumerate()
+                .map(move |(position, index)| {
+                    json!({
+                        "index": index,
+                        "file": file_index,
+                        "position": position,
+                    })
+                })
+        })
+        .collect();
+
+    let mut data = json!({
+        "files": files,
+        "placements": placements,
+    });
+    if let Some(fixed_dimensions) = fixed_dimensions_json_of(&result.fixed_dimensions) {
+        data["fixedDimensions"] = fixed_dimensions;
+    }
+    if let Some(mip_counts) = &result.mip_counts {
+        data["mipCounts"] = json!(mip_counts);
+    }
+    data
+}
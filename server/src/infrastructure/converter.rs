@@ -1,8 +1,8 @@
 use std::io::Write;
 
 use async_trait::async_trait;
+use image::ImageFormat;
 use log::info;
-use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tempfile::Builder;
@@ -10,27 +10,104 @@ use tokio::fs;
 use tokio::process::Command;
 
 use crate::infrastructure::error::{InfrastructureError, InfrastructureResult};
+use crate::model::dds::PixelFormat;
+use crate::model::{ColorSpace, ConversionOptions, DdsFormat, DdsHeader};
+
+/// 起動時の確認で変換する同梱のテスト画像（8x8 の png）
+const SELF_CHECK_IMAGE: &[u8] = include_bytes!("../../resources/self_check.png");
 
 #[async_trait]
 pub trait Converter: Send + Sync {
-    async fn jpeg_to_dds(&self, image: &[u8]) -> InfrastructureResult<Vec<u8>>;
-    async fn convert(&self, input_path: &Path, output_path: &Path) -> InfrastructureResult<()>;
+    /// `options.mipmaps` を指定しなければミップマップなし（1 段だけ）にする
+    async fn jpeg_to_dds(
+        &self,
+        image: &[u8],
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<Vec<u8>>;
+    async fn convert(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<()>;
+
+    /// 同梱のテスト画像を変換できるか確認する（起動時に使う）
+    async fn self_check(&self) -> InfrastructureResult<()> {
+        check_conversion(self).await
+    }
+}
+
+/// 同梱のテスト画像を変換し、8x8 の DXT1 のDDSになるか確認する
+async fn check_conversion<C: Converter + ?Sized>(converter: &C) -> InfrastructureResult<()> {
+    let dds_data = converter
+        .jpeg_to_dds(SELF_CHECK_IMAGE, &ConversionOptions::default())
+        .await?;
+    let header = DdsHeader::parse(&dds_data).map_err(|e| {
+        InfrastructureError::Converter(format!("self-check output is not dds: {}", e))
+    })?;
+    if (header.width, header.height, header.format) != (8, 8, PixelFormat::Dxt1) {
+        return Err(InfrastructureError::Converter(format!(
+            "self-check output is unexpected ({}x{} {})",
+            header.width,
+            header.height,
+            header.format.name()
+        )));
+    }
+    Ok(())
 }
 
-pub struct DefaultConverter;
+/// crunch で変換するコンバーター
+pub struct DefaultConverter {
+    crunch_path: PathBuf,
+}
 
 impl DefaultConverter {
-    pub fn new() -> Self {
-        Self
+    /// `crunch_path` は実行時の作業ディレクトリからの相対パスか絶対パス
+    pub fn new(crunch_path: impl Into<PathBuf>) -> Self {
+        Self {
+            crunch_path: crunch_path.into(),
+        }
+    }
+
+    /// crunch が存在し、実行できるか確認する
+    fn check_crunch(&self) -> InfrastructureResult<()> {
+        let metadata = std::fs::metadata(&self.crunch_path).map_err(|_| {
+            InfrastructureError::Converter(format!(
+                "crunch command not found: {}",
+                self.crunch_path.display()
+            ))
+        })?;
+        if !metadata.is_file() {
+            return Err(InfrastructureError::Converter(format!(
+                "crunch command is not a file: {}",
+                self.crunch_path.display()
+            )));
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if metadata.permissions().mode() & 0o111 == 0 {
+                return Err(InfrastructureError::Converter(format!(
+                    "crunch command is not executable: {}",
+                    self.crunch_path.display()
+                )));
+            }
+        }
+        Ok(())
     }
 }
 
 #[async_trait]
 impl Converter for DefaultConverter {
-    async fn jpeg_to_dds(&self, image: &[u8]) -> InfrastructureResult<Vec<u8>> {
+    async fn jpeg_to_dds(
+        &self,
+        image: &[u8],
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<Vec<u8>> {
         info!(
-            "Converting image to DDS format (size: {} bytes)",
-            image.len()
+            "Converting image to DDS format (size: {} bytes, options: {:?})",
+            image.len(),
+            options
         );
 
         if image.is_empty() {
@@ -40,15 +117,20 @@ impl Converter for DefaultConverter {
         }
 
         // NOTE: 入力用一時ファイル作成
+        // 縮小などで PNG にエンコードし直した画像も渡されるため、中身に合わせた拡張子にする
+        let suffix = match image::guess_format(image) {
+            Ok(ImageFormat::Png) => ".png",
+            _ => ".jpeg",
+        };
         let temp_input_file = Builder::new()
             .prefix("temp_")
-            .suffix(".jpeg")
+            .suffix(suffix)
             .tempfile()
-            .map_err(|e| InfrastructureError::Io(e))?;
+            .map_err(InfrastructureError::Io)?;
         temp_input_file
             .as_file()
             .write_all(image)
-            .map_err(|e| InfrastructureError::Io(e))?;
+            .map_err(InfrastructureError::Io)?;
         let input_file_path = temp_input_file.path();
 
         // NOTE: 出力用一時ファイル作成
@@ -56,45 +138,66 @@ impl Converter for DefaultConverter {
             .prefix("converted_")
             .suffix(".dds")
             .tempfile()
-            .map_err(|e| InfrastructureError::Io(e))?;
+            .map_err(InfrastructureError::Io)?;
         let output_file_path = temp_output_file.path();
 
-        self.convert(input_file_path, output_file_path).await?;
+        self.convert(input_file_path, output_file_path, options)
+            .await?;
 
         // NOTE: 出力用一時ファイルからデータを読み込む
         let dds_data = fs::read(output_file_path)
             .await
-            .map_err(|e| InfrastructureError::Io(e))?;
+            .map_err(InfrastructureError::Io)?;
 
         Ok(dds_data)
     }
 
-    async fn convert(&self, input_path: &Path, output_path: &Path) -> InfrastructureResult<()> {
-        let crunch_path: PathBuf =
-            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("resources/bin/crunch");
+    async fn convert(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<()> {
+        self.check_crunch()?;
 
-        if !crunch_path.exists() {
-            return Err(InfrastructureError::Converter(format!(
-                "crunch command not found: {}",
-                crunch_path.display()
-            )));
+        // NOTE: crunch は既定で入力に合わせてミップマップを生成するので、生成するかどうかを明示する
+        let mut command = Command::new(&self.crunch_path);
+        match &options.mipmaps {
+            Some(mipmaps) => command
+                .arg("-mipMode")
+                .arg("Generate")
+                .arg("-mipFilter")
+                .arg(mipmaps.filter.name())
+                .arg("-mipSmallest")
+                .arg(mipmaps.min_size.to_string()),
+            None => command.arg("-mipMode").arg("None"),
+        };
+        // NOTE: crunch は既定で人の目に合わせた誤差で圧縮するので、linear なら RGB を均等に扱う
+        if options.color_space == ColorSpace::Linear {
+            command.arg("-uniformMetrics");
+        }
+        if options.dithering {
+            command.arg("-dither");
         }
 
-        let output = Command::new(crunch_path)
+        let output = command
             .arg("-file")
             .arg(input_path)
             .arg("-fileformat")
             .arg("dds")
-            .arg("-dxt1")
+            .arg(match options.format {
+                DdsFormat::Dxt1 => "-dxt1",
+                DdsFormat::Dxt5 => "-dxt5",
+            })
             .arg("-quality")
-            .arg("255")
+            .arg(options.quality.to_string())
             .arg("-out")
             .arg(output_path)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .output()
             .await
-            .map_err(|e| InfrastructureError::from(e))?;
+            .map_err(InfrastructureError::from)?;
 
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -106,26 +209,80 @@ impl Converter for DefaultConverter {
 
         Ok(())
     }
+
+    async fn self_check(&self) -> InfrastructureResult<()> {
+        self.check_crunch()?;
+        check_conversion(self).await
+    }
+}
+
+#[cfg(test)]
+pub mod test_util {
+    use super::DefaultConverter;
+
+    /// テストは server ディレクトリで実行される
+    pub const CRUNCH_PATH: &str = "resources/bin/crunch";
+
+    /// ビルドした crunch のコンバーター
+    ///
+    /// crunch が必要なテストは `#[ignore]` にして、`task test` で `task gen:crunch` の後に `--ignored` で実行する
+    pub fn crunch_converter() -> DefaultConverter {
+        DefaultConverter::new(CRUNCH_PATH)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::test_util::{crunch_converter, CRUNCH_PATH};
     use super::{Converter, DefaultConverter};
+    use crate::model::ConversionOptions;
     use tokio::fs;
 
     #[tokio::test]
     async fn 画像が空ならエラーを返す() {
-        let converter = DefaultConverter::new();
-        let result = converter.jpeg_to_dds(&[]).await;
+        let converter = DefaultConverter::new(CRUNCH_PATH);
+        let result = converter
+            .jpeg_to_dds(&[], &ConversionOptions::default())
+            .await;
         assert!(result.is_err());
     }
 
     #[tokio::test]
+    #[ignore = "requires crunch (task gen:crunch)"]
     async fn 入力画像が存在する場合に成功を返す() {
-        let converter = DefaultConverter::new();
+        let converter = crunch_converter();
         let input = fs::read("resources/4_multiple_size.jpg").await.unwrap();
-        let result = converter.jpeg_to_dds(&input).await;
+        let result = converter
+            .jpeg_to_dds(&input, &ConversionOptions::default())
+            .await;
         assert!(result.is_ok());
         assert!(!result.unwrap().is_empty());
     }
+
+    #[tokio::test]
+    async fn crunchが存在しなければ起動時の確認でエラーを返す() {
+        let converter = DefaultConverter::new("resources/bin/not_found");
+        let e = converter.self_check().await.unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "converter error: crunch command not found: resources/bin/not_found"
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn 実行できないファイルなら起動時の確認でエラーを返す() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let converter = DefaultConverter::new(file.path());
+        let e = converter.self_check().await.unwrap_err();
+        assert!(e.to_string().contains("crunch command is not executable"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn ddsを出力しないコマンドなら起動時の確認でエラーを返す() {
+        // NOTE: true は何も出力せずに成功するので、出力ファイルが空になる
+        let converter = DefaultConverter::new("/bin/true");
+        assert!(converter.self_check().await.is_err());
+    }
 }
@@ -8,6 +8,8 @@ pub enum InfrastructureError {
     Converter(String),
     #[error("storage error: {0}")]
     Storage(String),
+    #[error("downloaded file is too large (limit: {limit} bytes)")]
+    DownloadTooLarge { limit: usize },
 }
 
 pub type InfrastructureResult<T> = Result<T, InfrastructureError>;
@@ -1,7 +1,9 @@
 mod converter;
 pub mod error;
+mod native_converter;
 mod storage;
 
 pub use converter::{Converter, DefaultConverter};
+pub use native_converter::NativeConverter;
 pub use error::InfrastructureError;
 pub use storage::{DefaultStorage, Storage};
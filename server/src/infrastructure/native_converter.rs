@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use log::info;
+use std::path::Path;
+use tokio::fs;
+
+use crate::infrastructure::error::{InfrastructureError, InfrastructureResult};
+use crate::infrastructure::Converter;
+use crate::model::block_compression::compress_to_dds;
+use crate::model::ConversionOptions;
+
+/// crunch を使わずに、デコードした画像からプロセス内で DXT1 (BC1) / DXT5 (BC3) に圧縮するコンバーター
+pub struct NativeConverter;
+
+impl NativeConverter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl Converter for NativeConverter {
+    async fn jpeg_to_dds(
+        &self,
+        image: &[u8],
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<Vec<u8>> {
+        info!(
+            "Converting image to DDS format in process (size: {} bytes, options: {:?})",
+            image.len(),
+            options
+        );
+
+        if image.is_empty() {
+            return Err(InfrastructureError::Converter(
+                "input image is empty".to_string(),
+            ));
+        }
+
+        let decoded = image::load_from_memory(image)
+            .map_err(|e| InfrastructureError::Converter(format!("failed to decode image: {}", e)))?
+            .to_rgba8();
+
+        // NOTE: 圧縮は CPU を使い続けるので、非同期のワーカーを塞がないように別スレッドで行う
+        let options = *options;
+        tokio::task::spawn_blocking(move || compress_to_dds(&decoded, &options))
+            .await
+            .map_err(|e| InfrastructureError::Converter(format!("failed to compress image: {}", e)))
+    }
+
+    async fn convert(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<()> {
+        let image = fs::read(input_path)
+            .await
+            .map_err(InfrastructureError::Io)?;
+        let dds_data = self.jpeg_to_dds(&image, options).await?;
+        fs::write(output_path, dds_data)
+            .await
+            .map_err(InfrastructureError::Io)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NativeConverter;
+    use crate::infrastructure::converter::test_util::crunch_converter;
+    use crate::infrastructure::Converter;
+    use crate::model::block_compression::test_util::{decode_dds, psnr};
+    use crate::model::{ConversionOptions, DdsFormat, DdsHeader};
+    use tokio::fs;
+
+    #[tokio::test]
+    async fn 画像が空ならエラーを返す() {
+        let converter = NativeConverter::new();
+        let result = converter
+            .jpeg_to_dds(&[], &ConversionOptions::default())
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn 画像としてデコードできなければエラーを返す() {
+        let converter = NativeConverter::new();
+        let result = converter
+            .jpeg_to_dds(&[0, 1, 2, 3], &ConversionOptions::default())
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn 入力画像をdxt1のddsに変換できる() {
+        let converter = NativeConverter::new();
+        let input = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let original = image::load_from_memory(&input).unwrap().to_rgba8();
+
+        let dds_data = converter
+            .jpeg_to_dds(&input, &ConversionOptions::default())
+            .await
+            .unwrap();
+        let header = DdsHeader::parse(&dds_data).unwrap();
+        assert_eq!((header.width, header.height), original.dimensions());
+        assert_eq!(header.mip_count, 1);
+
+        let decoded = decode_dds(&dds_data, header.width, header.height, DdsFormat::Dxt1);
+        assert!(psnr(&original, &decoded) > 30.0);
+    }
+
+    #[tokio::test]
+    async fn 起動時の確認で同梱のテスト画像を変換できる() {
+        let converter = NativeConverter::new();
+        assert!(converter.self_check().await.is_ok());
+    }
+
+    #[tokio::test]
+    #[ignore = "requires crunch (task gen:crunch)"]
+    async fn crunchと同等以上の品質で変換できる() {
+        let input = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let original = image::load_from_memory(&input).unwrap().to_rgba8();
+        let (width, height) = original.dimensions();
+        let options = ConversionOptions::default();
+
+        let native = NativeConverter::new()
+            .jpeg_to_dds(&input, &options)
+            .await
+            .unwrap();
+        let crunch = crunch_converter()
+            .jpeg_to_dds(&input, &options)
+            .await
+            .unwrap();
+        let native_psnr = psnr(
+            &original,
+            &decode_dds(&native, width, height, DdsFormat::Dxt1),
+        );
+        let crunch_psnr = psnr(
+            &original,
+            &decode_dds(&crunch, width, height, DdsFormat::Dxt1),
+        );
+
+        // NOTE: crunch より 1 dB 以上悪くなければ同等とみなす
+        assert!(
+            native_psnr >= crunch_psnr - 1.0,
+            "native: {:.2} dB, crunch: {:.2} dB",
+            native_psnr,
+            crunch_psnr
+        );
+    }
+}
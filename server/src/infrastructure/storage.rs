@@ -8,6 +8,8 @@ use crate::infrastructure::error::{InfrastructureError, InfrastructureResult};
 #[async_trait]
 pub trait Storage: Send + Sync {
     async fn upload_file(&self, presigned_url: &str, file_data: &[u8]) -> InfrastructureResult<()>;
+    /// `max_size` バイトを超えるファイルは読み込まずにエラーを返す
+    async fn download_file(&self, url: &str, max_size: usize) -> InfrastructureResult<Vec<u8>>;
 }
 
 pub struct DefaultStorage;
@@ -55,6 +57,51 @@ impl Storage for DefaultStorage {
         info!("Upload succeeded");
         Ok(())
     }
+
+    async fn download_file(&self, url: &str, max_size: usize) -> InfrastructureResult<Vec<u8>> {
+        info!("Downloading file from storage (url: {})", url);
+
+        if url.trim().is_empty() {
+            return Err(InfrastructureError::Storage(
+                "download url is missing".to_string(),
+            ));
+        }
+
+        let client = Client::new();
+        let response = client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| InfrastructureError::Storage(format!("failed to send request: {e}")))?;
+
+        let mut response = response
+            .error_for_status()
+            .map_err(|e| InfrastructureError::Storage(format!("download failed: {e}")))?;
+
+        // NOTE: ダウンロード先はクライアントが指定するので、大きすぎるファイルでメモリを使い切らないように上限を設ける
+        if response
+            .content_length()
+            .is_some_and(|length| length > max_size as u64)
+        {
+            return Err(InfrastructureError::DownloadTooLarge { limit: max_size });
+        }
+
+        // Content-Length がない・偽っている場合に備えて、読み込みながら上限を確認する
+        let mut body = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| InfrastructureError::Storage(format!("failed to read body: {e}")))?
+        {
+            if body.len() + chunk.len() > max_size {
+                return Err(InfrastructureError::DownloadTooLarge { limit: max_size });
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        info!("Download succeeded (size: {} bytes)", body.len());
+        Ok(body)
+    }
 }
 
 #[cfg(test)]
@@ -103,4 +150,73 @@ mod tests {
         let result = storage.upload_file(&presigned_url, &file_data).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn ダウンロード用urlが空ならエラーを返す() {
+        let storage = DefaultStorage::new();
+        let result = storage.download_file("", usize::MAX).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn ダウンロードに成功したらファイルの中身を返す() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/download"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![1, 2, 3]))
+            .mount(&mock_server)
+            .await;
+
+        let url = format!("{}/download", mock_server.uri());
+        let storage = DefaultStorage::new();
+        let result = storage.download_file(&url, usize::MAX).await;
+        assert_eq!(result.unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn ファイルが上限より大きいならエラーを返す() {
+        use crate::infrastructure::InfrastructureError;
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/download"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0; 1024]))
+            .mount(&mock_server)
+            .await;
+
+        let url = format!("{}/download", mock_server.uri());
+        let storage = DefaultStorage::new();
+        let result = storage.download_file(&url, 1023).await;
+        assert!(matches!(
+            result,
+            Err(InfrastructureError::DownloadTooLarge { limit: 1023 })
+        ));
+
+        // 上限ちょうどなら読み込める
+        let result = storage.download_file(&url, 1024).await;
+        assert_eq!(result.unwrap().len(), 1024);
+    }
+
+    #[tokio::test]
+    async fn ダウンロード先がエラーを返したならエラーを返す() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/download"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let url = format!("{}/download", mock_server.uri());
+        let storage = DefaultStorage::new();
+        let result = storage.download_file(&url, usize::MAX).await;
+        assert!(result.is_err());
+    }
 }
@@ -10,6 +10,7 @@ use tower_http::cors::CorsLayer;
 
 mod handler;
 mod infrastructure;
+#[cfg(test)]
 mod mock;
 mod model;
 mod service;
@@ -19,16 +20,119 @@ async fn main() {
     dotenv().expect(".env file not found");
     env_logger::init();
 
-    let converter = Arc::new(infrastructure::DefaultConverter::new());
+    // 入力を許可する画像形式（カンマ区切り、デフォルト: jpeg,png）
+    let allowed_formats = env::var("API_SERVER_ALLOWED_FORMATS")
+        .unwrap_or_else(|_| "jpeg,png".to_string())
+        .parse::<model::AllowedFormats>()
+        .expect("Invalid image format for API_SERVER_ALLOWED_FORMATS");
+    info!(
+        "Allowed image formats: {}",
+        allowed_formats.names().join(", ")
+    );
+    // スプライトシートのアニメーションとして入力を許可する画像形式（カンマ区切り、デフォルト: gif,png）
+    let allowed_animation_formats = env::var("API_SERVER_ALLOWED_ANIMATION_FORMATS")
+        .unwrap_or_else(|_| "gif,png".to_string())
+        .parse::<model::AllowedFormats>()
+        .expect("Invalid image format for API_SERVER_ALLOWED_ANIMATION_FORMATS");
+    info!(
+        "Allowed animation formats: {}",
+        allowed_animation_formats.names().join(", ")
+    );
+    let conversion_policy = model::ConversionPolicy::parse(
+        &env::var("API_SERVER_ALLOWED_TEXTURE_FORMATS").unwrap_or_else(|_| "dxt1,dxt5".to_string()),
+        &env::var("API_SERVER_ALLOWED_QUALITY").unwrap_or_else(|_| "0-255".to_string()),
+        &env::var("API_SERVER_ALLOWED_COLOR_SPACES").unwrap_or_else(|_| "srgb,linear".to_string()),
+        env::var("API_SERVER_ALLOW_DITHERING")
+            .map(|s| s.trim().eq_ignore_ascii_case("true"))
+            .unwrap_or(true),
+    )
+    .expect("Invalid conversion policy for API_SERVER_ALLOWED_*");
+    // NOTE: 省略時の品質・色空間が許可されていないと、省略したリクエストが全て失敗するので起動しない
+    if let Err(e) = conversion_policy.check_defaults() {
+        panic!(
+            "Invalid conversion policy for API_SERVER_ALLOWED_*: default conversion is not allowed: {}",
+            e
+        );
+    }
+    info!(
+        "Allowed conversions: formats: {}, quality: {}-{}, color spaces: {}, dithering: {}",
+        conversion_policy.format_names().join(", "),
+        conversion_policy.quality().start(),
+        conversion_policy.quality().end(),
+        conversion_policy.color_space_names().join(", "),
+        conversion_policy.dithering()
+    );
+
+    // 変換に使うコンバーター（crunch: 外部の crunch、native: プロセス内で圧縮、デフォルト: crunch）
+    let converter: Arc<dyn infrastructure::Converter> = match env::var("API_SERVER_CONVERTER")
+        .as_deref()
+        .unwrap_or("crunch")
+    {
+        "crunch" => {
+            // crunch の実行ファイルのパス（作業ディレクトリからの相対パスか絶対パス）
+            let crunch_path = env::var("API_SERVER_CRUNCH_PATH")
+                .unwrap_or_else(|_| "server/resources/bin/crunch".to_string());
+            info!("Using crunch: {}", crunch_path);
+            Arc::new(infrastructure::DefaultConverter::new(crunch_path))
+        }
+        "native" => Arc::new(infrastructure::NativeConverter::new()),
+        other => panic!("Invalid converter for API_SERVER_CONVERTER: {}", other),
+    };
+    // NOTE: 変換できない状態でリクエストを受け付けないように、起動前に同梱のテスト画像を変換してみる
+    if let Err(e) = converter.self_check().await {
+        panic!("Converter self-check failed: {}", e);
+    }
+    info!("Converter self-check passed");
+
     let storage = Arc::new(infrastructure::DefaultStorage::new());
-    let upload_service = Arc::new(service::UploadSingleImageServiceImpl::new(
-        converter.clone(),
-        storage.clone(),
-    ));
-    let upload_merged_service = Arc::new(service::UploadMergedImageServiceImpl::new(
-        converter, storage,
-    ));
-    let server_impl = handler::ServerImpl::new(upload_service, upload_merged_service);
+    let upload_service = Arc::new(
+        service::UploadSingleImageServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let upload_merged_service = Arc::new(
+        service::UploadMergedImageServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let update_merged_service = Arc::new(
+        service::UpdateMergedImageServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let edit_merged_service = Arc::new(
+        service::EditMergedImageServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let inspect_merged_service =
+        Arc::new(service::InspectMergedImageServiceImpl::new(storage.clone()));
+    let upload_atlas_service = Arc::new(
+        service::UploadAtlasServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let upload_flipbook_service = Arc::new(
+        service::UploadFlipbookServiceImpl::new(converter.clone(), storage.clone())
+            .with_allowed_formats(allowed_formats.clone())
+            .with_allowed_animation_formats(allowed_animation_formats)
+            .with_conversion_policy(conversion_policy.clone()),
+    );
+    let upload_cubemap_service = Arc::new(
+        service::UploadCubemapServiceImpl::new(converter, storage)
+            .with_allowed_formats(allowed_formats)
+            .with_conversion_policy(conversion_policy),
+    );
+    let server_impl = handler::ServerImpl::new(
+        upload_service,
+        upload_merged_service,
+        update_merged_service,
+        edit_merged_service,
+        inspect_merged_service,
+        upload_atlas_service,
+        upload_flipbook_service,
+        upload_cubemap_service,
+    );
 
     // ボディサイズ制限を設定（デフォルトは2MB、100MBに設定）
     // 環境変数で設定可能（デフォルト: 100MB = 100 * 1024 * 1024 bytes）
@@ -5,8 +5,9 @@ use std::sync::Arc;
 
 use crate::infrastructure::error::{InfrastructureError, InfrastructureResult};
 use crate::infrastructure::Converter;
+use crate::model::ConversionOptions;
 
-type ConverterFn = dyn Fn(&[u8]) -> InfrastructureResult<Vec<u8>> + Send + Sync;
+type ConverterFn = dyn Fn(&[u8], &ConversionOptions) -> InfrastructureResult<Vec<u8>> + Send + Sync;
 
 #[derive(Clone)]
 pub struct MockConverter {
@@ -17,6 +18,14 @@ impl MockConverter {
     pub fn new<F>(handler: F) -> Self
     where
         F: Fn(&[u8]) -> InfrastructureResult<Vec<u8>> + Send + Sync + 'static,
+    {
+        Self::with_options(move |image, _| handler(image))
+    }
+
+    /// 変換の設定を全て受け取って振る舞いを決める
+    pub fn with_options<F>(handler: F) -> Self
+    where
+        F: Fn(&[u8], &ConversionOptions) -> InfrastructureResult<Vec<u8>> + Send + Sync + 'static,
     {
         Self {
             responder: Arc::new(handler),
@@ -35,15 +44,25 @@ impl MockConverter {
 
 #[async_trait]
 impl Converter for MockConverter {
-    async fn jpeg_to_dds(&self, image: &[u8]) -> InfrastructureResult<Vec<u8>> {
-        (self.responder)(image)
+    async fn jpeg_to_dds(
+        &self,
+        image: &[u8],
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<Vec<u8>> {
+        (self.responder)(image, options)
     }
 
-    async fn convert(&self, input_path: &Path, output_path: &Path) -> InfrastructureResult<()> {
+    async fn convert(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        options: &ConversionOptions,
+    ) -> InfrastructureResult<()> {
         info!(
-            "Converting image to DDS format (input_path: {}, output_path: {})",
+            "Converting image to DDS format (input_path: {}, output_path: {}, options: {:?})",
             input_path.display(),
-            output_path.display()
+            output_path.display(),
+            options
         );
         Ok(())
     }
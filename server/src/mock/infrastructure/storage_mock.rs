@@ -5,10 +5,12 @@ use crate::infrastructure::error::{InfrastructureError, InfrastructureResult};
 use crate::infrastructure::Storage;
 
 type StorageFn = dyn Fn(&str, &[u8]) -> InfrastructureResult<()> + Send + Sync;
+type DownloadFn = dyn Fn(&str) -> InfrastructureResult<Vec<u8>> + Send + Sync;
 
 #[derive(Clone)]
 pub struct MockStorage {
     responder: Arc<StorageFn>,
+    download_responder: Arc<DownloadFn>,
 }
 
 impl MockStorage {
@@ -18,6 +20,11 @@ impl MockStorage {
     {
         Self {
             responder: Arc::new(handler),
+            download_responder: Arc::new(|_| {
+                Err(InfrastructureError::Storage(
+                    "download is not configured".to_string(),
+                ))
+            }),
         }
     }
 
@@ -29,6 +36,20 @@ impl MockStorage {
         let msg = message.into();
         Self::new(move |_, _| Err(InfrastructureError::Storage(msg.clone())))
     }
+
+    /// ダウンロード時の振る舞いを差し替える
+    pub fn with_download<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&str) -> InfrastructureResult<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.download_responder = Arc::new(handler);
+        self
+    }
+
+    /// ダウンロード時に指定したデータを返す
+    pub fn with_download_data(self, data: Vec<u8>) -> Self {
+        self.with_download(move |_| Ok(data.clone()))
+    }
 }
 
 #[async_trait]
@@ -36,4 +57,12 @@ impl Storage for MockStorage {
     async fn upload_file(&self, presigned_url: &str, file_data: &[u8]) -> InfrastructureResult<()> {
         (self.responder)(presigned_url, file_data)
     }
+
+    async fn download_file(&self, url: &str, max_size: usize) -> InfrastructureResult<Vec<u8>> {
+        let data = (self.download_responder)(url)?;
+        if data.len() > max_size {
+            return Err(InfrastructureError::DownloadTooLarge { limit: max_size });
+        }
+        Ok(data)
+    }
 }
@@ -0,0 +1,280 @@
+use std::cmp::Reverse;
+
+use image::{DynamicImage, GenericImageView, RgbaImage};
+
+use crate::model::error::AtlasError;
+use crate::model::Image;
+
+/// アトラスの一辺の最大ピクセル数のデフォルト
+pub const DEFAULT_ATLAS_MAX_SIZE: u32 = 2048;
+
+/// 指定できるアトラスの一辺の最大ピクセル数の上限
+pub const ATLAS_MAX_SIZE_LIMIT: u32 = 8192;
+
+/// アトラス内での画像1枚分の位置（左上原点、px）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasRect {
+    /// 何枚目のアトラスに入っているか
+    pub atlas: usize,
+    /// 左端の位置
+    pub x: u32,
+    /// 上端の位置
+    pub y: u32,
+    /// 横幅
+    pub width: u32,
+    /// 高さ
+    pub height: u32,
+}
+
+/// 画像をアトラスに詰め込む配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AtlasLayout {
+    /// 各アトラスの (横幅, 高さ)
+    pub sizes: Vec<(u32, u32)>,
+    /// 入力と同じ順番の各画像の位置
+    pub rects: Vec<AtlasRect>,
+}
+
+impl AtlasLayout {
+    /// 高さの大きい順に棚詰めして各画像の位置を決める
+    ///
+    /// 各画像は4の倍数の位置に置き、占める領域も4の倍数に切り上げるので、
+    /// DXT の 4x4 ブロックに別の画像が混ざらない
+    /// 1枚のアトラスに収まらない分は次のアトラスに詰める
+    pub fn pack(dimensions: &[(u32, u32)], max_size: u32) -> Result<Self, AtlasError> {
+        if dimensions.is_empty() {
+            return Err(AtlasError::Empty);
+        }
+        if max_size == 0 || !max_size.is_multiple_of(4) || max_size > ATLAS_MAX_SIZE_LIMIT {
+            return Err(AtlasError::InvalidMaxSize {
+                actual: max_size,
+                max: ATLAS_MAX_SIZE_LIMIT,
+            });
+        }
+        if let Some((index, &(width, height))) = dimensions
+            .iter()
+            .enumerate()
+            .find(|(_, (width, height))| *width > max_size || *height > max_size)
+        {
+            return Err(AtlasError::TooLarge {
+                index,
+                width,
+                height,
+                max_size,
+            });
+        }
+
+        let cell =
+            |(width, height): (u32, u32)| (width.next_multiple_of(4), height.next_multiple_of(4));
+        let mut order: Vec<usize> = (0..dimensions.len()).collect();
+        order.sort_by_key(|&index| {
+            let (width, height) = cell(dimensions[index]);
+            Reverse((height, width))
+        });
+
+        let mut sizes = Vec::new();
+        let mut rects = vec![None; dimensions.len()];
+        // NOTE: 詰めている途中のアトラスの使用済みサイズと、棚の位置
+        let (mut used_width, mut used_height) = (0, 0);
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+        for index in order {
+            let (width, height) = dimensions[index];
+            let (cell_width, cell_height) = cell((width, height));
+            if x + cell_width > max_size {
+                y += shelf_height;
+                x = 0;
+                shelf_height = 0;
+            }
+            if y + cell_height > max_size {
+                sizes.push((used_width, used_height));
+                (used_width, used_height) = (0, 0);
+                (x, y, shelf_height) = (0, 0, 0);
+            }
+
+            rects[index] = Some(AtlasRect {
+                atlas: sizes.len(),
+                x,
+                y,
+                width,
+                height,
+            });
+            x += cell_width;
+            shelf_height = shelf_height.max(cell_height);
+            used_width = used_width.max(x);
+            used_height = used_height.max(y + cell_height);
+        }
+        sizes.push((used_width, used_height));
+
+        Ok(Self {
+            sizes,
+            // NOTE: 全ての画像を配置済み
+            rects: rects.into_iter().flatten().collect(),
+        })
+    }
+}
+
+/// 画像をアトラスに詰め込み、配置と各アトラスの画像を返す
+///
+/// アトラスの余白は透明で、PNG でエンコードする
+pub fn build_atlases(
+    images: &[Vec<u8>],
+    max_size: u32,
+) -> Result<(AtlasLayout, Vec<Image>), AtlasError> {
+    let decoded = images
+        .iter()
+        .enumerate()
+        .map(|(index, data)| {
+            if data.is_empty() {
+                return Err(AtlasError::InvalidImage {
+                    index,
+                    reason: "image data is empty".to_string(),
+                });
+            }
+            image::load_from_memory(data).map_err(|e| AtlasError::InvalidImage {
+                index,
+                reason: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<DynamicImage>, _>>()?;
+
+    let dimensions: Vec<(u32, u32)> = decoded.iter().map(|img| img.dimensions()).collect();
+    let layout = AtlasLayout::pack(&dimensions, max_size)?;
+
+    let mut canvases: Vec<RgbaImage> = layout
+        .sizes
+        .iter()
+        .map(|&(width, height)| RgbaImage::new(width, height))
+        .collect();
+    for (img, rect) in decoded.iter().zip(&layout.rects) {
+        image::imageops::replace(
+            &mut canvases[rect.atlas],
+            &img.to_rgba8(),
+            rect.x as i64,
+            rect.y as i64,
+        );
+    }
+
+    let atlases = canvases
+        .into_iter()
+        .map(|canvas| Image::encode(&DynamicImage::ImageRgba8(canvas)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AtlasError::EncodeError(e.to_string()))?;
+    Ok((layout, atlases))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    fn png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba(color)));
+        Image::encode(&img).unwrap().data
+    }
+
+    #[test]
+    fn 高さの大きい順に棚に並べる() {
+        let layout = AtlasLayout::pack(&[(8, 4), (8, 8), (4, 4)], 16).unwrap();
+        assert_eq!(layout.sizes, vec![(16, 12)]);
+        assert_eq!(
+            layout.rects,
+            vec![
+                AtlasRect {
+                    atlas: 0,
+                    x: 8,
+                    y: 0,
+                    width: 8,
+                    height: 4
+                },
+                AtlasRect {
+                    atlas: 0,
+                    x: 0,
+                    y: 0,
+                    width: 8,
+                    height: 8
+                },
+                AtlasRect {
+                    atlas: 0,
+                    x: 0,
+                    y: 8,
+                    width: 4,
+                    height: 4
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn 四の倍数でない画像も四の倍数の位置に置く() {
+        let layout = AtlasLayout::pack(&[(5, 3), (3, 3)], 16).unwrap();
+        assert_eq!((layout.rects[0].x, layout.rects[0].y), (0, 0));
+        assert_eq!((layout.rects[1].x, layout.rects[1].y), (8, 0));
+        assert_eq!((layout.rects[0].width, layout.rects[0].height), (5, 3));
+        assert_eq!(layout.sizes, vec![(12, 4)]);
+    }
+
+    #[test]
+    fn 収まらない分は次のアトラスに詰める() {
+        let layout = AtlasLayout::pack(&[(8, 8), (8, 8), (8, 8)], 8).unwrap();
+        assert_eq!(layout.sizes, vec![(8, 8); 3]);
+        let atlases: Vec<usize> = layout.rects.iter().map(|rect| rect.atlas).collect();
+        assert_eq!(atlases, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn 最大サイズより大きい画像があるならエラーを返す() {
+        let result = AtlasLayout::pack(&[(4, 4), (4, 12)], 8);
+        assert_eq!(
+            result.unwrap_err(),
+            AtlasError::TooLarge {
+                index: 1,
+                width: 4,
+                height: 12,
+                max_size: 8
+            }
+        );
+    }
+
+    #[test]
+    fn 最大サイズが不正ならエラーを返す() {
+        for max_size in [0, 10, ATLAS_MAX_SIZE_LIMIT + 4] {
+            assert!(matches!(
+                AtlasLayout::pack(&[(4, 4)], max_size),
+                Err(AtlasError::InvalidMaxSize { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn 画像がないならエラーを返す() {
+        assert_eq!(AtlasLayout::pack(&[], 8).unwrap_err(), AtlasError::Empty);
+    }
+
+    #[test]
+    fn 各画像を配置した位置に描き込む() {
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let (layout, atlases) = build_atlases(&[png(4, 4, red), png(6, 8, blue)], 16).unwrap();
+
+        assert_eq!(atlases.len(), 1);
+        assert_eq!((atlases[0].width, atlases[0].height), (12, 8));
+        let atlas = image::load_from_memory(&atlases[0].data)
+            .unwrap()
+            .to_rgba8();
+        let rect = layout.rects[0];
+        assert_eq!(atlas.get_pixel(rect.x, rect.y).0, red);
+        let rect = layout.rects[1];
+        assert_eq!(atlas.get_pixel(rect.x + 5, rect.y + 7).0, blue);
+        // NOTE: 4の倍数に切り上げた余白は透明
+        assert_eq!(atlas.get_pixel(rect.x + 6, rect.y).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn 画像として解釈できないならエラーを返す() {
+        let result = build_atlases(&[png(4, 4, [0, 0, 0, 255]), vec![1, 2, 3]], 16);
+        assert!(matches!(
+            result.unwrap_err(),
+            AtlasError::InvalidImage { index: 1, .. }
+        ));
+    }
+}
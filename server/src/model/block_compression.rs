@@ -0,0 +1,559 @@
+use image::{imageops, RgbaImage};
+
+use crate::model::dds::{dds_file_size, dds_header};
+use crate::model::mipmap::mip_count;
+use crate::model::{ColorSpace, ConversionOptions, DdsFormat};
+
+/// 4x4 ピクセルのブロック（左上から行ごとに並べた RGBA）
+type Block = [[u8; 4]; 16];
+
+/// RGB の誤差に掛ける重み
+///
+/// sRGB は人の目が緑に敏感なことに合わせ、linear は RGB を均等に扱う
+fn channel_weights(color_space: ColorSpace) -> [f32; 3] {
+    match color_space {
+        ColorSpace::Srgb => [0.299, 0.587, 0.114],
+        ColorSpace::Linear => [1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0],
+    }
+}
+
+/// 画像をミップマップも含めてDDSに圧縮する
+///
+/// ミップマップは前の段を縦横半分（1 未満にはしない）に縮小して作る
+pub fn compress_to_dds(image: &RgbaImage, options: &ConversionOptions) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let mip_count = mip_count(width, height, options.mipmaps.as_ref());
+
+    let mut output = Vec::with_capacity(dds_file_size(width, height, mip_count, options.format));
+    output.extend(dds_header(width, height, mip_count, options.format));
+    output.extend(compress_level(image, options));
+
+    let mut level = image.clone();
+    for _ in 1..mip_count {
+        let filter = options
+            .mipmaps
+            .map(|mipmaps| mipmaps.filter.resize_filter())
+            .unwrap_or(imageops::FilterType::Triangle);
+        level = imageops::resize(
+            &level,
+            (level.width() / 2).max(1),
+            (level.height() / 2).max(1),
+            filter,
+        );
+        output.extend(compress_level(&level, options));
+    }
+    output
+}
+
+/// 1 段分の画像をブロックごとに圧縮する
+///
+/// 縦横が 4 の倍数でない場合（小さいミップマップの段）は端のピクセルを繰り返して埋める
+pub fn compress_level(image: &RgbaImage, options: &ConversionOptions) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let (blocks_x, blocks_y) = (width.div_ceil(4), height.div_ceil(4));
+    let mut output =
+        Vec::with_capacity((blocks_x * blocks_y) as usize * options.format.block_size());
+    for block_y in 0..blocks_y {
+        for block_x in 0..blocks_x {
+            let mut block = [[0u8; 4]; 16];
+            for (i, pixel) in block.iter_mut().enumerate() {
+                let x = (block_x * 4 + i as u32 % 4).min(width - 1);
+                let y = (block_y * 4 + i as u32 / 4).min(height - 1);
+                *pixel = image.get_pixel(x, y).0;
+            }
+            if options.format == DdsFormat::Dxt5 {
+                output.extend(encode_alpha_block(&block));
+            }
+            output.extend(encode_color_block(&block, options));
+        }
+    }
+    output
+}
+
+/// ブロックの色を BC1 の 8 byte（565 の端点 2 つと 2bit のインデックス 16 個）にする
+///
+/// 主成分の方向に並べた両端を端点の初期値にし、品質に応じて最小二乗法で端点を調整し直す
+pub fn encode_color_block(block: &Block, options: &ConversionOptions) -> [u8; 8] {
+    let weights = channel_weights(options.color_space);
+    let colors: Vec<[f32; 3]> = block
+        .iter()
+        .map(|pixel| [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32])
+        .collect();
+
+    let (start, end) = principal_endpoints(&colors, &weights);
+    let mut best = quantize_and_fit(start, end, &colors, &weights, options.dithering);
+
+    // NOTE: 品質 0 では初期値のみ、255 では 4 回まで調整する
+    let refinements = (options.quality as usize + 1) / 64;
+    for _ in 0..refinements {
+        let Some((start, end)) = least_squares_endpoints(&colors, &best.indices) else {
+            break;
+        };
+        let candidate = quantize_and_fit(start, end, &colors, &weights, options.dithering);
+        if candidate.error >= best.error {
+            break;
+        }
+        best = candidate;
+    }
+    best.to_bytes()
+}
+
+/// 端点とインデックスを決めた BC1 のブロック
+struct ColorBlock {
+    color0: u16,
+    color1: u16,
+    indices: [u8; 16],
+    error: f32,
+}
+
+impl ColorBlock {
+    fn to_bytes(&self) -> [u8; 8] {
+        let mut bits = 0u32;
+        for (i, index) in self.indices.iter().enumerate() {
+            bits |= (*index as u32) << (i * 2);
+        }
+        let mut bytes = [0u8; 8];
+        bytes[0..2].copy_from_slice(&self.color0.to_le_bytes());
+        bytes[2..4].copy_from_slice(&self.color1.to_le_bytes());
+        bytes[4..8].copy_from_slice(&bits.to_le_bytes());
+        bytes
+    }
+}
+
+/// 重み付きの主成分の方向に射影したときの両端の色
+fn principal_endpoints(colors: &[[f32; 3]], weights: &[f32; 3]) -> ([f32; 3], [f32; 3]) {
+    let scale = weights.map(f32::sqrt);
+    let weighted: Vec<[f32; 3]> = colors
+        .iter()
+        .map(|c| [c[0] * scale[0], c[1] * scale[1], c[2] * scale[2]])
+        .collect();
+    let n = weighted.len() as f32;
+    let mut mean = [0f32; 3];
+    for c in &weighted {
+        for k in 0..3 {
+            mean[k] += c[k] / n;
+        }
+    }
+    let mut covariance = [[0f32; 3]; 3];
+    for c in &weighted {
+        let d = [c[0] - mean[0], c[1] - mean[1], c[2] - mean[2]];
+        for i in 0..3 {
+            for j in 0..3 {
+                covariance[i][j] += d[i] * d[j];
+            }
+        }
+    }
+
+    // NOTE: べき乗法で最大固有値の固有ベクトルを求める
+    let mut axis = [1f32, 1.0, 1.0];
+    for _ in 0..8 {
+        let next = [0, 1, 2].map(|i| {
+            covariance[i][0] * axis[0] + covariance[i][1] * axis[1] + covariance[i][2] * axis[2]
+        });
+        let length = (next[0] * next[0] + next[1] * next[1] + next[2] * next[2]).sqrt();
+        if length < f32::EPSILON {
+            break;
+        }
+        axis = next.map(|v| v / length);
+    }
+
+    let project = |c: &[f32; 3]| {
+        (c[0] - mean[0]) * axis[0] + (c[1] - mean[1]) * axis[1] + (c[2] - mean[2]) * axis[2]
+    };
+    let (mut min, mut max) = (0, 0);
+    for (i, c) in weighted.iter().enumerate() {
+        if project(c) < project(&weighted[min]) {
+            min = i;
+        }
+        if project(c) > project(&weighted[max]) {
+            max = i;
+        }
+    }
+    (colors[max], colors[min])
+}
+
+/// 今のインデックスで誤差が最小になる端点を最小二乗法で求める（全て同じインデックスなら None）
+fn least_squares_endpoints(
+    colors: &[[f32; 3]],
+    indices: &[u8; 16],
+) -> Option<([f32; 3], [f32; 3])> {
+    // NOTE: インデックス 0, 1, 2, 3 は color0 の割合が 1, 0, 2/3, 1/3 の色
+    const RATIO: [f32; 4] = [1.0, 0.0, 2.0 / 3.0, 1.0 / 3.0];
+    let (mut aa, mut bb, mut ab) = (0f32, 0f32, 0f32);
+    let mut ax = [0f32; 3];
+    let mut bx = [0f32; 3];
+    for (c, index) in colors.iter().zip(indices) {
+        let a = RATIO[*index as usize];
+        let b = 1.0 - a;
+        aa += a * a;
+        bb += b * b;
+        ab += a * b;
+        for k in 0..3 {
+            ax[k] += a * c[k];
+            bx[k] += b * c[k];
+        }
+    }
+    let det = aa * bb - ab * ab;
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let start = [0, 1, 2].map(|k| ((ax[k] * bb - bx[k] * ab) / det).clamp(0.0, 255.0));
+    let end = [0, 1, 2].map(|k| ((bx[k] * aa - ax[k] * ab) / det).clamp(0.0, 255.0));
+    Some((start, end))
+}
+
+/// 端点を 565 に丸め、各ピクセルに最も近い色のインデックスを選ぶ
+///
+/// 常に 4 色のモード（color0 > color1）にする。ディザリングする場合は、
+/// 選んだ色との誤差をブロック内の右と下のピクセルに Floyd-Steinberg で振り分ける
+fn quantize_and_fit(
+    start: [f32; 3],
+    end: [f32; 3],
+    colors: &[[f32; 3]],
+    weights: &[f32; 3],
+    dithering: bool,
+) -> ColorBlock {
+    let (mut color0, mut color1) = (to_rgb565(start), to_rgb565(end));
+    if color0 < color1 {
+        std::mem::swap(&mut color0, &mut color1);
+    }
+    if color0 == color1 {
+        // NOTE: 端点が同じなら全てインデックス 0 の単色にする
+        let c = from_rgb565(color0);
+        let error = colors.iter().map(|p| weighted_error(p, &c, weights)).sum();
+        return ColorBlock {
+            color0,
+            color1,
+            indices: [0; 16],
+            error,
+        };
+    }
+
+    let palette = palette4(color0, color1);
+    let mut pixels = colors.to_vec();
+    let mut indices = [0u8; 16];
+    let mut error = 0f32;
+    for i in 0..16 {
+        let pixel = pixels[i];
+        let index = palette
+            .iter()
+            .enumerate()
+            .map(|(index, c)| (index, weighted_error(&pixel, c, weights)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+            .unwrap();
+        indices[i] = index as u8;
+        error += weighted_error(&colors[i], &palette[index], weights);
+
+        if dithering {
+            let diff = [0, 1, 2].map(|k| pixel[k] - palette[index][k]);
+            let (x, y) = (i % 4, i / 4);
+            for (dx, dy, ratio) in [(1, 0, 7.0), (-1, 1, 3.0), (0, 1, 5.0), (1, 1, 1.0)] {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if (0..4).contains(&nx) && ny < 4 {
+                    let neighbor = &mut pixels[ny as usize * 4 + nx as usize];
+                    for k in 0..3 {
+                        neighbor[k] = (neighbor[k] + diff[k] * ratio / 16.0).clamp(0.0, 255.0);
+                    }
+                }
+            }
+        }
+    }
+    ColorBlock {
+        color0,
+        color1,
+        indices,
+        error,
+    }
+}
+
+/// 4 色のモードのパレット（color0, color1, 2:1 の中間色, 1:2 の中間色）
+fn palette4(color0: u16, color1: u16) -> [[f32; 3]; 4] {
+    let c0 = from_rgb565(color0);
+    let c1 = from_rgb565(color1);
+    [
+        c0,
+        c1,
+        [0, 1, 2].map(|k| (2.0 * c0[k] + c1[k]) / 3.0),
+        [0, 1, 2].map(|k| (c0[k] + 2.0 * c1[k]) / 3.0),
+    ]
+}
+
+fn weighted_error(a: &[f32; 3], b: &[f32; 3], weights: &[f32; 3]) -> f32 {
+    (0..3).map(|k| weights[k] * (a[k] - b[k]).powi(2)).sum()
+}
+
+fn to_rgb565(c: [f32; 3]) -> u16 {
+    let r = (c[0] * 31.0 / 255.0).round() as u16;
+    let g = (c[1] * 63.0 / 255.0).round() as u16;
+    let b = (c[2] * 31.0 / 255.0).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from_rgb565(c: u16) -> [f32; 3] {
+    let r = ((c >> 11) & 0x1F) as u32;
+    let g = ((c >> 5) & 0x3F) as u32;
+    let b = (c & 0x1F) as u32;
+    // NOTE: デコーダーと同じく上位ビットを下位に複製して 8bit に広げる
+    [
+        ((r << 3) | (r >> 2)) as f32,
+        ((g << 2) | (g >> 4)) as f32,
+        ((b << 3) | (b >> 2)) as f32,
+    ]
+}
+
+/// ブロックのアルファを BC3 の 8 byte（端点 2 つと 3bit のインデックス 16 個）にする
+///
+/// 最大値と最小値を端点にし、間を 7 等分した 8 段階から最も近い値を選ぶ
+pub fn encode_alpha_block(block: &Block) -> [u8; 8] {
+    let alpha0 = block.iter().map(|p| p[3]).max().unwrap_or(255);
+    let alpha1 = block.iter().map(|p| p[3]).min().unwrap_or(255);
+    let mut bytes = [0u8; 8];
+    bytes[0] = alpha0;
+    bytes[1] = alpha1;
+    if alpha0 == alpha1 {
+        return bytes;
+    }
+
+    let palette = alpha_palette(alpha0, alpha1);
+    let mut bits = 0u64;
+    for (i, pixel) in block.iter().enumerate() {
+        let index = palette
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, a)| (**a as i32 - pixel[3] as i32).abs())
+            .map(|(index, _)| index)
+            .unwrap();
+        bits |= (index as u64) << (i * 3);
+    }
+    bytes[2..8].copy_from_slice(&bits.to_le_bytes()[..6]);
+    bytes
+}
+
+/// 8 段階のモード（alpha0 > alpha1）のアルファのパレット
+fn alpha_palette(alpha0: u8, alpha1: u8) -> [u8; 8] {
+    let (a0, a1) = (alpha0 as u32, alpha1 as u32);
+    let mut palette = [alpha0, alpha1, 0, 0, 0, 0, 0, 0];
+    for i in 1..7u32 {
+        palette[i as usize + 1] = (((7 - i) * a0 + i * a1 + 3) / 7) as u8;
+    }
+    palette
+}
+
+#[cfg(test)]
+pub mod test_util {
+    use super::*;
+
+    /// BC1 のブロックを RGBA に戻す（3 色のモードの透明も含む）
+    pub fn decode_color_block(bytes: &[u8]) -> Block {
+        let color0 = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let color1 = u16::from_le_bytes([bytes[2], bytes[3]]);
+        let bits = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        let (c0, c1) = (from_rgb565(color0), from_rgb565(color1));
+        let palette: [[f32; 4]; 4] = if color0 > color1 {
+            palette4(color0, color1).map(|c| [c[0], c[1], c[2], 255.0])
+        } else {
+            let mid = [0, 1, 2].map(|k| (c0[k] + c1[k]) / 2.0);
+            [
+                [c0[0], c0[1], c0[2], 255.0],
+                [c1[0], c1[1], c1[2], 255.0],
+                [mid[0], mid[1], mid[2], 255.0],
+                [0.0; 4],
+            ]
+        };
+        let mut block = [[0u8; 4]; 16];
+        for (i, pixel) in block.iter_mut().enumerate() {
+            *pixel = palette[(bits >> (i * 2)) as usize & 0x3].map(|v| v.round() as u8);
+        }
+        block
+    }
+
+    /// BC3 のアルファのブロックを 16 ピクセル分のアルファに戻す
+    pub fn decode_alpha_block(bytes: &[u8]) -> [u8; 16] {
+        let (alpha0, alpha1) = (bytes[0], bytes[1]);
+        let palette = if alpha0 > alpha1 {
+            alpha_palette(alpha0, alpha1)
+        } else {
+            let (a0, a1) = (alpha0 as u32, alpha1 as u32);
+            let mut palette = [alpha0, alpha1, 0, 0, 0, 0, 0, 255];
+            for i in 1..5u32 {
+                palette[i as usize + 1] = (((5 - i) * a0 + i * a1 + 2) / 5) as u8;
+            }
+            palette
+        };
+        let mut raw = [0u8; 8];
+        raw[..6].copy_from_slice(&bytes[2..8]);
+        let bits = u64::from_le_bytes(raw);
+        std::array::from_fn(|i| palette[(bits >> (i * 3)) as usize & 0x7])
+    }
+
+    /// 圧縮したDDSの先頭の段を画像に戻す
+    pub fn decode_dds(data: &[u8], width: u32, height: u32, format: DdsFormat) -> RgbaImage {
+        let mut image = RgbaImage::new(width, height);
+        let blocks_x = width.div_ceil(4);
+        let block_size = format.block_size();
+        let blocks = &data[crate::model::dds::DDS_HEADER_SIZE..];
+        for (n, bytes) in blocks
+            .chunks(block_size)
+            .take((blocks_x * height.div_ceil(4)) as usize)
+            .enumerate()
+        {
+            let (block_x, block_y) = (n as u32 % blocks_x, n as u32 / blocks_x);
+            let (color, alpha) = match format {
+                DdsFormat::Dxt1 => (decode_color_block(bytes), None),
+                DdsFormat::Dxt5 => (
+                    decode_color_block(&bytes[8..]),
+                    Some(decode_alpha_block(&bytes[..8])),
+                ),
+            };
+            for (i, mut pixel) in color.into_iter().enumerate() {
+                let (x, y) = (block_x * 4 + i as u32 % 4, block_y * 4 + i as u32 / 4);
+                if x >= width || y >= height {
+                    continue;
+                }
+                if let Some(alpha) = alpha {
+                    pixel[3] = alpha[i];
+                }
+                image.put_pixel(x, y, image::Rgba(pixel));
+            }
+        }
+        image
+    }
+
+    /// 元の画像との PSNR (dB)
+    pub fn psnr(original: &RgbaImage, decoded: &RgbaImage) -> f64 {
+        let mut squared = 0f64;
+        for (a, b) in original.pixels().zip(decoded.pixels()) {
+            for k in 0..3 {
+                squared += (a[k] as f64 - b[k] as f64).powi(2);
+            }
+        }
+        let mse = squared / (original.width() * original.height() * 3) as f64;
+        if mse == 0.0 {
+            return f64::INFINITY;
+        }
+        10.0 * (255.0 * 255.0 / mse).log10()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::{decode_alpha_block, decode_color_block, decode_dds, psnr};
+    use super::*;
+    use crate::model::dds::{DdsHeader, PixelFormat};
+    use crate::model::MipmapOptions;
+    use image::Rgba;
+
+    fn options(format: DdsFormat) -> ConversionOptions {
+        ConversionOptions::new(format, &Default::default())
+    }
+
+    /// 左から右へ黒から赤、上から下へ黒から青になるグラデーション
+    fn gradient(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([
+                (x * 255 / (width - 1)) as u8,
+                64,
+                (y * 255 / (height - 1)) as u8,
+                255,
+            ])
+        })
+    }
+
+    #[test]
+    fn 単色のブロックはほぼそのまま戻せる() {
+        let block = [[200, 100, 50, 255]; 16];
+        let decoded = decode_color_block(&encode_color_block(&block, &options(DdsFormat::Dxt1)));
+        for pixel in decoded {
+            for k in 0..3 {
+                // NOTE: 565 に丸めるので 1 段階分（最大 8）までずれる
+                assert!((pixel[k] as i32 - block[0][k] as i32).abs() <= 8);
+            }
+        }
+    }
+
+    #[test]
+    fn 二色のブロックは端点でそのまま戻せる() {
+        let mut block = [[0, 0, 0, 255]; 16];
+        for pixel in block.iter_mut().skip(8) {
+            *pixel = [255, 255, 255, 255];
+        }
+        let decoded = decode_color_block(&encode_color_block(&block, &options(DdsFormat::Dxt1)));
+        assert_eq!(decoded, block);
+    }
+
+    #[test]
+    fn アルファは端点と中間の8段階で戻せる() {
+        let mut block = [[0, 0, 0, 0]; 16];
+        for (i, pixel) in block.iter_mut().enumerate() {
+            pixel[3] = (i * 17) as u8;
+        }
+        let decoded = decode_alpha_block(&encode_alpha_block(&block));
+        assert_eq!(decoded[0], 0);
+        assert_eq!(decoded[15], 255);
+        for (pixel, alpha) in block.iter().zip(decoded) {
+            // NOTE: 255 / 7 の半分（約 18）以内に収まる
+            assert!((pixel[3] as i32 - alpha as i32).abs() <= 19);
+        }
+    }
+
+    #[test]
+    fn グラデーションを十分な品質で圧縮できる() {
+        let image = gradient(64, 64);
+        let dds = compress_to_dds(&image, &options(DdsFormat::Dxt1));
+        let decoded = decode_dds(&dds, 64, 64, DdsFormat::Dxt1);
+        assert!(psnr(&image, &decoded) > 35.0);
+    }
+
+    #[test]
+    fn 品質を下げても大きく劣化しない() {
+        let image = gradient(64, 64);
+        let low = ConversionOptions {
+            quality: 0,
+            ..options(DdsFormat::Dxt1)
+        };
+        let high = psnr(
+            &image,
+            &decode_dds(
+                &compress_to_dds(&image, &options(DdsFormat::Dxt1)),
+                64,
+                64,
+                DdsFormat::Dxt1,
+            ),
+        );
+        let low = psnr(
+            &image,
+            &decode_dds(&compress_to_dds(&image, &low), 64, 64, DdsFormat::Dxt1),
+        );
+        assert!(high >= low);
+        assert!(low > 30.0);
+    }
+
+    #[test]
+    fn ヘッダーとサイズがフォーマットとミップマップに合う() {
+        let image = gradient(16, 8);
+        let dds = compress_to_dds(&image, &options(DdsFormat::Dxt1));
+        assert_eq!(dds.len(), dds_file_size(16, 8, 1, DdsFormat::Dxt1));
+
+        let with_mipmaps = ConversionOptions {
+            mipmaps: Some(MipmapOptions::default()),
+            ..options(DdsFormat::Dxt5)
+        };
+        let dds = compress_to_dds(&image, &with_mipmaps);
+        // 16x8, 8x4, 4x2, 2x1, 1x1
+        assert_eq!(dds.len(), dds_file_size(16, 8, 5, DdsFormat::Dxt5));
+        let header = DdsHeader::parse(&dds).unwrap();
+        assert_eq!((header.width, header.height), (16, 8));
+        assert_eq!(header.mip_count, 5);
+        assert_eq!(header.format, PixelFormat::Dxt5);
+    }
+
+    #[test]
+    fn dxt5なら透過を残す() {
+        let image = RgbaImage::from_fn(8, 8, |x, _| Rgba([255, 0, 0, if x < 4 { 0 } else { 255 }]));
+        let dds = compress_to_dds(&image, &options(DdsFormat::Dxt5));
+        let decoded = decode_dds(&dds, 8, 8, DdsFormat::Dxt5);
+        assert_eq!(decoded.get_pixel(0, 0)[3], 0);
+        assert_eq!(decoded.get_pixel(7, 7)[3], 255);
+    }
+}
@@ -0,0 +1,399 @@
+use std::ops::RangeInclusive;
+
+use crate::model::error::{ConversionNotAllowedError, ConversionOptionsError};
+use crate::model::{DdsFormat, MipmapOptions};
+
+/// 圧縮時に誤差を測る色空間
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// 人の目に合わせて誤差を測る（カラーのテクスチャ向け）
+    #[default]
+    Srgb,
+    /// RGB を均等に扱って誤差を測る（法線マップやマスクなどのデータ向け）
+    Linear,
+}
+
+impl ColorSpace {
+    /// 色空間の名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "srgb",
+            ColorSpace::Linear => "linear",
+        }
+    }
+}
+
+impl std::str::FromStr for ColorSpace {
+    type Err = ConversionOptionsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "srgb" => Ok(ColorSpace::Srgb),
+            "linear" => Ok(ColorSpace::Linear),
+            _ => Err(ConversionOptionsError::UnsupportedColorSpace(s.to_string())),
+        }
+    }
+}
+
+/// リクエストで指定する圧縮の設定（変換後のフォーマットとミップマップ以外）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompressionOptions {
+    /// 圧縮の品質（0 から 255、大きいほど高品質で遅い）
+    pub quality: u8,
+    /// 誤差を測る色空間
+    pub color_space: ColorSpace,
+    /// ディザリングするか
+    pub dithering: bool,
+}
+
+impl Default for CompressionOptions {
+    /// 最高品質、sRGB、ディザリングなし（これまでの変換と同じ）
+    fn default() -> Self {
+        Self {
+            quality: 255,
+            color_space: ColorSpace::default(),
+            dithering: false,
+        }
+    }
+}
+
+impl CompressionOptions {
+    /// 品質と色空間の文字列から作る（指定しなければデフォルト）
+    pub fn parse(
+        quality: Option<&str>,
+        color_space: Option<&str>,
+        dithering: bool,
+    ) -> Result<Self, ConversionOptionsError> {
+        let mut options = Self {
+            dithering,
+            ..Self::default()
+        };
+        if let Some(quality) = quality {
+            options.quality = quality
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| ConversionOptionsError::InvalidQuality(quality.to_string()))?;
+        }
+        if let Some(color_space) = color_space {
+            options.color_space = color_space.parse()?;
+        }
+        Ok(options)
+    }
+}
+
+/// コンバーターに渡す変換の設定
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConversionOptions {
+    /// 変換後のフォーマット
+    pub format: DdsFormat,
+    /// 圧縮の品質（0 から 255）
+    pub quality: u8,
+    /// 誤差を測る色空間
+    pub color_space: ColorSpace,
+    /// ディザリングするか
+    pub dithering: bool,
+    /// ミップマップを生成する場合の設定（指定しなければミップマップなし）
+    pub mipmaps: Option<MipmapOptions>,
+}
+
+impl ConversionOptions {
+    /// 変換後のフォーマットと圧縮の設定から作る（ミップマップなし）
+    pub fn new(format: DdsFormat, compression: &CompressionOptions) -> Self {
+        Self {
+            format,
+            quality: compression.quality,
+            color_space: compression.color_space,
+            dithering: compression.dithering,
+            mipmaps: None,
+        }
+    }
+}
+
+/// サーバーで許可する変換の設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionPolicy {
+    formats: Vec<DdsFormat>,
+    quality: RangeInclusive<u8>,
+    color_spaces: Vec<ColorSpace>,
+    dithering: bool,
+}
+
+impl Default for ConversionPolicy {
+    /// 全て許可する
+    fn default() -> Self {
+        Self {
+            formats: vec![DdsFormat::Dxt1, DdsFormat::Dxt5],
+            quality: 0..=255,
+            color_spaces: vec![ColorSpace::Srgb, ColorSpace::Linear],
+            dithering: true,
+        }
+    }
+}
+
+impl ConversionPolicy {
+    /// カンマ区切りのフォーマットと色空間、`MIN-MAX` 形式の品質の範囲から作る
+    pub fn parse(
+        formats: &str,
+        quality: &str,
+        color_spaces: &str,
+        dithering: bool,
+    ) -> Result<Self, ConversionOptionsError> {
+        let formats = parse_list(formats, "texture formats", |name| {
+            match name.to_ascii_lowercase().as_str() {
+                "dxt1" => Ok(DdsFormat::Dxt1),
+                "dxt5" => Ok(DdsFormat::Dxt5),
+                _ => Err(ConversionOptionsError::UnsupportedTextureFormat(
+                    name.to_string(),
+                )),
+            }
+        })?;
+        let color_spaces = parse_list(color_spaces, "color spaces", str::parse)?;
+        let invalid_range = || ConversionOptionsError::InvalidQualityRange(quality.to_string());
+        let (min, max) = quality.split_once('-').ok_or_else(invalid_range)?;
+        let min = min.trim().parse::<u8>().map_err(|_| invalid_range())?;
+        let max = max.trim().parse::<u8>().map_err(|_| invalid_range())?;
+        if min > max {
+            return Err(invalid_range());
+        }
+        Ok(Self {
+            formats,
+            quality: min..=max,
+            color_spaces,
+            dithering,
+        })
+    }
+
+    /// 変換の設定が許可されているか確認する
+    pub fn check(&self, options: &ConversionOptions) -> Result<(), ConversionNotAllowedError> {
+        if !self.formats.contains(&options.format) {
+            return Err(ConversionNotAllowedError::Format {
+                format: format_name(options.format),
+                allowed: self.format_names(),
+            });
+        }
+        if !self.quality.contains(&options.quality) {
+            return Err(ConversionNotAllowedError::Quality {
+                quality: options.quality,
+                min: *self.quality.start(),
+                max: *self.quality.end(),
+            });
+        }
+        if !self.color_spaces.contains(&options.color_space) {
+            return Err(ConversionNotAllowedError::ColorSpace {
+                color_space: options.color_space.name().to_string(),
+                allowed: self.color_space_names(),
+            });
+        }
+        if options.dithering && !self.dithering {
+            return Err(ConversionNotAllowedError::Dithering);
+        }
+        Ok(())
+    }
+
+    /// 圧縮の設定を省略したときのデフォルト（最高品質、sRGB、ディザリングなし）が許可されているか確認する
+    ///
+    /// 許可されていないと圧縮の設定を省略したリクエストが全て失敗するので、起動時に確認する
+    pub fn check_defaults(&self) -> Result<(), ConversionNotAllowedError> {
+        let compression = CompressionOptions::default();
+        self.formats
+            .iter()
+            .try_for_each(|format| self.check(&ConversionOptions::new(*format, &compression)))
+    }
+
+    /// 許可されているフォーマットの名前
+    pub fn format_names(&self) -> Vec<String> {
+        self.formats.iter().copied().map(format_name).collect()
+    }
+
+    /// 許可されている色空間の名前
+    pub fn color_space_names(&self) -> Vec<String> {
+        self.color_spaces
+            .iter()
+            .map(|color_space| color_space.name().to_string())
+            .collect()
+    }
+
+    /// 許可されている品質の範囲
+    pub fn quality(&self) -> &RangeInclusive<u8> {
+        &self.quality
+    }
+
+    /// ディザリングを許可するか
+    pub fn dithering(&self) -> bool {
+        self.dithering
+    }
+}
+
+/// カンマ区切りの一覧を重複なしで読み込む
+fn parse_list<T: PartialEq>(
+    s: &str,
+    label: &'static str,
+    parse: impl Fn(&str) -> Result<T, ConversionOptionsError>,
+) -> Result<Vec<T>, ConversionOptionsError> {
+    let mut items = Vec::new();
+    for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+        let item = parse(name)?;
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    if items.is_empty() {
+        return Err(ConversionOptionsError::EmptyAllowList(label));
+    }
+    Ok(items)
+}
+
+/// レスポンスやログに出すフォーマットの名前（dxt1, dxt5）
+fn format_name(format: DdsFormat) -> String {
+    format.name().to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 指定しなければこれまでと同じ設定で変換する() {
+        let options = CompressionOptions::parse(None, None, false).unwrap();
+        assert_eq!(options, CompressionOptions::default());
+        assert_eq!(options.quality, 255);
+        assert_eq!(options.color_space, ColorSpace::Srgb);
+        assert!(!options.dithering);
+    }
+
+    #[test]
+    fn 品質と色空間を指定できる() {
+        let options = CompressionOptions::parse(Some(" 128 "), Some("Linear"), true).unwrap();
+        assert_eq!(options.quality, 128);
+        assert_eq!(options.color_space, ColorSpace::Linear);
+        assert!(options.dithering);
+
+        let conversion = ConversionOptions::new(DdsFormat::Dxt5, &options);
+        assert_eq!(conversion.format, DdsFormat::Dxt5);
+        assert_eq!(conversion.quality, 128);
+        assert_eq!(conversion.mipmaps, None);
+    }
+
+    #[test]
+    fn 不正な品質や色空間ならエラーを返す() {
+        assert_eq!(
+            CompressionOptions::parse(Some("256"), None, false).unwrap_err(),
+            ConversionOptionsError::InvalidQuality("256".to_string())
+        );
+        assert_eq!(
+            CompressionOptions::parse(None, Some("p3"), false).unwrap_err(),
+            ConversionOptionsError::UnsupportedColorSpace("p3".to_string())
+        );
+    }
+
+    #[test]
+    fn デフォルトの許可リストは全て許可する() {
+        let policy = ConversionPolicy::default();
+        let options = ConversionOptions {
+            format: DdsFormat::Dxt5,
+            quality: 0,
+            color_space: ColorSpace::Linear,
+            dithering: true,
+            mipmaps: None,
+        };
+        assert!(policy.check(&options).is_ok());
+    }
+
+    #[test]
+    fn 許可リストにない設定ならエラーを返す() {
+        let policy = ConversionPolicy::parse("dxt1", "64-192", "srgb", false).unwrap();
+        let allowed = ConversionOptions {
+            format: DdsFormat::Dxt1,
+            quality: 128,
+            ..Default::default()
+        };
+        assert!(policy.check(&allowed).is_ok());
+
+        let e = policy
+            .check(&ConversionOptions {
+                format: DdsFormat::Dxt5,
+                ..allowed
+            })
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "texture format dxt5 is not allowed (allowed: dxt1)"
+        );
+        assert_eq!(
+            policy
+                .check(&ConversionOptions {
+                    quality: 255,
+                    ..allowed
+                })
+                .unwrap_err(),
+            ConversionNotAllowedError::Quality {
+                quality: 255,
+                min: 64,
+                max: 192,
+            }
+        );
+        assert_eq!(
+            policy
+                .check(&ConversionOptions {
+                    color_space: ColorSpace::Linear,
+                    ..allowed
+                })
+                .unwrap_err()
+                .to_string(),
+            "color space linear is not allowed (allowed: srgb)"
+        );
+        assert_eq!(
+            policy
+                .check(&ConversionOptions {
+                    dithering: true,
+                    ..allowed
+                })
+                .unwrap_err(),
+            ConversionNotAllowedError::Dithering
+        );
+    }
+
+    #[test]
+    fn 省略したときの圧縮の設定が許可されていなければエラーを返す() {
+        assert!(ConversionPolicy::default().check_defaults().is_ok());
+        assert!(ConversionPolicy::parse("dxt1", "128-255", "srgb", false)
+            .unwrap()
+            .check_defaults()
+            .is_ok());
+        assert_eq!(
+            ConversionPolicy::parse("dxt1", "0-128", "srgb", true)
+                .unwrap()
+                .check_defaults()
+                .unwrap_err(),
+            ConversionNotAllowedError::Quality {
+                quality: 255,
+                min: 0,
+                max: 128,
+            }
+        );
+        assert_eq!(
+            ConversionPolicy::parse("dxt1,dxt5", "0-255", "linear", true)
+                .unwrap()
+                .check_defaults()
+                .unwrap_err()
+                .to_string(),
+            "color space srgb is not allowed (allowed: linear)"
+        );
+    }
+
+    #[test]
+    fn 不正な許可リストならエラーを返す() {
+        assert_eq!(
+            ConversionPolicy::parse("dxt1,bc7", "0-255", "srgb", true).unwrap_err(),
+            ConversionOptionsError::UnsupportedTextureFormat("bc7".to_string())
+        );
+        assert_eq!(
+            ConversionPolicy::parse("dxt1", "200-100", "srgb", true).unwrap_err(),
+            ConversionOptionsError::InvalidQualityRange("200-100".to_string())
+        );
+        assert_eq!(
+            ConversionPolicy::parse("dxt1", "0-255", " , ", true).unwrap_err(),
+            ConversionOptionsError::EmptyAllowList("color spaces")
+        );
+    }
+}
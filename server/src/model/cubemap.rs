@@ -0,0 +1,185 @@
+use crate::model::dds::{mark_as_cubemap, DdsHeader, DDS_HEADER_SIZE};
+use crate::model::error::CubemapError;
+use crate::model::Image;
+
+/// キューブマップの面の名前（DDS に並べる順番）
+pub const CUBEMAP_FACES: [&str; 6] = [
+    "positiveX",
+    "negativeX",
+    "positiveY",
+    "negativeY",
+    "positiveZ",
+    "negativeZ",
+];
+
+/// 6面が同じサイズの正方形か確認し、一辺のピクセル数を返す
+///
+/// 面は `CUBEMAP_FACES` の順番に並べる
+pub fn validate_faces(faces: &[Image]) -> Result<u32, CubemapError> {
+    if faces.len() != CUBEMAP_FACES.len() {
+        return Err(CubemapError::FaceCount(faces.len()));
+    }
+
+    let expected = faces[0].width;
+    for (face, image) in CUBEMAP_FACES.into_iter().zip(faces) {
+        if image.width != image.height {
+            return Err(CubemapError::NotSquare {
+                face,
+                width: image.width,
+                height: image.height,
+            });
+        }
+        if image.width != expected {
+            return Err(CubemapError::SizeMismatch {
+                face,
+                size: image.width,
+                expected,
+            });
+        }
+    }
+    Ok(expected)
+}
+
+/// 面ごとに変換した DDS を1つのキューブマップの DDS にまとめる
+///
+/// ヘッダーは最初の面のものに cubemap の caps を立てて使い、
+/// その後ろに各面のデータを `CUBEMAP_FACES` の順番で並べる
+pub fn build_cubemap_dds(faces: &[Vec<u8>]) -> Result<(DdsHeader, Vec<u8>), CubemapError> {
+    if faces.len() != CUBEMAP_FACES.len() {
+        return Err(CubemapError::FaceCount(faces.len()));
+    }
+
+    let headers = CUBEMAP_FACES
+        .into_iter()
+        .zip(faces)
+        .map(|(face, data)| {
+            DdsHeader::parse(data).map_err(|e| CubemapError::InvalidFace {
+                face,
+                reason: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some((face, _)) = CUBEMAP_FACES
+        .into_iter()
+        .zip(&headers)
+        .find(|(_, header)| **header != headers[0])
+    {
+        return Err(CubemapError::FaceMismatch { face });
+    }
+
+    let data_size: usize = faces.iter().map(|data| data.len() - DDS_HEADER_SIZE).sum();
+    let mut output = Vec::with_capacity(DDS_HEADER_SIZE + data_size);
+    output.extend_from_slice(&faces[0][..DDS_HEADER_SIZE]);
+    mark_as_cubemap(&mut output);
+    for data in faces {
+        output.extend_from_slice(&data[DDS_HEADER_SIZE..]);
+    }
+    Ok((headers[0], output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dds::test_util::build_dds;
+    use image::{DynamicImage, RgbaImage};
+
+    fn image(width: u32, height: u32) -> Image {
+        Image::encode(&DynamicImage::ImageRgba8(RgbaImage::new(width, height))).unwrap()
+    }
+
+    /// 面ごとに異なる値で埋めたデータを持つ DDS
+    fn face_dds(size: u32, fill: u8) -> Vec<u8> {
+        let mut data = build_dds(size, size, 1, b"DXT1");
+        data.extend(std::iter::repeat_n(fill, 8));
+        data
+    }
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn 同じサイズの正方形なら一辺のピクセル数を返す() {
+        let faces = vec![image(8, 8); 6];
+        assert_eq!(validate_faces(&faces).unwrap(), 8);
+    }
+
+    #[test]
+    fn 面の数が6でないならエラーを返す() {
+        let faces = vec![image(8, 8); 5];
+        assert_eq!(
+            validate_faces(&faces).unwrap_err(),
+            CubemapError::FaceCount(5)
+        );
+    }
+
+    #[test]
+    fn 正方形でない面があるならエラーを返す() {
+        let mut faces = vec![image(8, 8); 6];
+        faces[2] = image(8, 4);
+        assert_eq!(
+            validate_faces(&faces).unwrap_err(),
+            CubemapError::NotSquare {
+                face: "positiveY",
+                width: 8,
+                height: 4
+            }
+        );
+    }
+
+    #[test]
+    fn サイズの異なる面があるならエラーを返す() {
+        let mut faces = vec![image(8, 8); 6];
+        faces[5] = image(4, 4);
+        assert_eq!(
+            validate_faces(&faces).unwrap_err(),
+            CubemapError::SizeMismatch {
+                face: "negativeZ",
+                size: 4,
+                expected: 8
+            }
+        );
+    }
+
+    #[test]
+    fn キューブマップのcapsを立てて面のデータを順番に並べる() {
+        let faces: Vec<Vec<u8>> = (0..6).map(|i| face_dds(4, i)).collect();
+
+        let (header, output) = build_cubemap_dds(&faces).unwrap();
+
+        assert_eq!((header.width, header.height), (4, 4));
+        assert_eq!(output.len(), DDS_HEADER_SIZE + 6 * 8);
+        assert_eq!(read_u32(&output, 108) & 0x1008, 0x1008);
+        assert_eq!(read_u32(&output, 112), 0xFE00);
+        // NOTE: 面のデータは +X, -X, +Y, -Y, +Z, -Z の順
+        for i in 0..6 {
+            assert_eq!(output[DDS_HEADER_SIZE + i * 8], i as u8);
+        }
+        assert_eq!(DdsHeader::parse(&output).unwrap(), header);
+    }
+
+    #[test]
+    fn 変換後の面のフォーマットが異なるならエラーを返す() {
+        let mut faces: Vec<Vec<u8>> = (0..6).map(|i| face_dds(4, i)).collect();
+        faces[3] = build_dds(4, 4, 1, b"DXT5");
+
+        assert_eq!(
+            build_cubemap_dds(&faces).unwrap_err(),
+            CubemapError::FaceMismatch { face: "negativeY" }
+        );
+    }
+
+    #[test]
+    fn 変換後の面がddsでないならエラーを返す() {
+        let mut faces: Vec<Vec<u8>> = (0..6).map(|i| face_dds(4, i)).collect();
+        faces[0] = vec![0; 4];
+
+        assert!(matches!(
+            build_cubemap_dds(&faces).unwrap_err(),
+            CubemapError::InvalidFace {
+                face: "positiveX",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,463 @@
+use crate::model::error::DdsError;
+
+/// DDSファイル先頭のマジックナンバー
+pub const DDS_MAGIC: [u8; 4] = *b"DDS ";
+
+/// マジックナンバーを含むDDSヘッダーのサイズ
+pub const DDS_HEADER_SIZE: usize = 128;
+
+/// DDS_HEADER.dwFlags: 必須のフラグ (DDSD_CAPS | DDSD_HEIGHT | DDSD_WIDTH | DDSD_PIXELFORMAT)
+const DDSD_REQUIRED: u32 = 0x1 | 0x2 | 0x4 | 0x1000;
+
+/// DDS_HEADER.dwFlags: pitchOrLinearSize が圧縮後のサイズ
+const DDSD_LINEARSIZE: u32 = 0x80000;
+
+/// DDS_HEADER.dwFlags: mipMapCount が有効
+const DDSD_MIPMAPCOUNT: u32 = 0x20000;
+
+/// DDS_PIXELFORMAT.dwFlags: fourCC が有効
+const DDPF_FOURCC: u32 = 0x4;
+
+/// DDS_HEADER.dwCaps: 複数の面やミップマップを持つ
+const DDSCAPS_COMPLEX: u32 = 0x8;
+
+/// DDS_HEADER.dwCaps: テクスチャ（必須）
+const DDSCAPS_TEXTURE: u32 = 0x1000;
+
+/// DDS_HEADER.dwCaps: ミップマップを持つ
+const DDSCAPS_MIPMAP: u32 = 0x400000;
+
+/// DDS_HEADER.dwCaps2: キューブマップで、+X, -X, +Y, -Y, +Z, -Z の6面すべてを含む
+const DDSCAPS2_CUBEMAP_ALL_FACES: u32 = 0x200 | 0xFC00;
+
+/// マジックナンバーを含めた dwCaps の位置
+const CAPS_OFFSET: usize = 108;
+
+/// マジックナンバーを含めた dwCaps2 の位置
+const CAPS2_OFFSET: usize = 112;
+
+/// DX10 拡張ヘッダーが続くことを示す fourCC
+const FOUR_CC_DX10: [u8; 4] = *b"DX10";
+
+/// DX10 拡張ヘッダー (DDS_HEADER_DXT10) のサイズ
+pub const DDS_HEADER_DXT10_SIZE: usize = 20;
+
+/// DXGI_FORMAT_BC1_UNORM
+const DXGI_FORMAT_BC1_UNORM: u32 = 71;
+
+/// DXGI_FORMAT_BC3_UNORM
+const DXGI_FORMAT_BC3_UNORM: u32 = 77;
+
+/// D3D10_RESOURCE_DIMENSION_TEXTURE2D
+const RESOURCE_DIMENSION_TEXTURE2D: u32 = 3;
+
+/// DDSのピクセルフォーマット
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// BC1 (アルファなし)
+    Dxt1,
+    /// BC3 (アルファあり)
+    Dxt5,
+    /// 未対応のフォーマット (fourCC をそのまま保持)
+    Other(u32),
+}
+
+impl PixelFormat {
+    /// fourCC から判別する
+    pub fn from_four_cc(four_cc: u32) -> Self {
+        match &four_cc.to_le_bytes() {
+            b"DXT1" => PixelFormat::Dxt1,
+            b"DXT5" => PixelFormat::Dxt5,
+            _ => PixelFormat::Other(four_cc),
+        }
+    }
+
+    /// DX10 拡張ヘッダーの dxgiFormat から判別する
+    ///
+    /// 未対応のフォーマットは fourCC の代わりに "DX10" として保持する
+    pub fn from_dxgi_format(dxgi_format: u32) -> Self {
+        match dxgi_format {
+            DXGI_FORMAT_BC1_UNORM => PixelFormat::Dxt1,
+            DXGI_FORMAT_BC3_UNORM => PixelFormat::Dxt5,
+            _ => PixelFormat::Other(u32::from_le_bytes(FOUR_CC_DX10)),
+        }
+    }
+
+    /// DX10 拡張ヘッダーに書き込む dxgiFormat (未対応なら None)
+    pub fn dxgi_format(&self) -> Option<u32> {
+        match self {
+            PixelFormat::Dxt1 => Some(DXGI_FORMAT_BC1_UNORM),
+            PixelFormat::Dxt5 => Some(DXGI_FORMAT_BC3_UNORM),
+            PixelFormat::Other(_) => None,
+        }
+    }
+
+    /// Unity の TextureFormat の値 (未対応なら 0)
+    ///
+    /// Udon 側で `(TextureFormat)value` としてそのまま使えるようにする
+    pub fn unity_texture_format(&self) -> i32 {
+        match self {
+            PixelFormat::Dxt1 => 10,
+            PixelFormat::Dxt5 => 12,
+            PixelFormat::Other(_) => 0,
+        }
+    }
+
+    /// 表示用の名前（未対応のフォーマットなら fourCC の文字列）
+    pub fn name(&self) -> String {
+        match self {
+            PixelFormat::Dxt1 => "DXT1".to_string(),
+            PixelFormat::Dxt5 => "DXT5".to_string(),
+            PixelFormat::Other(0) => "unknown".to_string(),
+            PixelFormat::Other(four_cc) => {
+                String::from_utf8_lossy(&four_cc.to_le_bytes()).to_string()
+            }
+        }
+    }
+}
+
+/// 変換後のDDSのフォーマット
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DdsFormat {
+    /// BC1 (アルファなし)
+    #[default]
+    Dxt1,
+    /// BC3 (アルファあり)
+    Dxt5,
+}
+
+impl DdsFormat {
+    /// 4x4 ピクセルのブロック1つ分のバイト数
+    pub fn block_size(&self) -> usize {
+        match self {
+            DdsFormat::Dxt1 => 8,
+            DdsFormat::Dxt5 => 16,
+        }
+    }
+
+    /// 表示用の名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            DdsFormat::Dxt1 => "DXT1",
+            DdsFormat::Dxt5 => "DXT5",
+        }
+    }
+
+    /// ヘッダーに書き込む fourCC
+    pub fn four_cc(&self) -> [u8; 4] {
+        match self {
+            DdsFormat::Dxt1 => *b"DXT1",
+            DdsFormat::Dxt5 => *b"DXT5",
+        }
+    }
+}
+
+/// 指定したフォーマットで圧縮したときのDDSファイルのサイズ
+///
+/// 4x4 ピクセルのブロックごとに DXT1 は 8 byte、DXT5 は 16 byte になるので、画像サイズだけで決まる
+/// ミップマップは縦横を半分（1 未満にはしない）にしながら `mip_count` 段分を含める
+pub fn dds_file_size(width: u32, height: u32, mip_count: u32, format: DdsFormat) -> usize {
+    let mut size = DDS_HEADER_SIZE;
+    let (mut width, mut height) = (width.max(1), height.max(1));
+    for _ in 0..mip_count.max(1) {
+        size += (width.div_ceil(4) * height.div_ceil(4)) as usize * format.block_size();
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+    }
+    size
+}
+
+/// 指定したサイズとフォーマットのDDSヘッダーを作る（マジックナンバーを含めて `DDS_HEADER_SIZE` byte）
+///
+/// 後ろに大きい段から順に `mip_count` 段分のブロックを並べる必要がある
+pub fn dds_header(width: u32, height: u32, mip_count: u32, format: DdsFormat) -> Vec<u8> {
+    let mip_count = mip_count.max(1);
+    let mut flags = DDSD_REQUIRED | DDSD_LINEARSIZE;
+    let mut caps = DDSCAPS_TEXTURE;
+    if mip_count > 1 {
+        flags |= DDSD_MIPMAPCOUNT;
+        caps |= DDSCAPS_COMPLEX | DDSCAPS_MIPMAP;
+    }
+    let linear_size = dds_file_size(width, height, 1, format) - DDS_HEADER_SIZE;
+
+    let mut header = vec![0u8; DDS_HEADER_SIZE];
+    header[0..4].copy_from_slice(&DDS_MAGIC);
+    for (offset, value) in [
+        (4, 124),
+        (8, flags),
+        (12, height),
+        (16, width),
+        (20, linear_size as u32),
+        (28, mip_count),
+        // NOTE: DDS_PIXELFORMAT の dwSize
+        (76, 32),
+        (80, DDPF_FOURCC),
+        (CAPS_OFFSET, caps),
+    ] {
+        header[offset..offset + 4].copy_from_slice(&u32::to_le_bytes(value));
+    }
+    header[84..88].copy_from_slice(&format.four_cc());
+    header
+}
+
+/// ヘッダーの caps を、6面すべてを含むキューブマップのものにする
+///
+/// 面のデータはヘッダーの後ろに +X, -X, +Y, -Y, +Z, -Z の順で並べる必要がある
+pub fn mark_as_cubemap(header: &mut [u8]) {
+    let caps = u32::from_le_bytes([
+        header[CAPS_OFFSET],
+        header[CAPS_OFFSET + 1],
+        header[CAPS_OFFSET + 2],
+        header[CAPS_OFFSET + 3],
+    ]) | DDSCAPS_COMPLEX
+        | DDSCAPS_TEXTURE;
+    header[CAPS_OFFSET..CAPS_OFFSET + 4].copy_from_slice(&caps.to_le_bytes());
+    header[CAPS2_OFFSET..CAPS2_OFFSET + 4]
+        .copy_from_slice(&DDSCAPS2_CUBEMAP_ALL_FACES.to_le_bytes());
+}
+
+/// 従来のヘッダーを DX10 拡張ヘッダー付きのテクスチャ配列のヘッダーにする
+///
+/// 返すヘッダーは `DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE` byte で、
+/// 後ろに各要素のデータ（ミップマップを含む）を要素の順に並べる必要がある
+pub fn texture_array_header(header: &[u8], dxgi_format: u32, array_size: u32) -> Vec<u8> {
+    let mut output = Vec::with_capacity(DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE);
+    output.extend_from_slice(&header[..DDS_HEADER_SIZE]);
+    let pixel_format_flags = u32::from_le_bytes([output[80], output[81], output[82], output[83]]);
+    output[80..84].copy_from_slice(&(pixel_format_flags | DDPF_FOURCC).to_le_bytes());
+    output[84..88].copy_from_slice(&FOUR_CC_DX10);
+    for value in [
+        dxgi_format,
+        RESOURCE_DIMENSION_TEXTURE2D,
+        // NOTE: miscFlag (キューブマップではない), arraySize, miscFlags2 (アルファの扱いは不明)
+        0,
+        array_size,
+        0,
+    ] {
+        output.extend_from_slice(&value.to_le_bytes());
+    }
+    output
+}
+
+/// DDSヘッダーから読み取った情報
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DdsHeader {
+    /// 横幅 (px)
+    pub width: u32,
+    /// 高さ (px)
+    pub height: u32,
+    /// ミップマップの段数 (ミップマップなしなら 1)
+    pub mip_count: u32,
+    /// ピクセルフォーマット
+    pub format: PixelFormat,
+    /// テクスチャ配列の要素数 (DX10 拡張ヘッダーがなければ 1)
+    pub array_size: u32,
+}
+
+impl DdsHeader {
+    /// DDSデータの先頭からヘッダーを読み取る
+    pub fn parse(data: &[u8]) -> Result<Self, DdsError> {
+        if data.len() < DDS_HEADER_SIZE {
+            return Err(DdsError::TooShort(data.len()));
+        }
+        if data[0..4] != DDS_MAGIC {
+            return Err(DdsError::InvalidMagic);
+        }
+
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+
+        let header_size = read_u32(4);
+        if header_size != 124 {
+            return Err(DdsError::InvalidHeaderSize(header_size));
+        }
+
+        let flags = read_u32(8);
+        let mip_count = if flags & DDSD_MIPMAPCOUNT != 0 {
+            read_u32(28).max(1)
+        } else {
+            1
+        };
+
+        let pixel_format_flags = read_u32(80);
+        let four_cc = read_u32(84);
+        let (format, array_size) = if pixel_format_flags & DDPF_FOURCC == 0 {
+            (PixelFormat::Other(0), 1)
+        } else if four_cc.to_le_bytes() == FOUR_CC_DX10 {
+            if data.len() < DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE {
+                return Err(DdsError::TooShort(data.len()));
+            }
+            (
+                PixelFormat::from_dxgi_format(read_u32(128)),
+                read_u32(140).max(1),
+            )
+        } else {
+            (PixelFormat::from_four_cc(four_cc), 1)
+        };
+
+        Ok(Self {
+            width: read_u32(16),
+            height: read_u32(12),
+            mip_count,
+            format,
+            array_size,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test_util {
+    use super::*;
+
+    /// テスト用に最小限のDDSヘッダー付きデータを組み立てる
+    pub fn build_dds(width: u32, height: u32, mip_count: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut data = vec![0u8; DDS_HEADER_SIZE];
+        data[0..4].copy_from_slice(&DDS_MAGIC);
+        data[4..8].copy_from_slice(&124u32.to_le_bytes());
+        let flags = if mip_count > 1 { DDSD_MIPMAPCOUNT } else { 0 };
+        data[8..12].copy_from_slice(&flags.to_le_bytes());
+        data[12..16].copy_from_slice(&height.to_le_bytes());
+        data[16..20].copy_from_slice(&width.to_le_bytes());
+        data[28..32].copy_from_slice(&mip_count.to_le_bytes());
+        data[76..80].copy_from_slice(&32u32.to_le_bytes());
+        data[80..84].copy_from_slice(&DDPF_FOURCC.to_le_bytes());
+        data[84..88].copy_from_slice(four_cc);
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::build_dds;
+    use super::*;
+
+    #[test]
+    fn ヘッダーから情報を読み取れる() {
+        let data = build_dds(256, 128, 9, b"DXT1");
+        let header = DdsHeader::parse(&data).unwrap();
+
+        assert_eq!(header.width, 256);
+        assert_eq!(header.height, 128);
+        assert_eq!(header.mip_count, 9);
+        assert_eq!(header.format, PixelFormat::Dxt1);
+        assert_eq!(header.format.unity_texture_format(), 10);
+        assert_eq!(header.array_size, 1);
+    }
+
+    #[test]
+    fn 作ったヘッダーを読み取れる() {
+        let header = dds_header(256, 128, 9, DdsFormat::Dxt5);
+        assert_eq!(header.len(), DDS_HEADER_SIZE);
+        assert_eq!(
+            DdsHeader::parse(&header).unwrap(),
+            DdsHeader {
+                width: 256,
+                height: 128,
+                mip_count: 9,
+                format: PixelFormat::Dxt5,
+                array_size: 1,
+            }
+        );
+
+        let header = DdsHeader::parse(&dds_header(4, 4, 1, DdsFormat::Dxt1)).unwrap();
+        assert_eq!(header.mip_count, 1);
+        assert_eq!(header.format, PixelFormat::Dxt1);
+    }
+
+    #[test]
+    fn ミップマップ数のフラグがなければ1段とみなす() {
+        let data = build_dds(4, 4, 1, b"DXT5");
+        let header = DdsHeader::parse(&data).unwrap();
+
+        assert_eq!(header.mip_count, 1);
+        assert_eq!(header.format, PixelFormat::Dxt5);
+        assert_eq!(header.format.unity_texture_format(), 12);
+    }
+
+    #[test]
+    fn dxt1のサイズを画像サイズから計算できる() {
+        let dxt1_file_size =
+            |width, height, mip_count| dds_file_size(width, height, mip_count, DdsFormat::Dxt1);
+        // 8x8 = 4 ブロック
+        assert_eq!(dxt1_file_size(8, 8, 1), DDS_HEADER_SIZE + 4 * 8);
+        // 8x8 (4 ブロック) + 4x4 (1) + 2x2 (1) + 1x1 (1)
+        assert_eq!(dxt1_file_size(8, 8, 4), DDS_HEADER_SIZE + 7 * 8);
+        // 1024x1024 は 512 KB
+        assert_eq!(dxt1_file_size(1024, 1024, 1), DDS_HEADER_SIZE + 512 * 1024);
+    }
+
+    #[test]
+    fn dxt5のサイズはdxt1の倍になる() {
+        assert_eq!(
+            dds_file_size(8, 8, 4, DdsFormat::Dxt5),
+            DDS_HEADER_SIZE + 7 * 16
+        );
+    }
+
+    #[test]
+    fn フォーマット名を返す() {
+        assert_eq!(PixelFormat::Dxt1.name(), "DXT1");
+        assert_eq!(
+            PixelFormat::from_four_cc(u32::from_le_bytes(*b"ATI2")).name(),
+            "ATI2"
+        );
+        assert_eq!(PixelFormat::Other(0).name(), "unknown");
+    }
+
+    #[test]
+    fn 短すぎるならエラーを返す() {
+        let result = DdsHeader::parse(&[0; 10]);
+        assert_eq!(result.unwrap_err(), DdsError::TooShort(10));
+    }
+
+    #[test]
+    fn マジックナンバーがなければエラーを返す() {
+        let mut data = build_dds(4, 4, 1, b"DXT1");
+        data[0] = b'X';
+        let result = DdsHeader::parse(&data);
+        assert_eq!(result.unwrap_err(), DdsError::InvalidMagic);
+    }
+
+    #[test]
+    fn ヘッダーサイズが不正ならエラーを返す() {
+        let mut data = build_dds(4, 4, 1, b"DXT1");
+        data[4..8].copy_from_slice(&100u32.to_le_bytes());
+        let result = DdsHeader::parse(&data);
+        assert_eq!(result.unwrap_err(), DdsError::InvalidHeaderSize(100));
+    }
+
+    #[test]
+    fn dx10拡張ヘッダーからフォーマットと要素数を読み取れる() {
+        let legacy = build_dds(8, 4, 1, b"DXT5");
+        let data = texture_array_header(&legacy, DXGI_FORMAT_BC3_UNORM, 3);
+        let header = DdsHeader::parse(&data).unwrap();
+
+        assert_eq!(data.len(), DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE);
+        assert_eq!(&data[84..88], b"DX10");
+        assert_eq!((header.width, header.height), (8, 4));
+        assert_eq!(header.format, PixelFormat::Dxt5);
+        assert_eq!(header.array_size, 3);
+    }
+
+    #[test]
+    fn dx10拡張ヘッダーが途中で切れていればエラーを返す() {
+        let data = build_dds(4, 4, 1, b"DX10");
+        let result = DdsHeader::parse(&data);
+        assert_eq!(result.unwrap_err(), DdsError::TooShort(DDS_HEADER_SIZE));
+    }
+
+    #[test]
+    fn 未対応のフォーマットならotherになる() {
+        let data = build_dds(4, 4, 1, b"ATI2");
+        let header = DdsHeader::parse(&data).unwrap();
+
+        assert!(matches!(header.format, PixelFormat::Other(_)));
+        assert_eq!(header.format.unity_texture_format(), 0);
+    }
+}
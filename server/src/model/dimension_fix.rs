@@ -0,0 +1,124 @@
+use crate::model::error::DimensionFixError;
+
+/// 余白の色を指定しなかった場合の色（不透明な黒）
+const DEFAULT_PAD_COLOR: [u8; 4] = [0, 0, 0, 255];
+
+/// 縦横のピクセル数が4の倍数でない画像の補正方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DimensionFix {
+    /// 右端と下端に指定した色 (RGBA) の余白を足して、切り上げた4の倍数にする
+    Pad { color: [u8; 4] },
+    /// 中央を残して、切り捨てた4の倍数に切り抜く
+    Crop,
+    /// 最も近い4の倍数に拡大縮小する
+    Resize,
+}
+
+impl DimensionFix {
+    /// 補正方法の名前と余白の色から作る
+    ///
+    /// 余白の色は `#RRGGBB` または `#RRGGBBAA` で、pad 以外では使わない
+    pub fn parse(name: &str, pad_color: Option<&str>) -> Result<Self, DimensionFixError> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "pad" => {
+                let color = match pad_color {
+                    Some(color) => parse_color(color)?,
+                    None => DEFAULT_PAD_COLOR,
+                };
+                Ok(DimensionFix::Pad { color })
+            }
+            "crop" => Ok(DimensionFix::Crop),
+            "resize" => Ok(DimensionFix::Resize),
+            _ => Err(DimensionFixError::UnsupportedStrategy(name.to_string())),
+        }
+    }
+
+    /// 補正方法の名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            DimensionFix::Pad { .. } => "pad",
+            DimensionFix::Crop => "crop",
+            DimensionFix::Resize => "resize",
+        }
+    }
+}
+
+/// `#RRGGBB` または `#RRGGBBAA` を RGBA に変換する
+fn parse_color(s: &str) -> Result<[u8; 4], DimensionFixError> {
+    let invalid = || DimensionFixError::InvalidColor(s.to_string());
+    let hex = s.trim().strip_prefix('#').ok_or_else(invalid)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid());
+    }
+
+    let mut color = DEFAULT_PAD_COLOR;
+    for (index, channel) in color.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(color)
+}
+
+/// 縦横のピクセル数を補正した結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedDimensions {
+    /// 適用した補正方法
+    pub strategy: DimensionFix,
+    /// 補正前の横幅 (px)
+    pub original_width: u32,
+    /// 補正前の高さ (px)
+    pub original_height: u32,
+    /// 補正後の横幅 (px)
+    pub width: u32,
+    /// 補正後の高さ (px)
+    pub height: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 名前から補正方法を判別できる() {
+        assert_eq!(DimensionFix::parse("crop", None).unwrap(), DimensionFix::Crop);
+        assert_eq!(
+            DimensionFix::parse(" Resize ", None).unwrap(),
+            DimensionFix::Resize
+        );
+        assert_eq!(
+            DimensionFix::parse("pad", None).unwrap(),
+            DimensionFix::Pad {
+                color: DEFAULT_PAD_COLOR
+            }
+        );
+        assert_eq!(
+            DimensionFix::parse("stretch", None).unwrap_err(),
+            DimensionFixError::UnsupportedStrategy("stretch".to_string())
+        );
+    }
+
+    #[test]
+    fn 余白の色を指定できる() {
+        assert_eq!(
+            DimensionFix::parse("pad", Some("#ff8000")).unwrap(),
+            DimensionFix::Pad {
+                color: [255, 128, 0, 255]
+            }
+        );
+        assert_eq!(
+            DimensionFix::parse("pad", Some("#FFFFFF00")).unwrap(),
+            DimensionFix::Pad {
+                color: [255, 255, 255, 0]
+            }
+        );
+    }
+
+    #[test]
+    fn 余白の色が不正ならエラーを返す() {
+        for color in ["ff8000", "#ff80", "#gg8000", "#ff8000ff00"] {
+            assert_eq!(
+                DimensionFix::parse("pad", Some(color)).unwrap_err(),
+                DimensionFixError::InvalidColor(color.to_string())
+            );
+        }
+    }
+}
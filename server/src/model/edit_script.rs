@@ -0,0 +1,265 @@
+use serde_json::{Map, Value};
+
+use crate::model::error::EditScriptError;
+
+/// 束ねたファイルに対する1件の編集操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditOperation {
+    /// index 枚目の位置に新しい画像を挿入する（index が枚数と同じなら末尾に追加）
+    Insert {
+        index: usize,
+        /// 挿入する画像のメタデータ（JSON オブジェクト）
+        metadata: Option<Value>,
+    },
+    /// index 枚目の画像を削除する
+    Delete { index: usize },
+    /// from 枚目の画像を取り除き、to 枚目の位置に入れ直す
+    Move { from: usize, to: usize },
+}
+
+/// 編集後の各画像が、どこから来たものか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntrySource {
+    /// 既存ファイルの指定枚目
+    Existing(usize),
+    /// 挿入した画像のうち指定番目（アップロードされた画像の順番と対応する）
+    Inserted(usize),
+}
+
+/// 束ねたファイルに対する編集操作の一覧
+///
+/// `[{"op": "insert", "index": 0}, {"op": "delete", "index": 2}, {"op": "move", "from": 3, "to": 0}]`
+/// のような JSON オブジェクトの配列で、先頭から順番に適用する
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditScript {
+    operations: Vec<EditOperation>,
+}
+
+impl EditScript {
+    /// 編集操作の一覧
+    #[cfg(test)]
+    pub fn operations(&self) -> &[EditOperation] {
+        &self.operations
+    }
+
+    /// 挿入操作の件数（アップロードが必要な画像の枚数）
+    pub fn insert_count(&self) -> usize {
+        self.inserted_metadata().count()
+    }
+
+    /// 挿入操作で指定されたメタデータ（挿入する画像の順番）
+    pub fn inserted_metadata(&self) -> impl Iterator<Item = Option<&Value>> {
+        self.operations
+            .iter()
+            .filter_map(|operation| match operation {
+                EditOperation::Insert { metadata, .. } => Some(metadata.as_ref()),
+                _ => None,
+            })
+    }
+
+    /// 既存ファイルの枚数に編集操作を順番に適用し、編集後の各画像の出どころを返す
+    pub fn apply(&self, len: usize) -> Result<Vec<EntrySource>, EditScriptError> {
+        let mut sources: Vec<EntrySource> = (0..len).map(EntrySource::Existing).collect();
+        let mut inserted = 0;
+
+        for (step, operation) in self.operations.iter().enumerate() {
+            let out_of_range =
+                |index: usize, len: usize| EditScriptError::OutOfRange { step, index, len };
+            match *operation {
+                EditOperation::Insert { index, .. } => {
+                    if index > sources.len() {
+                        return Err(out_of_range(index, sources.len()));
+                    }
+                    sources.insert(index, EntrySource::Inserted(inserted));
+                    inserted += 1;
+                }
+                EditOperation::Delete { index } => {
+                    if index >= sources.len() {
+                        return Err(out_of_range(index, sources.len()));
+                    }
+                    sources.remove(index);
+                }
+                EditOperation::Move { from, to } => {
+                    if from >= sources.len() {
+                        return Err(out_of_range(from, sources.len()));
+                    }
+                    if to >= sources.len() {
+                        return Err(out_of_range(to, sources.len()));
+                    }
+                    let source = sources.remove(from);
+                    sources.insert(to, source);
+                }
+            }
+        }
+
+        if sources.is_empty() {
+            return Err(EditScriptError::EmptyResult);
+        }
+        Ok(sources)
+    }
+}
+
+impl std::str::FromStr for EditScript {
+    type Err = EditScriptError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: Value =
+            serde_json::from_str(s).map_err(|e| EditScriptError::InvalidJson(e.to_string()))?;
+
+        let Value::Array(entries) = value else {
+            return Err(EditScriptError::NotArray);
+        };
+        if entries.is_empty() {
+            return Err(EditScriptError::Empty);
+        }
+
+        let operations = entries
+            .iter()
+            .enumerate()
+            .map(|(step, entry)| {
+                parse_operation(entry)
+                    .map_err(|reason| EditScriptError::InvalidOperation { step, reason })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { operations })
+    }
+}
+
+/// 1件の編集操作を読み取る（エラー時は理由を返す）
+fn parse_operation(entry: &Value) -> Result<EditOperation, String> {
+    let Value::Object(entry) = entry else {
+        return Err("operation must be a json object".to_string());
+    };
+
+    match entry.get("op").and_then(Value::as_str) {
+        Some("insert") => {
+            let metadata = match entry.get("metadata") {
+                None | Some(Value::Null) => None,
+                Some(metadata @ Value::Object(_)) => Some(metadata.clone()),
+                Some(_) => return Err("metadata must be a json object".to_string()),
+            };
+            Ok(EditOperation::Insert {
+                index: index_of(entry, "index")?,
+                metadata,
+            })
+        }
+        Some("delete") => Ok(EditOperation::Delete {
+            index: index_of(entry, "index")?,
+        }),
+        Some("move") => Ok(EditOperation::Move {
+            from: index_of(entry, "from")?,
+            to: index_of(entry, "to")?,
+        }),
+        _ => Err("op must be insert, delete or move".to_string()),
+    }
+}
+
+/// 0 以上の整数のフィールドを読み取る
+fn index_of(entry: &Map<String, Value>, key: &str) -> Result<usize, String> {
+    entry
+        .get(key)
+        .and_then(Value::as_u64)
+        .map(|index| index as usize)
+        .ok_or_else(|| format!("{} must be a non-negative integer", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::str::FromStr;
+
+    #[test]
+    fn 操作の配列なら読み取れる() {
+        let script = EditScript::from_str(
+            r#"[{"op": "insert", "index": 0, "metadata": {"title": "A"}}, {"op": "delete", "index": 2}, {"op": "move", "from": 3, "to": 0}]"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            script.operations(),
+            &[
+                EditOperation::Insert {
+                    index: 0,
+                    metadata: Some(json!({"title": "A"})),
+                },
+                EditOperation::Delete { index: 2 },
+                EditOperation::Move { from: 3, to: 0 },
+            ]
+        );
+        assert_eq!(script.insert_count(), 1);
+    }
+
+    #[test]
+    fn 不正な操作ならエラーを返す() {
+        assert!(matches!(
+            EditScript::from_str("not json"),
+            Err(EditScriptError::InvalidJson(_))
+        ));
+        assert_eq!(
+            EditScript::from_str(r#"{"op": "delete", "index": 0}"#).unwrap_err(),
+            EditScriptError::NotArray
+        );
+        assert_eq!(
+            EditScript::from_str("[]").unwrap_err(),
+            EditScriptError::Empty
+        );
+        assert_eq!(
+            EditScript::from_str(r#"[{"op": "delete", "index": 0}, {"op": "swap"}]"#).unwrap_err(),
+            EditScriptError::InvalidOperation {
+                step: 1,
+                reason: "op must be insert, delete or move".to_string(),
+            }
+        );
+        assert_eq!(
+            EditScript::from_str(r#"[{"op": "move", "from": 0, "to": -1}]"#).unwrap_err(),
+            EditScriptError::InvalidOperation {
+                step: 0,
+                reason: "to must be a non-negative integer".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn 先頭から順番に適用する() {
+        let script = EditScript::from_str(
+            r#"[{"op": "insert", "index": 3}, {"op": "delete", "index": 0}, {"op": "move", "from": 2, "to": 0}, {"op": "insert", "index": 1}]"#,
+        )
+        .unwrap();
+
+        // [0, 1, 2] -> [0, 1, 2, new0] -> [1, 2, new0] -> [new0, 1, 2] -> [new0, new1, 1, 2]
+        assert_eq!(
+            script.apply(3).unwrap(),
+            vec![
+                EntrySource::Inserted(0),
+                EntrySource::Inserted(1),
+                EntrySource::Existing(1),
+                EntrySource::Existing(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn 範囲外の位置を指定したならエラーを返す() {
+        let script =
+            EditScript::from_str(r#"[{"op": "delete", "index": 0}, {"op": "delete", "index": 1}]"#)
+                .unwrap();
+
+        assert_eq!(
+            script.apply(2).unwrap_err(),
+            EditScriptError::OutOfRange {
+                step: 1,
+                index: 1,
+                len: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn 全て削除したならエラーを返す() {
+        let script = EditScript::from_str(r#"[{"op": "delete", "index": 0}]"#).unwrap();
+
+        assert_eq!(script.apply(1).unwrap_err(), EditScriptError::EmptyResult);
+    }
+}
@@ -0,0 +1,103 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::model::error::EncodingError;
+
+/// ストレージに置くファイルのエンコーディング
+///
+/// VRChat の String Loading は文字列として受け取るため、
+/// バイナリをそのまま渡せない場合は Base64 にする
+/// Udon 側では `Convert.FromBase64String` で元のバイナリに戻せる
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputEncoding {
+    /// バイナリのまま
+    #[default]
+    Binary,
+    /// Base64（標準のアルファベット、パディングあり）
+    Base64,
+}
+
+impl OutputEncoding {
+    /// バイナリをこのエンコーディングに変換する
+    pub fn encode(&self, data: Vec<u8>) -> Vec<u8> {
+        match self {
+            OutputEncoding::Binary => data,
+            OutputEncoding::Base64 => STANDARD.encode(data).into_bytes(),
+        }
+    }
+
+    /// 指定したサイズのバイナリをエンコードした後のサイズ
+    pub fn encoded_len(&self, len: usize) -> usize {
+        match self {
+            OutputEncoding::Binary => len,
+            OutputEncoding::Base64 => len.div_ceil(3) * 4,
+        }
+    }
+
+    /// このエンコーディングのデータをバイナリに戻す
+    pub fn decode(&self, data: Vec<u8>) -> Result<Vec<u8>, EncodingError> {
+        match self {
+            OutputEncoding::Binary => Ok(data),
+            OutputEncoding::Base64 => STANDARD
+                .decode(data.trim_ascii())
+                .map_err(|e| EncodingError::InvalidBase64(e.to_string())),
+        }
+    }
+}
+
+impl std::str::FromStr for OutputEncoding {
+    type Err = EncodingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "binary" => Ok(OutputEncoding::Binary),
+            "base64" => Ok(OutputEncoding::Base64),
+            _ => Err(EncodingError::UnsupportedEncoding(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn base64でエンコードして元に戻せる() {
+        let data = vec![0, 1, 2, 0xfe, 0xff];
+        let encoded = OutputEncoding::Base64.encode(data.clone());
+
+        assert_eq!(encoded, b"AAEC/v8=");
+        assert_eq!(OutputEncoding::Base64.encoded_len(data.len()), encoded.len());
+        assert_eq!(OutputEncoding::Base64.decode(encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn binaryならそのまま返す() {
+        let data = vec![0, 1, 2];
+        assert_eq!(OutputEncoding::Binary.encode(data.clone()), data);
+        assert_eq!(OutputEncoding::Binary.decode(data.clone()).unwrap(), data);
+    }
+
+    #[test]
+    fn base64として不正ならエラーを返す() {
+        let result = OutputEncoding::Base64.decode(b"not base64!".to_vec());
+        assert!(matches!(result, Err(EncodingError::InvalidBase64(_))));
+    }
+
+    #[test]
+    fn 名前から判別できる() {
+        assert_eq!(
+            OutputEncoding::from_str("base64").unwrap(),
+            OutputEncoding::Base64
+        );
+        assert_eq!(
+            OutputEncoding::from_str(" Binary ").unwrap(),
+            OutputEncoding::Binary
+        );
+        assert_eq!(
+            OutputEncoding::from_str("hex").unwrap_err(),
+            EncodingError::UnsupportedEncoding("hex".to_string())
+        );
+    }
+}
@@ -14,4 +14,383 @@ pub enum ImageError {
     /// ピクセル数が4の倍数でない
     #[error("image dimensions must be multiples of 4 (width: {width}, height: {height})")]
     InvalidDimensions { width: u32, height: u32 },
+
+    /// 画像のエンコードエラー
+    #[error("failed to encode image: {0}")]
+    EncodeError(String),
+}
+
+/// 独自形式ファイル読み取り時のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MergedFileError {
+    /// データが空
+    #[error("merged file is empty")]
+    EmptyData,
+
+    /// ヘッダーが途中で切れている
+    #[error("header is truncated (size: {actual} bytes)")]
+    TruncatedHeader { actual: usize },
+
+    /// 未対応のフォーマットバージョン
+    #[error("unsupported format version: {0}")]
+    UnsupportedVersion(i32),
+
+    /// 未対応のヘッダーフラグ
+    #[error("unsupported header flags: {0:#010x}")]
+    UnsupportedFlags(u32),
+
+    /// 画像枚数が0以下
+    #[error("texture count must be positive (count: {0})")]
+    InvalidCount(i32),
+
+    /// 画像枚数に対してサイズ一覧が足りない
+    #[error("texture count does not match size list (count: {count}, available: {available})")]
+    CountMismatch { count: usize, available: usize },
+
+    /// データサイズが負数
+    #[error("data size must not be negative (index: {index}, size: {size})")]
+    NegativeSize { index: usize, size: i32 },
+
+    /// データ位置が負数
+    #[error("data offset must not be negative (index: {index}, offset: {offset})")]
+    NegativeOffset { index: usize, offset: i32 },
+
+    /// データ部がサイズ一覧の合計より短い
+    #[error("data is truncated (expected: {expected} bytes, actual: {actual} bytes)")]
+    TruncatedData { expected: usize, actual: usize },
+
+    /// メタデータセクションが不正
+    #[error("metadata section is invalid: {0}")]
+    InvalidMetadata(String),
+
+    /// 画像ごとのチェックサムが一致しない
+    #[error("checksum mismatch at index {index} (expected: {expected:#010x}, actual: {actual:#010x})")]
+    ChecksumMismatch {
+        index: usize,
+        expected: u32,
+        actual: u32,
+    },
+
+    /// ファイル全体のチェックサムが一致しない
+    #[error("file checksum mismatch (expected: {expected:#010x}, actual: {actual:#010x})")]
+    FileChecksumMismatch { expected: u32, actual: u32 },
+
+    /// データ部の後ろに余分なデータがある
+    #[error("unexpected trailing data (expected: {expected} bytes, actual: {actual} bytes)")]
+    TrailingData { expected: usize, actual: usize },
+}
+
+/// DDSヘッダー読み取り時のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DdsError {
+    /// ヘッダーに満たない長さ
+    #[error("dds data is too short (size: {0} bytes)")]
+    TooShort(usize),
+
+    /// 先頭が "DDS " でない
+    #[error("dds magic number is missing")]
+    InvalidMagic,
+
+    /// ヘッダーサイズが不正
+    #[error("invalid dds header size: {0}")]
+    InvalidHeaderSize(u32),
+}
+
+/// 画像メタデータのエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MetadataError {
+    /// JSON として解釈できない
+    #[error("metadata is not valid json: {0}")]
+    InvalidJson(String),
+
+    /// JSON の配列でない
+    #[error("metadata must be a json array")]
+    NotArray,
+
+    /// 配列の要素がオブジェクトでない
+    #[error("metadata entry at index {0} must be a json object")]
+    EntryNotObject(usize),
+}
+
+/// 出力エンコーディングのエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EncodingError {
+    /// 未対応のエンコーディング名
+    #[error("unsupported encoding: {0} (expected: binary or base64)")]
+    UnsupportedEncoding(String),
+
+    /// Base64 として解釈できない
+    #[error("data is not valid base64: {0}")]
+    InvalidBase64(String),
+}
+
+/// 縦横のピクセル数の補正方法のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DimensionFixError {
+    /// 未対応の補正方法
+    #[error("unsupported dimension fix: {0} (expected: pad, crop or resize)")]
+    UnsupportedStrategy(String),
+
+    /// 余白の色として解釈できない
+    #[error("invalid pad color: {0} (expected: #RRGGBB or #RRGGBBAA)")]
+    InvalidColor(String),
+}
+
+/// 束ねたファイルの編集操作のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum EditScriptError {
+    /// JSON として解釈できない
+    #[error("edit script is not valid json: {0}")]
+    InvalidJson(String),
+
+    /// JSON の配列でない
+    #[error("edit script must be a json array")]
+    NotArray,
+
+    /// 操作が1件もない
+    #[error("edit script must contain at least one operation")]
+    Empty,
+
+    /// 操作として解釈できない
+    #[error("operation {step} is invalid: {reason}")]
+    InvalidOperation { step: usize, reason: String },
+
+    /// 操作の時点の枚数に対して位置が範囲外
+    #[error("operation {step}: index {index} is out of range (texture count: {len})")]
+    OutOfRange { step: usize, index: usize, len: usize },
+
+    /// 編集後に画像が1枚も残らない
+    #[error("edit script must leave at least one texture")]
+    EmptyResult,
+}
+
+/// アトラスへの詰め込みのエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AtlasError {
+    /// 画像が1枚もない
+    #[error("images must not be empty")]
+    Empty,
+
+    /// アトラスの一辺の最大ピクセル数が不正
+    #[error("max size must be a multiple of 4 between 4 and {max} (max size: {actual})")]
+    InvalidMaxSize { actual: u32, max: u32 },
+
+    /// 画像がアトラスの最大サイズより大きい
+    #[error("image at index {index} is larger than max size (width: {width}, height: {height}, max size: {max_size})")]
+    TooLarge {
+        index: usize,
+        width: u32,
+        height: u32,
+        max_size: u32,
+    },
+
+    /// 画像として解釈できない
+    #[error("image at index {index} is invalid: {reason}")]
+    InvalidImage { index: usize, reason: String },
+
+    /// アトラスのエンコードに失敗した
+    #[error("failed to encode atlas: {0}")]
+    EncodeError(String),
+}
+
+/// スプライトシート作成のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FlipbookError {
+    /// フレームが1枚もない
+    #[error("frames must not be empty")]
+    Empty,
+
+    /// アニメーション画像として解釈できない
+    #[error("animation is invalid: {0}")]
+    InvalidAnimation(String),
+
+    /// フレームの画像として解釈できない
+    #[error("frame {index} is invalid: {reason}")]
+    InvalidFrame { index: usize, reason: String },
+
+    /// フレームのサイズが先頭のフレームと異なる
+    #[error("frame {index} size must match the first frame (expected: {expected_width}x{expected_height}, actual: {width}x{height})")]
+    FrameSizeMismatch {
+        index: usize,
+        width: u32,
+        height: u32,
+        expected_width: u32,
+        expected_height: u32,
+    },
+
+    /// スプライトシートが最大サイズを超える
+    #[error("sprite sheet is too large (width: {width}, height: {height}, max size: {max_size})")]
+    TooLarge {
+        width: u32,
+        height: u32,
+        max_size: u32,
+    },
+
+    /// スプライトシートのエンコードに失敗した
+    #[error("failed to encode sprite sheet: {0}")]
+    EncodeError(String),
+}
+
+/// キューブマップ作成時のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CubemapError {
+    /// 面の数が6でない
+    #[error("cubemap requires exactly 6 faces (count: {0})")]
+    FaceCount(usize),
+
+    /// 面が正方形でない
+    #[error("face {face} must be square (width: {width}, height: {height})")]
+    NotSquare {
+        face: &'static str,
+        width: u32,
+        height: u32,
+    },
+
+    /// 面のサイズが最初の面と異なる
+    #[error("face {face} size must match the first face (expected: {expected}, actual: {size})")]
+    SizeMismatch {
+        face: &'static str,
+        size: u32,
+        expected: u32,
+    },
+
+    /// 変換後の面が DDS として読み取れない
+    #[error("converted face {face} is invalid: {reason}")]
+    InvalidFace { face: &'static str, reason: String },
+
+    /// 変換後の面のサイズ・フォーマット・ミップマップ数が最初の面と異なる
+    #[error("converted face {face} must have the same size, format and mip count as the first face")]
+    FaceMismatch { face: &'static str },
+}
+
+/// テクスチャ配列作成時のエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TextureArrayError {
+    /// 画像がない
+    #[error("texture array requires at least one image")]
+    Empty,
+
+    /// 要素数が上限を超えている
+    #[error("texture array must have at most {max} images (count: {count})")]
+    TooManyLayers { count: usize, max: usize },
+
+    /// 画像のサイズが最初の画像と異なる
+    #[error("texture array requires all images to be the same size (image at index {index}: {width}x{height}, first image: {expected_width}x{expected_height})")]
+    SizeMismatch {
+        index: usize,
+        width: u32,
+        height: u32,
+        expected_width: u32,
+        expected_height: u32,
+    },
+
+    /// 変換後の画像が DDS として読み取れない
+    #[error("converted image at index {index} is invalid: {reason}")]
+    InvalidLayer { index: usize, reason: String },
+
+    /// 変換後の画像のサイズ・フォーマット・ミップマップ数が最初の画像と異なる
+    #[error("converted image at index {index} must have the same size, format and mip count as the first image")]
+    LayerMismatch { index: usize },
+
+    /// DX10 拡張ヘッダーに書き込めないフォーマット
+    #[error("format {0} cannot be used in texture array (expected: DXT1 or DXT5)")]
+    UnsupportedFormat(String),
+}
+
+/// 変換後のフォーマット指定の解析エラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TextureFormatError {
+    /// 未対応のフォーマット
+    #[error("unsupported format: {0} (expected: auto, dxt1 or dxt5)")]
+    UnsupportedFormat(String),
+}
+
+/// 入力画像の形式の許可リストの解析エラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InputFormatError {
+    /// 未知の画像形式
+    #[error("unknown image format: {0}")]
+    UnknownFormat(String),
+
+    /// 許可リストが空
+    #[error("allowed image formats must not be empty")]
+    Empty,
+}
+
+/// 許可されていない形式の画像が入力された
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error(
+    "{}unsupported image format: {detected} (allowed: {})",
+    .index.map(|index| format!("image at index {}: ", index)).unwrap_or_default(),
+    .allowed.join(", ")
+)]
+pub struct UnsupportedFormatError {
+    /// 何枚目の画像か（1枚だけの場合は None）
+    pub index: Option<usize>,
+    /// マジックバイトから判定した形式
+    pub detected: String,
+    /// 許可されている形式
+    pub allowed: Vec<String>,
+}
+
+/// ミップマップ指定の解析エラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MipmapError {
+    /// 最小サイズが正の整数でない
+    #[error("invalid min mip size: {0} (expected: positive integer)")]
+    InvalidMinSize(String),
+
+    /// 未対応のフィルター
+    #[error("unsupported mip filter: {0} (expected: box, tent, lanczos4, mitchell or kaiser)")]
+    UnsupportedFilter(String),
+}
+
+/// 変換の設定の解析エラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConversionOptionsError {
+    /// 品質が 0 から 255 の整数でない
+    #[error("invalid quality: {0} (expected: integer between 0 and 255)")]
+    InvalidQuality(String),
+
+    /// 未対応の色空間
+    #[error("unsupported color space: {0} (expected: srgb or linear)")]
+    UnsupportedColorSpace(String),
+
+    /// 許可する品質の範囲が `MIN-MAX` の形式でない
+    #[error("invalid quality range: {0} (expected: MIN-MAX between 0 and 255)")]
+    InvalidQualityRange(String),
+
+    /// 未対応の変換後のフォーマット
+    #[error("unsupported texture format: {0} (expected: dxt1 or dxt5)")]
+    UnsupportedTextureFormat(String),
+
+    /// 許可する一覧が空
+    #[error("allowed {0} must not be empty")]
+    EmptyAllowList(&'static str),
+}
+
+/// サーバーで許可されていない変換の設定が指定されたエラー
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConversionNotAllowedError {
+    /// 許可されていない変換後のフォーマット
+    #[error("texture format {format} is not allowed (allowed: {})", allowed.join(", "))]
+    Format {
+        format: String,
+        allowed: Vec<String>,
+    },
+
+    /// 許可されている範囲外の品質
+    #[error("quality {quality} is not allowed (allowed: {min}-{max})")]
+    Quality { quality: u8, min: u8, max: u8 },
+
+    /// 許可されていない色空間
+    #[error("color space {color_space} is not allowed (allowed: {})", allowed.join(", "))]
+    ColorSpace {
+        color_space: String,
+        allowed: Vec<String>,
+    },
+
+    /// ディザリングが許可されていない
+    #[error("dithering is not allowed")]
+    Dithering,
 }
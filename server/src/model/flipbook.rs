@@ -0,0 +1,323 @@
+use std::io::Cursor;
+
+use image::codecs::gif::GifDecoder;
+use image::codecs::png::PngDecoder;
+use image::{AnimationDecoder, DynamicImage, Frame, RgbaImage};
+
+use crate::model::error::FlipbookError;
+use crate::model::Image;
+
+/// スプライトシートの一辺の最大ピクセル数
+pub const SPRITE_SHEET_MAX_SIZE: u32 = 8192;
+
+/// 連番画像で表示時間を指定しなかった場合の1フレームの表示時間 (ms)
+pub const DEFAULT_FRAME_DURATION_MS: u32 = 100;
+
+/// GIF のマジックバイト
+const GIF_MAGIC: &[u8] = b"GIF8";
+
+/// PNG のマジックバイト
+const PNG_MAGIC: &[u8] = b"\x89PNG";
+
+/// デコードしたフレームと表示時間
+#[derive(Debug, Clone)]
+pub struct DecodedFrames {
+    /// 各フレームの画像
+    pub frames: Vec<RgbaImage>,
+    /// 各フレームの表示時間 (ms)
+    pub durations: Vec<u32>,
+}
+
+/// スプライトシートの格子の配置
+///
+/// フレームは左上から右へ、行が埋まったら次の行へ並べる
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlipbookLayout {
+    /// フレーム数
+    pub frame_count: usize,
+    /// 列数
+    pub columns: u32,
+    /// 行数
+    pub rows: u32,
+    /// 1フレームの横幅 (px)
+    pub frame_width: u32,
+    /// 1フレームの高さ (px)
+    pub frame_height: u32,
+    /// 1マスの横幅（フレームの横幅を4の倍数に切り上げたもの）
+    pub cell_width: u32,
+    /// 1マスの高さ（フレームの高さを4の倍数に切り上げたもの）
+    pub cell_height: u32,
+    /// 各フレームの表示時間 (ms)
+    pub durations: Vec<u32>,
+}
+
+impl FlipbookLayout {
+    /// スプライトシート全体の横幅 (px)
+    pub fn sheet_width(&self) -> u32 {
+        self.columns * self.cell_width
+    }
+
+    /// スプライトシート全体の高さ (px)
+    pub fn sheet_height(&self) -> u32 {
+        self.rows * self.cell_height
+    }
+}
+
+/// アニメーション GIF または APNG をフレームごとにデコードする
+///
+/// 各フレームは前のフレームと合成済みの、画像全体の大きさのもの
+pub fn decode_animation(data: &[u8]) -> Result<DecodedFrames, FlipbookError> {
+    let invalid = |e: image::ImageError| FlipbookError::InvalidAnimation(e.to_string());
+    let frames: Vec<Frame> = if data.starts_with(GIF_MAGIC) {
+        GifDecoder::new(Cursor::new(data))
+            .map_err(invalid)?
+            .into_frames()
+            .collect_frames()
+            .map_err(invalid)?
+    } else if data.starts_with(PNG_MAGIC) {
+        let decoder = PngDecoder::new(Cursor::new(data)).map_err(invalid)?;
+        if !decoder.is_apng().map_err(invalid)? {
+            return Err(FlipbookError::InvalidAnimation(
+                "png is not animated".to_string(),
+            ));
+        }
+        decoder
+            .apng()
+            .map_err(invalid)?
+            .into_frames()
+            .collect_frames()
+            .map_err(invalid)?
+    } else {
+        return Err(FlipbookError::InvalidAnimation(
+            "only gif and apng are supported".to_string(),
+        ));
+    };
+
+    let durations = frames
+        .iter()
+        .map(|frame| {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            numer / denom.max(1)
+        })
+        .collect();
+    let frames = frames.into_iter().map(Frame::into_buffer).collect();
+    Ok(DecodedFrames { frames, durations })
+}
+
+/// 連番画像をデコードする
+pub fn decode_frames(images: &[Vec<u8>]) -> Result<Vec<RgbaImage>, FlipbookError> {
+    images
+        .iter()
+        .enumerate()
+        .map(|(index, data)| {
+            if data.is_empty() {
+                return Err(FlipbookError::InvalidFrame {
+                    index,
+                    reason: "image data is empty".to_string(),
+                });
+            }
+            image::load_from_memory(data)
+                .map(|img| img.to_rgba8())
+                .map_err(|e| FlipbookError::InvalidFrame {
+                    index,
+                    reason: e.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// フレームを正方形に近い格子に並べたスプライトシートを作る
+///
+/// 各マスは4の倍数に切り上げるので、DXT の 4x4 ブロックに別のフレームが混ざらない
+/// マスの余白は透明で、PNG でエンコードする
+pub fn build_sprite_sheet(
+    frames: &[RgbaImage],
+    durations: Vec<u32>,
+) -> Result<(FlipbookLayout, Image), FlipbookError> {
+    let first = frames.first().ok_or(FlipbookError::Empty)?;
+    let (frame_width, frame_height) = first.dimensions();
+    if let Some((index, frame)) = frames
+        .iter()
+        .enumerate()
+        .find(|(_, frame)| frame.dimensions() != (frame_width, frame_height))
+    {
+        return Err(FlipbookError::FrameSizeMismatch {
+            index,
+            width: frame.width(),
+            height: frame.height(),
+            expected_width: frame_width,
+            expected_height: frame_height,
+        });
+    }
+
+    let frame_count = frames.len();
+    let columns = (frame_count as f64).sqrt().ceil() as u32;
+    let rows = (frame_count as u32).div_ceil(columns);
+    let layout = FlipbookLayout {
+        frame_count,
+        columns,
+        rows,
+        frame_width,
+        frame_height,
+        cell_width: frame_width.next_multiple_of(4),
+        cell_height: frame_height.next_multiple_of(4),
+        durations,
+    };
+    let (width, height) = (layout.sheet_width(), layout.sheet_height());
+    if width > SPRITE_SHEET_MAX_SIZE || height > SPRITE_SHEET_MAX_SIZE {
+        return Err(FlipbookError::TooLarge {
+            width,
+            height,
+            max_size: SPRITE_SHEET_MAX_SIZE,
+        });
+    }
+
+    let mut sheet = RgbaImage::new(width, height);
+    for (index, frame) in frames.iter().enumerate() {
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        image::imageops::replace(
+            &mut sheet,
+            frame,
+            (column * layout.cell_width) as i64,
+            (row * layout.cell_height) as i64,
+        );
+    }
+
+    let sheet = Image::encode(&DynamicImage::ImageRgba8(sheet))
+        .map_err(|e| FlipbookError::EncodeError(e.to_string()))?;
+    Ok((layout, sheet))
+}
+
+#[cfg(test)]
+pub(crate) mod test_util {
+    use image::codecs::gif::GifEncoder;
+    use image::{Delay, Frame, Rgba, RgbaImage};
+
+    /// テスト用に、指定した色と表示時間のフレームを持つアニメーション GIF を作る
+    pub fn build_gif(width: u32, height: u32, frames: &[([u8; 4], u32)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut data);
+            let frames = frames.iter().map(|&(color, duration)| {
+                Frame::from_parts(
+                    RgbaImage::from_pixel(width, height, Rgba(color)),
+                    0,
+                    0,
+                    Delay::from_numer_denom_ms(duration, 1),
+                )
+            });
+            encoder.encode_frames(frames).unwrap();
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_util::build_gif;
+    use super::*;
+    use image::Rgba;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    #[test]
+    fn アニメーションgifをフレームごとにデコードできる() {
+        let gif = build_gif(4, 4, &[(RED, 50), (BLUE, 120)]);
+
+        let decoded = decode_animation(&gif).unwrap();
+
+        assert_eq!(decoded.frames.len(), 2);
+        assert_eq!(decoded.durations, vec![50, 120]);
+        assert_eq!(decoded.frames[1].get_pixel(0, 0).0, BLUE);
+    }
+
+    #[test]
+    fn アニメーションでないpngならエラーを返す() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba(RED)));
+        let png = Image::encode(&img).unwrap().data;
+
+        let result = decode_animation(&png);
+
+        assert_eq!(
+            result.unwrap_err(),
+            FlipbookError::InvalidAnimation("png is not animated".to_string())
+        );
+    }
+
+    #[test]
+    fn gifでもpngでもないならエラーを返す() {
+        let result = decode_animation(&[0xff, 0xd8, 0xff]);
+        assert!(matches!(
+            result.unwrap_err(),
+            FlipbookError::InvalidAnimation(_)
+        ));
+    }
+
+    #[test]
+    fn 正方形に近い格子にフレームを並べる() {
+        let frames = vec![RgbaImage::from_pixel(6, 4, Rgba(RED)); 5];
+
+        let (layout, sheet) = build_sprite_sheet(&frames, vec![100; 5]).unwrap();
+
+        assert_eq!((layout.columns, layout.rows), (3, 2));
+        assert_eq!((layout.cell_width, layout.cell_height), (8, 4));
+        assert_eq!((sheet.width, sheet.height), (24, 8));
+        let sheet = image::load_from_memory(&sheet.data).unwrap().to_rgba8();
+        // NOTE: 5枚目は2行目の2列目
+        assert_eq!(sheet.get_pixel(8, 4).0, RED);
+        // NOTE: マスの余白と空きマスは透明
+        assert_eq!(sheet.get_pixel(6, 0).0, [0, 0, 0, 0]);
+        assert_eq!(sheet.get_pixel(16, 4).0, [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn フレームのサイズが異なるならエラーを返す() {
+        let frames = vec![
+            RgbaImage::from_pixel(4, 4, Rgba(RED)),
+            RgbaImage::from_pixel(8, 4, Rgba(RED)),
+        ];
+
+        let result = build_sprite_sheet(&frames, vec![100; 2]);
+
+        assert_eq!(
+            result.unwrap_err(),
+            FlipbookError::FrameSizeMismatch {
+                index: 1,
+                width: 8,
+                height: 4,
+                expected_width: 4,
+                expected_height: 4
+            }
+        );
+    }
+
+    #[test]
+    fn スプライトシートが最大サイズを超えるならエラーを返す() {
+        let frames = vec![RgbaImage::new(SPRITE_SHEET_MAX_SIZE / 2 + 4, 4); 2];
+
+        let result = build_sprite_sheet(&frames, vec![100; 2]);
+
+        assert!(matches!(
+            result.unwrap_err(),
+            FlipbookError::TooLarge { .. }
+        ));
+    }
+
+    #[test]
+    fn フレームがないならエラーを返す() {
+        assert_eq!(
+            build_sprite_sheet(&[], Vec::new()).unwrap_err(),
+            FlipbookError::Empty
+        );
+    }
+
+    #[test]
+    fn 連番画像として解釈できないならエラーを返す() {
+        let result = decode_frames(&[vec![1, 2, 3]]);
+        assert!(matches!(
+            result.unwrap_err(),
+            FlipbookError::InvalidFrame { index: 0, .. }
+        ));
+    }
+}
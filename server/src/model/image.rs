@@ -1,15 +1,25 @@
+use crate::model::dimension_fix::{DimensionFix, FixedDimensions};
 use crate::model::error::ImageError;
-use image::GenericImageView;
+use image::imageops::FilterType;
+use image::{
+    DynamicImage, GenericImageView, ImageDecoder, ImageFormat, ImageReader, Rgba, RgbaImage,
+};
+use std::io::Cursor;
 
 /// 画像情報を表すモデル
 #[derive(Debug, Clone)]
 pub struct Image {
     /// 画像のバイトデータ
     pub data: Vec<u8>,
+    /// 横幅 (px)
+    pub width: u32,
+    /// 高さ (px)
+    pub height: u32,
 }
 
 impl Image {
     /// 画像データが空かどうかを確認
+    #[cfg(test)]
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
@@ -18,6 +28,130 @@ impl Image {
     pub fn as_bytes(&self) -> &[u8] {
         &self.data
     }
+
+    /// 指定したサイズに拡大縮小した画像を作る
+    ///
+    /// 再圧縮による劣化を避けるため PNG でエンコードし直す
+    pub fn resize(&self, width: u32, height: u32) -> Result<Self, ImageError> {
+        if width == 0 || height == 0 || !width.is_multiple_of(4) || !height.is_multiple_of(4) {
+            return Err(ImageError::InvalidDimensions { width, height });
+        }
+
+        let img = image::load_from_memory(&self.data)
+            .map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        Self::encode(&img.resize_exact(width, height, FilterType::Lanczos3))
+    }
+
+    /// 縦横のピクセル数が4の倍数でなければ、指定した方法で補正してから変換する
+    ///
+    /// 補正した場合は PNG でエンコードし直し、補正前後のサイズも返す
+    /// 補正方法を指定しなければ `try_from` と同じ
+    pub fn try_from_with_fix(
+        data: &[u8],
+        fix: Option<&DimensionFix>,
+    ) -> Result<(Self, Option<FixedDimensions>), ImageError> {
+        let Some(fix) = fix else {
+            return Self::try_from(data).map(|image| (image, None));
+        };
+
+        if data.is_empty() {
+            return Err(ImageError::EmptyData);
+        }
+
+        let img =
+            image::load_from_memory(data).map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        let (width, height) = img.dimensions();
+        if width.is_multiple_of(4) && height.is_multiple_of(4) {
+            return Ok((
+                Self {
+                    data: data.to_vec(),
+                    width,
+                    height,
+                },
+                None,
+            ));
+        }
+
+        let fixed = match fix {
+            DimensionFix::Pad { color } => {
+                let mut canvas = RgbaImage::from_pixel(
+                    width.next_multiple_of(4),
+                    height.next_multiple_of(4),
+                    Rgba(*color),
+                );
+                image::imageops::replace(&mut canvas, &img.to_rgba8(), 0, 0);
+                DynamicImage::ImageRgba8(canvas)
+            }
+            DimensionFix::Crop => {
+                let (fixed_width, fixed_height) = (width / 4 * 4, height / 4 * 4);
+                if fixed_width == 0 || fixed_height == 0 {
+                    return Err(ImageError::InvalidDimensions { width, height });
+                }
+                img.crop_imm(
+                    (width - fixed_width) / 2,
+                    (height - fixed_height) / 2,
+                    fixed_width,
+                    fixed_height,
+                )
+            }
+            DimensionFix::Resize => {
+                let nearest = |value: u32| ((value + 2) / 4 * 4).max(4);
+                img.resize_exact(nearest(width), nearest(height), FilterType::Lanczos3)
+            }
+        };
+
+        let image = Self::encode(&fixed)?;
+        let report = FixedDimensions {
+            strategy: *fix,
+            original_width: width,
+            original_height: height,
+            width: image.width,
+            height: image.height,
+        };
+        Ok((image, Some(report)))
+    }
+
+    /// 上下を反転した画像を作る
+    ///
+    /// Unity の `Texture2D.LoadRawTextureData` は下の行から読み込むので、あらかじめ反転しておくと正しい向きで表示される
+    /// 再圧縮による劣化を避けるため PNG でエンコードし直す
+    pub fn flip_vertical(&self) -> Result<Self, ImageError> {
+        let img = image::load_from_memory(&self.data)
+            .map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        Self::encode(&img.flipv())
+    }
+
+    /// 不透明でないピクセルがあるか
+    ///
+    /// アルファチャンネルを持つ形式でも、全て不透明なら false を返す
+    /// アルファチャンネルを持たない場合（JPEG など）はヘッダーだけ読み、ピクセルはデコードしない
+    pub fn has_alpha(&self) -> Result<bool, ImageError> {
+        let decoder = ImageReader::new(Cursor::new(&self.data))
+            .with_guessed_format()
+            .map_err(|e| ImageError::DecodeError(e.to_string()))?
+            .into_decoder()
+            .map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        if !decoder.color_type().has_alpha() {
+            return Ok(false);
+        }
+
+        let img = DynamicImage::from_decoder(decoder)
+            .map_err(|e| ImageError::DecodeError(e.to_string()))?;
+        Ok(img.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX))
+    }
+
+    /// デコード済みの画像を PNG でエンコードしてモデルにする
+    pub(crate) fn encode(img: &DynamicImage) -> Result<Self, ImageError> {
+        let mut data = Vec::new();
+        img.write_to(&mut Cursor::new(&mut data), ImageFormat::Png)
+            .map_err(|e| ImageError::EncodeError(e.to_string()))?;
+
+        Ok(Self {
+            data,
+            width: img.width(),
+            height: img.height(),
+        })
+    }
 }
 
 impl std::convert::TryFrom<Vec<u8>> for Image {
@@ -50,6 +184,8 @@ impl std::convert::TryFrom<&[u8]> for Image {
 
         Ok(Self {
             data: data.to_vec(),
+            width,
+            height,
         })
     }
 }
@@ -82,6 +218,8 @@ mod tests {
         let image = result.unwrap();
         assert!(!image.is_empty());
         assert_eq!(image.as_bytes().len(), jpeg_data.len());
+        assert_eq!(image.width % 4, 0);
+        assert_eq!(image.height % 4, 0);
     }
 
     #[tokio::test]
@@ -108,6 +246,135 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn 指定したサイズに縮小できる() {
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let image = Image::try_from(jpeg_data.as_slice()).unwrap();
+
+        let resized = image.resize(8, 4).unwrap();
+        assert_eq!((resized.width, resized.height), (8, 4));
+
+        let decoded = Image::try_from(resized.as_bytes()).unwrap();
+        assert_eq!((decoded.width, decoded.height), (8, 4));
+    }
+
+    #[tokio::test]
+    async fn 四の倍数でないサイズには縮小できない() {
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let image = Image::try_from(jpeg_data.as_slice()).unwrap();
+
+        let result = image.resize(6, 4);
+        assert!(matches!(
+            result,
+            Err(ImageError::InvalidDimensions {
+                width: 6,
+                height: 4
+            })
+        ));
+    }
+
+    /// テスト用に指定したサイズの単色 PNG を作る
+    fn png(width: u32, height: u32, color: [u8; 4]) -> Vec<u8> {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba(color)));
+        Image::encode(&img).unwrap().data
+    }
+
+    #[tokio::test]
+    async fn 透過のあるピクセルがあるかを判定できる() {
+        let jpeg = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        assert!(!Image::try_from(jpeg).unwrap().has_alpha().unwrap());
+
+        let opaque = Image::try_from(png(4, 4, [255, 0, 0, 255])).unwrap();
+        assert!(!opaque.has_alpha().unwrap());
+        let translucent = Image::try_from(png(4, 4, [255, 0, 0, 128])).unwrap();
+        assert!(translucent.has_alpha().unwrap());
+    }
+
+    #[test]
+    fn 上下を反転できる() {
+        let mut img = RgbaImage::from_pixel(4, 8, Rgba([0, 0, 0, 255]));
+        img.put_pixel(1, 0, Rgba([255, 0, 0, 255]));
+        let image = Image::encode(&DynamicImage::ImageRgba8(img)).unwrap();
+
+        let flipped = image.flip_vertical().unwrap();
+
+        assert_eq!((flipped.width, flipped.height), (4, 8));
+        let decoded = image::load_from_memory(flipped.as_bytes())
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded.get_pixel(1, 7), &Rgba([255, 0, 0, 255]));
+        assert_eq!(decoded.get_pixel(1, 0), &Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn 四の倍数なら補正しない() {
+        let data = png(8, 4, [255, 0, 0, 255]);
+        let (image, report) =
+            Image::try_from_with_fix(&data, Some(&DimensionFix::Crop)).unwrap();
+
+        assert!(report.is_none());
+        assert_eq!(image.as_bytes(), data.as_slice());
+    }
+
+    #[test]
+    fn 余白を足して四の倍数にする() {
+        let data = png(5, 6, [255, 0, 0, 255]);
+        let fix = DimensionFix::Pad {
+            color: [0, 0, 255, 255],
+        };
+        let (image, report) = Image::try_from_with_fix(&data, Some(&fix)).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 8));
+        let report = report.unwrap();
+        assert_eq!(report.strategy, fix);
+        assert_eq!((report.original_width, report.original_height), (5, 6));
+        assert_eq!((report.width, report.height), (8, 8));
+
+        // 元の画像は左上に残り、右下が余白の色になる
+        let decoded = image::load_from_memory(image.as_bytes())
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded.get_pixel(4, 5), &Rgba([255, 0, 0, 255]));
+        assert_eq!(decoded.get_pixel(7, 7), &Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn 中央を切り抜いて四の倍数にする() {
+        let data = png(10, 7, [255, 0, 0, 255]);
+        let (image, report) =
+            Image::try_from_with_fix(&data, Some(&DimensionFix::Crop)).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 4));
+        assert_eq!(report.unwrap().strategy, DimensionFix::Crop);
+    }
+
+    #[test]
+    fn 四未満は切り抜けない() {
+        let data = png(3, 8, [255, 0, 0, 255]);
+        let result = Image::try_from_with_fix(&data, Some(&DimensionFix::Crop));
+
+        assert!(matches!(
+            result,
+            Err(ImageError::InvalidDimensions {
+                width: 3,
+                height: 8
+            })
+        ));
+    }
+
+    #[test]
+    fn 最も近い四の倍数に拡大縮小する() {
+        let data = png(9, 6, [255, 0, 0, 255]);
+        let (image, _) =
+            Image::try_from_with_fix(&data, Some(&DimensionFix::Resize)).unwrap();
+
+        assert_eq!((image.width, image.height), (8, 8));
+    }
+
     #[tokio::test]
     async fn vec_u8からも変換できる() {
         let jpeg_data = fs::read("resources/4_multiple_size.jpg")
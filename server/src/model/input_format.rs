@@ -0,0 +1,171 @@
+use image::ImageFormat;
+
+use crate::model::error::{InputFormatError, UnsupportedFormatError};
+
+/// 入力を許可する画像形式の一覧
+///
+/// 形式は拡張子ではなくデータ先頭のマジックバイトから判定する
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllowedFormats {
+    formats: Vec<ImageFormat>,
+}
+
+impl Default for AllowedFormats {
+    /// jpeg と png のみ許可する
+    fn default() -> Self {
+        Self {
+            formats: vec![ImageFormat::Jpeg, ImageFormat::Png],
+        }
+    }
+}
+
+impl AllowedFormats {
+    /// アニメーションの入力のデフォルト（gif と png。APNG は png と判定される）
+    pub fn animation_default() -> Self {
+        Self {
+            formats: vec![ImageFormat::Gif, ImageFormat::Png],
+        }
+    }
+
+    /// 画像の形式が許可されているか確認する
+    ///
+    /// 空のデータや形式を判定できないデータは、デコードできないので画像モデルへの変換でエラーにする
+    pub fn check(&self, data: &[u8]) -> Result<(), UnsupportedFormatError> {
+        let Ok(detected) = image::guess_format(data) else {
+            return Ok(());
+        };
+        if self.formats.contains(&detected) {
+            return Ok(());
+        }
+        Err(UnsupportedFormatError {
+            index: None,
+            detected: format_name(detected),
+            allowed: self.names(),
+        })
+    }
+
+    /// 全ての画像の形式が許可されているか確認する
+    pub fn check_all<T: AsRef<[u8]>>(&self, images: &[T]) -> Result<(), UnsupportedFormatError> {
+        for (index, data) in images.iter().enumerate() {
+            self.check(data.as_ref()).map_err(|e| e.at(index))?;
+        }
+        Ok(())
+    }
+
+    /// 許可されている形式の名前
+    pub fn names(&self) -> Vec<String> {
+        self.formats.iter().copied().map(format_name).collect()
+    }
+}
+
+impl UnsupportedFormatError {
+    /// 何枚目の画像かを付ける
+    pub fn at(self, index: usize) -> Self {
+        Self {
+            index: Some(index),
+            ..self
+        }
+    }
+}
+
+impl std::str::FromStr for AllowedFormats {
+    type Err = InputFormatError;
+
+    /// `jpeg,png` のようなカンマ区切りの形式名から作る（`jpg` などの拡張子でもよい）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut formats = Vec::new();
+        for name in s.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let format = ImageFormat::from_extension(name.to_ascii_lowercase())
+                .ok_or_else(|| InputFormatError::UnknownFormat(name.to_string()))?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            return Err(InputFormatError::Empty);
+        }
+        Ok(Self { formats })
+    }
+}
+
+/// レスポンスやログに出す形式の名前（jpeg, png, gif, bmp など）
+fn format_name(format: ImageFormat) -> String {
+    format!("{:?}", format).to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Image;
+    use image::{DynamicImage, RgbaImage};
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    fn encode(format: ImageFormat) -> Vec<u8> {
+        let img = DynamicImage::ImageRgba8(RgbaImage::new(4, 4));
+        let mut data = Vec::new();
+        img.write_to(&mut Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    #[test]
+    fn デフォルトではjpegとpngを許可する() {
+        let allowed = AllowedFormats::default();
+        let png = Image::encode(&DynamicImage::ImageRgba8(RgbaImage::new(4, 4)))
+            .unwrap()
+            .data;
+
+        assert!(allowed.check(&png).is_ok());
+        assert_eq!(allowed.names(), vec!["jpeg", "png"]);
+    }
+
+    #[test]
+    fn 許可されていない形式ならマジックバイトから判定した形式を返す() {
+        let allowed = AllowedFormats::default();
+
+        assert_eq!(
+            allowed.check(&encode(ImageFormat::Bmp)).unwrap_err(),
+            UnsupportedFormatError {
+                index: None,
+                detected: "bmp".to_string(),
+                allowed: vec!["jpeg".to_string(), "png".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn 複数枚なら許可されていない画像のindexを返す() {
+        let allowed = AllowedFormats::from_str("png").unwrap();
+        let images = vec![encode(ImageFormat::Png), encode(ImageFormat::Gif)];
+
+        let e = allowed.check_all(&images).unwrap_err();
+        assert_eq!(e.index, Some(1));
+        assert_eq!(e.detected, "gif");
+        assert_eq!(
+            e.to_string(),
+            "image at index 1: unsupported image format: gif (allowed: png)"
+        );
+    }
+
+    #[test]
+    fn 形式を判定できないデータは確認しない() {
+        let allowed = AllowedFormats::default();
+        assert!(allowed.check(&[]).is_ok());
+        assert!(allowed.check(&[0, 1, 2, 3]).is_ok());
+    }
+
+    #[test]
+    fn カンマ区切りの文字列から変換できる() {
+        let allowed = AllowedFormats::from_str(" JPG, png ,gif,jpeg").unwrap();
+        assert_eq!(allowed.names(), vec!["jpeg", "png", "gif"]);
+
+        assert_eq!(
+            AllowedFormats::from_str("jpeg,heic").unwrap_err(),
+            InputFormatError::UnknownFormat("heic".to_string())
+        );
+        assert_eq!(
+            AllowedFormats::from_str(" , ").unwrap_err(),
+            InputFormatError::Empty
+        );
+    }
+}
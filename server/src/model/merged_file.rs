@@ -0,0 +1,1212 @@
+use std::ops::Range;
+
+use crate::model::error::MergedFileError;
+
+/// v2 以降のファイル先頭に置くマジックバイト
+pub const MAGIC: [u8; 4] = *b"VRCB";
+
+/// v2 以降のヘッダーサイズ (Magic + Version + Flags)
+pub const VERSIONED_HEADER_SIZE: usize = 12;
+
+/// ヘッダーフラグ: サイズ一覧の後ろに各画像の記述子一覧を持つ
+pub const FLAG_DESCRIPTORS: u32 = 0x1;
+
+/// ヘッダーフラグ: 記述子一覧の後ろに画像メタデータの JSON を持つ
+pub const FLAG_METADATA: u32 = 0x2;
+
+/// ヘッダーフラグ: 画像ごとの CRC32 一覧とファイル全体の CRC32 を持つ
+pub const FLAG_CHECKSUMS: u32 = 0x4;
+
+/// ヘッダーフラグ: サイズ一覧の後ろに各画像のデータ位置一覧を持ち、同じ内容のデータを共有する
+pub const FLAG_SHARED_DATA: u32 = 0x8;
+
+/// 現在解釈できるヘッダーフラグ
+pub const SUPPORTED_FLAGS: u32 =
+    FLAG_DESCRIPTORS | FLAG_METADATA | FLAG_CHECKSUMS | FLAG_SHARED_DATA;
+
+/// 記述子1件あたりのサイズ (Int32 × 4)
+pub const DESCRIPTOR_SIZE: usize = 16;
+
+/// 独自形式のフォーマットバージョン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FormatVersion {
+    /// 画像枚数から始まる最初のフォーマット
+    #[default]
+    V1,
+    /// マジックバイト・バージョン・フラグを先頭に持つフォーマット
+    V2,
+}
+
+impl FormatVersion {
+    /// ヘッダーに書き込むバージョン番号
+    pub fn number(&self) -> i32 {
+        match self {
+            FormatVersion::V1 => 1,
+            FormatVersion::V2 => 2,
+        }
+    }
+}
+
+impl std::convert::TryFrom<i32> for FormatVersion {
+    type Error = MergedFileError;
+
+    fn try_from(number: i32) -> Result<Self, Self::Error> {
+        match number {
+            1 => Ok(FormatVersion::V1),
+            2 => Ok(FormatVersion::V2),
+            _ => Err(MergedFileError::UnsupportedVersion(number)),
+        }
+    }
+}
+
+/// 各画像の記述子
+///
+/// Udon 側が DDS ヘッダーを解釈せずに Texture2D を生成できるようにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct EntryDescriptor {
+    /// 横幅 (px)
+    pub width: i32,
+    /// 高さ (px)
+    pub height: i32,
+    /// Unity の TextureFormat の値
+    pub format: i32,
+    /// ミップマップの段数
+    pub mip_count: i32,
+}
+
+impl EntryDescriptor {
+    /// 書き込み用のバイト列に変換
+    pub fn to_le_bytes(self) -> [u8; DESCRIPTOR_SIZE] {
+        let mut bytes = [0u8; DESCRIPTOR_SIZE];
+        bytes[0..4].copy_from_slice(&self.width.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.height.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.format.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.mip_count.to_le_bytes());
+        bytes
+    }
+}
+
+/// 複数のDDSデータを束ねた独自形式ファイルを表すモデル
+///
+/// v1 フォーマット:
+/// - Header: Texture Count (4byte, Int32, Little Endian)
+/// - Index: Data Size List (4byte * N, 各DDSデータのサイズ)
+/// - Data: Concatenated DDS Binaries
+///
+/// v2 フォーマット:
+/// - Magic: "VRCB" (4byte)
+/// - Version: Format Version (4byte, Int32, Little Endian)
+/// - Flags: Header Flags (4byte, UInt32, Little Endian)
+/// - Header: Texture Count (4byte, Int32, Little Endian)
+/// - Index: Data Size List (4byte * N, 各DDSデータのサイズ)
+/// - Offsets: (FLAG_SHARED_DATA) Data Offset List (4byte * N, Int32, Data 先頭からの各DDSデータの位置)
+/// - Descriptors: (FLAG_DESCRIPTORS) Width, Height, Format, Mip Count (4byte * 4 * N)
+/// - Checksums: (FLAG_CHECKSUMS) CRC32 List (4byte * N, UInt32, 各DDSデータの CRC32)
+/// - Metadata: (FLAG_METADATA) JSON Length (4byte, Int32) + UTF-8 JSON
+/// - Data: Concatenated DDS Binaries (FLAG_SHARED_DATA なら同じ内容のデータは1つだけ)
+/// - Trailer: (FLAG_CHECKSUMS) File CRC32 (4byte, UInt32, ここより前の全バイトの CRC32)
+///
+/// 先頭がマジックバイトかどうかでバージョンを判別する
+/// チェックサムを持つファイルは読み取り時に検証する
+/// データを共有するファイルも、各画像のデータは通常のファイルと同じように取り出せる
+#[derive(Debug, Clone)]
+pub struct MergedFile {
+    /// ファイル全体のバイトデータ
+    data: Vec<u8>,
+    /// フォーマットバージョン
+    version: FormatVersion,
+    /// ヘッダーフラグ（v1 は常に 0）
+    flags: u32,
+    /// 各DDSデータのファイル内での範囲
+    entries: Vec<Range<usize>>,
+    /// 各画像の記述子（FLAG_DESCRIPTORS がある場合のみ）
+    descriptors: Option<Vec<EntryDescriptor>>,
+    /// 画像メタデータの JSON のファイル内での範囲（FLAG_METADATA がある場合のみ）
+    metadata: Option<Range<usize>>,
+    /// 各DDSデータの CRC32（FLAG_CHECKSUMS がある場合のみ）
+    checksums: Option<Vec<u32>>,
+}
+
+impl MergedFile {
+    /// フォーマットバージョン
+    pub fn version(&self) -> FormatVersion {
+        self.version
+    }
+
+    /// ヘッダーフラグ
+    #[cfg(test)]
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// 束ねられている画像の枚数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 指定枚目のDDSデータを取得
+    pub fn entry(&self, index: usize) -> Option<&[u8]> {
+        self.entries.get(index).map(|range| &self.data[range.clone()])
+    }
+
+    /// 各画像の記述子一覧（記述子を持たないファイルなら None）
+    pub fn descriptors(&self) -> Option<&[EntryDescriptor]> {
+        self.descriptors.as_deref()
+    }
+
+    /// 画像メタデータの JSON（メタデータを持たないファイルなら None）
+    pub fn metadata(&self) -> Option<&str> {
+        // NOTE: パース時に UTF-8 であることを検証済み
+        self.metadata
+            .clone()
+            .and_then(|range| std::str::from_utf8(&self.data[range]).ok())
+    }
+
+    /// 各DDSデータの CRC32 一覧（チェックサムを持たないファイルなら None）
+    pub fn checksums(&self) -> Option<&[u32]> {
+        self.checksums.as_deref()
+    }
+
+    /// 同じ内容のDDSデータを共有する形式か
+    pub fn has_shared_data(&self) -> bool {
+        self.flags & FLAG_SHARED_DATA != 0
+    }
+
+    /// ファイル全体の CRC32（チェックサムを持たないファイルなら None）
+    #[cfg(test)]
+    pub fn file_checksum(&self) -> Option<u32> {
+        self.checksums.as_ref()?;
+        read_i32(&self.data, self.data.len() - 4).map(|value| value as u32)
+    }
+
+    /// 全てのDDSデータを先頭から順に取得
+    pub fn entries(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries.iter().map(|range| &self.data[range.clone()])
+    }
+}
+
+impl std::convert::TryFrom<Vec<u8>> for MergedFile {
+    type Error = MergedFileError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        if data.is_empty() {
+            return Err(MergedFileError::EmptyData);
+        }
+
+        let (version, flags, body_offset) = parse_header(&data)?;
+        let body = parse_body(&data, flags, body_offset)?;
+        Ok(Self {
+            data,
+            version,
+            flags,
+            entries: body.entries,
+            descriptors: body.descriptors,
+            metadata: body.metadata,
+            checksums: body.checksums,
+        })
+    }
+}
+
+impl std::convert::TryFrom<&[u8]> for MergedFile {
+    type Error = MergedFileError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from(data.to_vec())
+    }
+}
+
+/// 4byte のリトルエンディアン Int32 を読み取る
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(i32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// バージョンを判別し、(バージョン, フラグ, 画像枚数の位置) を返す
+fn parse_header(data: &[u8]) -> Result<(FormatVersion, u32, usize), MergedFileError> {
+    if !data.starts_with(&MAGIC) {
+        return Ok((FormatVersion::V1, 0, 0));
+    }
+
+    if data.len() < VERSIONED_HEADER_SIZE {
+        return Err(MergedFileError::TruncatedHeader { actual: data.len() });
+    }
+
+    // NOTE: 上で長さを検証済みなので必ず読める
+    let version = read_i32(data, 4).unwrap_or_default();
+    let version = match FormatVersion::try_from(version)? {
+        // NOTE: v1 はマジックバイトを持たない
+        FormatVersion::V1 => return Err(MergedFileError::UnsupportedVersion(version)),
+        version => version,
+    };
+
+    let flags = read_i32(data, 8).unwrap_or_default() as u32;
+    if flags & !SUPPORTED_FLAGS != 0 {
+        return Err(MergedFileError::UnsupportedFlags(flags & !SUPPORTED_FLAGS));
+    }
+
+    Ok((version, flags, VERSIONED_HEADER_SIZE))
+}
+
+/// 画像枚数以降を読み取った結果
+struct ParsedBody {
+    entries: Vec<Range<usize>>,
+    descriptors: Option<Vec<EntryDescriptor>>,
+    metadata: Option<Range<usize>>,
+    checksums: Option<Vec<u32>>,
+}
+
+/// 画像枚数以降を検証し、各DDSデータの範囲と任意セクションを求める
+fn parse_body(data: &[u8], flags: u32, body_offset: usize) -> Result<ParsedBody, MergedFileError> {
+    // Header Section: Texture Count
+    let count = read_i32(data, body_offset).ok_or(MergedFileError::TruncatedHeader {
+        actual: data.len(),
+    })?;
+    if count <= 0 {
+        return Err(MergedFileError::InvalidCount(count));
+    }
+    let count = count as usize;
+
+    // Index Section: Data Size List
+    let index_offset = body_offset + 4;
+    let available = (data.len() - index_offset) / 4;
+    if count > available {
+        return Err(MergedFileError::CountMismatch { count, available });
+    }
+
+    let mut sizes = Vec::with_capacity(count);
+    for index in 0..count {
+        // NOTE: 上で長さを検証済みなので必ず読める
+        let size = read_i32(data, index_offset + index * 4).unwrap_or_default();
+        if size < 0 {
+            return Err(MergedFileError::NegativeSize { index, size });
+        }
+        sizes.push(size as usize);
+    }
+    let mut offset = index_offset + count * 4;
+
+    // Offsets Section: Data Offset List
+    let data_offsets = if flags & FLAG_SHARED_DATA != 0 {
+        let expected = offset + count * 4;
+        if expected > data.len() {
+            return Err(MergedFileError::TruncatedData {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let mut data_offsets = Vec::with_capacity(count);
+        for index in 0..count {
+            // NOTE: 上で長さを検証済みなので必ず読める
+            let data_offset = read_i32(data, offset + index * 4).unwrap_or_default();
+            if data_offset < 0 {
+                return Err(MergedFileError::NegativeOffset {
+                    index,
+                    offset: data_offset,
+                });
+            }
+            data_offsets.push(data_offset as usize);
+        }
+        offset = expected;
+        Some(data_offsets)
+    } else {
+        None
+    };
+
+    // Descriptors Section: Width, Height, Format, Mip Count
+    let descriptors = if flags & FLAG_DESCRIPTORS != 0 {
+        let expected = offset + count * DESCRIPTOR_SIZE;
+        if expected > data.len() {
+            return Err(MergedFileError::TruncatedData {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let descriptors = (0..count)
+            .map(|index| {
+                let base = offset + index * DESCRIPTOR_SIZE;
+                // NOTE: 上で長さを検証済みなので必ず読める
+                EntryDescriptor {
+                    width: read_i32(data, base).unwrap_or_default(),
+                    height: read_i32(data, base + 4).unwrap_or_default(),
+                    format: read_i32(data, base + 8).unwrap_or_default(),
+                    mip_count: read_i32(data, base + 12).unwrap_or_default(),
+                }
+            })
+            .collect();
+        offset = expected;
+        Some(descriptors)
+    } else {
+        None
+    };
+
+    // Checksums Section: CRC32 List
+    let checksums = if flags & FLAG_CHECKSUMS != 0 {
+        let expected = offset + count * 4;
+        if expected > data.len() {
+            return Err(MergedFileError::TruncatedData {
+                expected,
+                actual: data.len(),
+            });
+        }
+        let checksums: Vec<u32> = (0..count)
+            .map(|index| read_i32(data, offset + index * 4).unwrap_or_default() as u32)
+            .collect();
+        offset = expected;
+        Some(checksums)
+    } else {
+        None
+    };
+
+    // Metadata Section: JSON Length + UTF-8 JSON
+    let metadata = if flags & FLAG_METADATA != 0 {
+        let length = read_i32(data, offset).ok_or(MergedFileError::TruncatedData {
+            expected: offset + 4,
+            actual: data.len(),
+        })?;
+        if length < 0 {
+            return Err(MergedFileError::InvalidMetadata(format!(
+                "length must not be negative (length: {})",
+                length
+            )));
+        }
+        let range = offset + 4..offset + 4 + length as usize;
+        let json = data.get(range.clone()).ok_or(MergedFileError::TruncatedData {
+            expected: range.end,
+            actual: data.len(),
+        })?;
+        std::str::from_utf8(json).map_err(|e| MergedFileError::InvalidMetadata(e.to_string()))?;
+        offset = range.end;
+        Some(range)
+    } else {
+        None
+    };
+
+    // Data Section: Concatenated DDS Binaries
+    // NOTE: データを共有する場合、データ部の長さは最も後ろにあるデータの終端で決まる
+    let data_offsets = data_offsets.unwrap_or_else(|| {
+        sizes
+            .iter()
+            .scan(0, |data_offset, size| {
+                let current = *data_offset;
+                *data_offset += size;
+                Some(current)
+            })
+            .collect()
+    });
+    let data_size = data_offsets
+        .iter()
+        .zip(&sizes)
+        .map(|(data_offset, size)| data_offset + size)
+        .max()
+        .unwrap_or_default();
+    let trailer_size = if checksums.is_some() { 4 } else { 0 };
+    let expected = offset + data_size + trailer_size;
+    if expected > data.len() {
+        return Err(MergedFileError::TruncatedData {
+            expected,
+            actual: data.len(),
+        });
+    }
+    if expected < data.len() {
+        return Err(MergedFileError::TrailingData {
+            expected,
+            actual: data.len(),
+        });
+    }
+
+    let entries: Vec<Range<usize>> = data_offsets
+        .iter()
+        .zip(&sizes)
+        .map(|(data_offset, size)| offset + data_offset..offset + data_offset + size)
+        .collect();
+    offset += data_size;
+
+    // Trailer: File CRC32
+    if let Some(checksums) = &checksums {
+        // NOTE: 上で長さを検証済みなので必ず読める
+        let expected = read_i32(data, offset).unwrap_or_default() as u32;
+        let actual = crc32fast::hash(&data[..offset]);
+        if expected != actual {
+            return Err(MergedFileError::FileChecksumMismatch { expected, actual });
+        }
+
+        for (index, (range, expected)) in entries.iter().zip(checksums).enumerate() {
+            let actual = crc32fast::hash(&data[range.clone()]);
+            if *expected != actual {
+                return Err(MergedFileError::ChecksumMismatch {
+                    index,
+                    expected: *expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Ok(ParsedBody {
+        entries,
+        descriptors,
+        metadata,
+        checksums,
+    })
+}
+
+/// チェックサムの検証結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChecksumCheck {
+    /// ファイルに書き込まれている CRC32
+    pub expected: u32,
+    /// データから計算した CRC32（データが途中で切れていて計算できなければ None）
+    pub actual: Option<u32>,
+}
+
+impl ChecksumCheck {
+    /// 書き込まれている CRC32 と一致したか
+    pub fn is_valid(&self) -> bool {
+        self.actual == Some(self.expected)
+    }
+}
+
+/// 読み取れるところまで読み取った1枚分の構造
+#[derive(Debug, PartialEq, Eq)]
+pub struct EntryLayout {
+    /// サイズ一覧に書き込まれているサイズ
+    pub size: i32,
+    /// ファイル内にあるDDSデータの範囲（位置がわからなければ None、途中で切れていれば切れたところまで）
+    pub range: Option<Range<usize>>,
+    /// 記述子（記述子一覧を読み取れた場合のみ）
+    pub descriptor: Option<EntryDescriptor>,
+    /// チェックサムの検証結果（チェックサム一覧を読み取れた場合のみ）
+    pub checksum: Option<ChecksumCheck>,
+    /// この画像の問題
+    pub error: Option<MergedFileError>,
+}
+
+/// 壊れていても読み取れるところまで読み取った束ねたファイルの構造
+///
+/// [`MergedFile`] と違い、ヘッダーさえ読めれば途中で切れていたりチェックサムが一致しなかったりしても失敗せず、
+/// 問題をファイル全体と画像ごとに分けて返す（読み込めないファイルの調査用）
+#[derive(Debug, PartialEq, Eq)]
+pub struct MergedFileLayout {
+    /// フォーマットバージョン
+    pub version: FormatVersion,
+    /// ヘッダーフラグ（v1 は常に 0）
+    pub flags: u32,
+    /// ヘッダーに書き込まれている画像枚数
+    pub count: i32,
+    /// サイズ一覧を読み取れた分の各画像の構造
+    pub entries: Vec<EntryLayout>,
+    /// 画像メタデータの JSON（読み取れた場合のみ）
+    pub metadata: Option<String>,
+    /// ファイル全体のチェックサムの検証結果（読み取れた場合のみ）
+    pub file_checksum: Option<ChecksumCheck>,
+    /// 特定の画像によらないファイル全体の問題
+    pub errors: Vec<MergedFileError>,
+}
+
+impl MergedFileLayout {
+    /// ヘッダー以降の問題をエラーにせずに読み取る（ヘッダーと画像枚数を読めなければエラーを返す）
+    pub fn parse(data: &[u8]) -> Result<Self, MergedFileError> {
+        if data.is_empty() {
+            return Err(MergedFileError::EmptyData);
+        }
+
+        let (version, flags, body_offset) = parse_header(data)?;
+        let count = read_i32(data, body_offset)
+            .ok_or(MergedFileError::TruncatedHeader { actual: data.len() })?;
+        let mut layout = Self {
+            version,
+            flags,
+            count,
+            entries: Vec::new(),
+            metadata: None,
+            file_checksum: None,
+            errors: Vec::new(),
+        };
+        if count <= 0 {
+            layout.errors.push(MergedFileError::InvalidCount(count));
+            return Ok(layout);
+        }
+
+        // Index Section: Data Size List
+        // NOTE: サイズ一覧が途中で切れていれば読み取れた分だけを調べ、以降のセクションの位置はわからないものとする
+        let count = count as usize;
+        let index_offset = body_offset + 4;
+        let available = (data.len() - index_offset) / 4;
+        if count > available {
+            layout
+                .errors
+                .push(MergedFileError::CountMismatch { count, available });
+        }
+        let sizes = i32_list(&data[index_offset..index_offset + count.min(available) * 4]);
+        let mut offset = (count <= available).then_some(index_offset + count * 4);
+
+        // Offsets Section: Data Offset List
+        let data_offsets = (flags & FLAG_SHARED_DATA != 0)
+            .then(|| layout.take_section(data, &mut offset, count * 4))
+            .flatten()
+            .map(i32_list);
+
+        // Descriptors Section: Width, Height, Format, Mip Count
+        let descriptors: Option<Vec<EntryDescriptor>> = (flags & FLAG_DESCRIPTORS != 0)
+            .then(|| layout.take_section(data, &mut offset, count * DESCRIPTOR_SIZE))
+            .flatten()
+            .map(|section| {
+                section
+                    .chunks_exact(DESCRIPTOR_SIZE)
+                    .map(|chunk| {
+                        let values = i32_list(chunk);
+                        EntryDescriptor {
+                            width: values[0],
+                            height: values[1],
+                            format: values[2],
+                            mip_count: values[3],
+                        }
+                    })
+                    .collect()
+            });
+
+        // Checksums Section: CRC32 List
+        let has_checksums = flags & FLAG_CHECKSUMS != 0;
+        let checksums = has_checksums
+            .then(|| layout.take_section(data, &mut offset, count * 4))
+            .flatten()
+            .map(i32_list);
+
+        // Metadata Section: JSON Length + UTF-8 JSON
+        if flags & FLAG_METADATA != 0 {
+            if let Some(length) = layout.take_section(data, &mut offset, 4).map(i32_list) {
+                if length[0] < 0 {
+                    layout.errors.push(MergedFileError::InvalidMetadata(format!(
+                        "length must not be negative (length: {})",
+                        length[0]
+                    )));
+                    offset = None;
+                } else if let Some(json) =
+                    layout.take_section(data, &mut offset, length[0] as usize)
+                {
+                    match std::str::from_utf8(json) {
+                        Ok(json) => layout.metadata = Some(json.to_string()),
+                        Err(e) => layout
+                            .errors
+                            .push(MergedFileError::InvalidMetadata(e.to_string())),
+                    }
+                }
+            }
+        }
+
+        // Data Section: Concatenated DDS Binaries
+        // NOTE: 負のサイズより後ろの画像は、データを共有しなければ位置がわからない
+        let mut next_data_offset = Some(0);
+        let mut data_end = offset;
+        for (index, &size) in sizes.iter().enumerate() {
+            // NOTE: 位置一覧の負の値は Err にする（位置一覧が切れていれば Ok(None)）
+            let data_offset = match &data_offsets {
+                Some(data_offsets) => usize::try_from(data_offsets[index])
+                    .map(Some)
+                    .map_err(|_| data_offsets[index]),
+                None if flags & FLAG_SHARED_DATA != 0 => Ok(None),
+                None => Ok(next_data_offset),
+            };
+            next_data_offset = next_data_offset
+                .zip((size >= 0).then_some(size as usize))
+                .map(|(data_offset, size)| data_offset + size);
+
+            let mut entry = EntryLayout {
+                size,
+                range: None,
+                descriptor: descriptors.as_ref().map(|descriptors| descriptors[index]),
+                checksum: None,
+                error: None,
+            };
+            if size < 0 {
+                entry.error = Some(MergedFileError::NegativeSize { index, size });
+            } else if let Err(data_offset) = data_offset {
+                entry.error = Some(MergedFileError::NegativeOffset {
+                    index,
+                    offset: data_offset,
+                });
+            } else if let Some((start, data_offset)) = offset.zip(data_offset.ok().flatten()) {
+                let start = start + data_offset;
+                let end = start + size as usize;
+                entry.range = Some(start.min(data.len())..end.min(data.len()));
+                if end > data.len() {
+                    entry.error = Some(MergedFileError::TruncatedData {
+                        expected: end,
+                        actual: data.len(),
+                    });
+                }
+                data_end = data_end.map(|data_end| data_end.max(end));
+            }
+            if entry.range.is_none() {
+                data_end = None;
+            }
+
+            if let Some(checksums) = &checksums {
+                let expected = checksums[index] as u32;
+                let actual = entry
+                    .range
+                    .clone()
+                    .filter(|_| entry.error.is_none())
+                    .map(|range| crc32fast::hash(&data[range]));
+                if let Some(actual) = actual.filter(|actual| *actual != expected) {
+                    entry.error = Some(MergedFileError::ChecksumMismatch {
+                        index,
+                        expected,
+                        actual,
+                    });
+                }
+                entry.checksum = Some(ChecksumCheck { expected, actual });
+            }
+            layout.entries.push(entry);
+        }
+
+        // Trailer: File CRC32
+        // NOTE: 全ての画像の位置がわかった場合だけ、ファイルの終端を確認できる
+        if let Some(data_end) = data_end.filter(|_| sizes.len() == count) {
+            let trailer_size = if has_checksums { 4 } else { 0 };
+            let expected = data_end + trailer_size;
+            if expected > data.len() {
+                layout.errors.push(MergedFileError::TruncatedData {
+                    expected,
+                    actual: data.len(),
+                });
+            } else if expected < data.len() {
+                layout.errors.push(MergedFileError::TrailingData {
+                    expected,
+                    actual: data.len(),
+                });
+            }
+            if let Some(expected) = read_i32(data, data_end).filter(|_| has_checksums) {
+                let expected = expected as u32;
+                let actual = crc32fast::hash(&data[..data_end]);
+                if expected != actual {
+                    layout
+                        .errors
+                        .push(MergedFileError::FileChecksumMismatch { expected, actual });
+                }
+                layout.file_checksum = Some(ChecksumCheck {
+                    expected,
+                    actual: Some(actual),
+                });
+            }
+        }
+
+        Ok(layout)
+    }
+
+    /// 位置がわかっていれば指定サイズのセクションを取り出して位置を進める
+    ///
+    /// 途中で切れていれば問題を記録し、以降のセクションの位置はわからないものとする
+    fn take_section<'a>(
+        &mut self,
+        data: &'a [u8],
+        offset: &mut Option<usize>,
+        size: usize,
+    ) -> Option<&'a [u8]> {
+        let start = (*offset)?;
+        let end = start + size;
+        *offset = data.get(start..end).map(|_| end);
+        if offset.is_none() {
+            self.errors.push(MergedFileError::TruncatedData {
+                expected: end,
+                actual: data.len(),
+            });
+        }
+        data.get(start..end)
+    }
+}
+
+/// 4byte ごとのリトルエンディアン Int32 の一覧として読み取る
+fn i32_list(section: &[u8]) -> Vec<i32> {
+    section
+        .chunks_exact(4)
+        .map(|chunk| i32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用に独自形式のバイナリを組み立てる
+    fn build(count: i32, sizes: &[i32], data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&count.to_le_bytes());
+        for size in sizes {
+            result.extend_from_slice(&size.to_le_bytes());
+        }
+        result.extend_from_slice(data);
+        result
+    }
+
+    /// テスト用に v2 のヘッダーを付ける
+    fn with_header(version: i32, flags: u32, body: &[u8]) -> Vec<u8> {
+        let mut result = Vec::new();
+        result.extend_from_slice(&MAGIC);
+        result.extend_from_slice(&version.to_le_bytes());
+        result.extend_from_slice(&flags.to_le_bytes());
+        result.extend_from_slice(body);
+        result
+    }
+
+    #[test]
+    fn マジックバイトがなければv1として読み取る() {
+        let data = build(1, &[2], &[1, 2]);
+        let merged_file = MergedFile::try_from(data).unwrap();
+
+        assert_eq!(merged_file.version(), FormatVersion::V1);
+        assert_eq!(merged_file.flags(), 0);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1, 2]);
+    }
+
+    #[test]
+    fn マジックバイトがあればv2として読み取る() {
+        let data = with_header(2, 0, &build(2, &[1, 2], &[1, 2, 3]));
+        let merged_file = MergedFile::try_from(data).unwrap();
+
+        assert_eq!(merged_file.version(), FormatVersion::V2);
+        assert_eq!(merged_file.flags(), 0);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[2, 3]);
+    }
+
+    #[test]
+    fn v2のヘッダーが途中で切れているならエラーを返す() {
+        let data = with_header(2, 0, &[])[..10].to_vec();
+        let result = MergedFile::try_from(data);
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedHeader { actual: 10 }
+        );
+    }
+
+    #[test]
+    fn 未対応のバージョンならエラーを返す() {
+        let result = MergedFile::try_from(with_header(3, 0, &build(1, &[1], &[1])));
+        assert_eq!(result.unwrap_err(), MergedFileError::UnsupportedVersion(3));
+
+        let result = MergedFile::try_from(with_header(1, 0, &build(1, &[1], &[1])));
+        assert_eq!(result.unwrap_err(), MergedFileError::UnsupportedVersion(1));
+    }
+
+    #[test]
+    fn 未対応のフラグならエラーを返す() {
+        let result = MergedFile::try_from(with_header(2, 0x8000_0000, &build(1, &[1], &[1])));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::UnsupportedFlags(0x8000_0000)
+        );
+    }
+
+    #[test]
+    fn 記述子一覧を読み取れる() {
+        let descriptor = EntryDescriptor {
+            width: 256,
+            height: 128,
+            format: 10,
+            mip_count: 1,
+        };
+        let mut body = Vec::new();
+        body.extend_from_slice(&1i32.to_le_bytes());
+        body.extend_from_slice(&2i32.to_le_bytes());
+        body.extend_from_slice(&descriptor.to_le_bytes());
+        body.extend_from_slice(&[1, 2]);
+
+        let merged_file = MergedFile::try_from(with_header(2, FLAG_DESCRIPTORS, &body)).unwrap();
+        assert_eq!(merged_file.descriptors().unwrap(), &[descriptor]);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1, 2]);
+    }
+
+    #[test]
+    fn 記述子一覧が途中で切れているならエラーを返す() {
+        let result =
+            MergedFile::try_from(with_header(2, FLAG_DESCRIPTORS, &build(1, &[0], &[0; 8])));
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedData { .. }
+        ));
+    }
+
+    #[test]
+    fn 記述子フラグがなければ記述子一覧はない() {
+        let merged_file = MergedFile::try_from(with_header(2, 0, &build(1, &[1], &[1]))).unwrap();
+        assert!(merged_file.descriptors().is_none());
+    }
+
+    /// テスト用にメタデータセクション付きの本体を組み立てる
+    fn build_with_metadata(json: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&1i32.to_le_bytes());
+        body.extend_from_slice(&(data.len() as i32).to_le_bytes());
+        body.extend_from_slice(&(json.len() as i32).to_le_bytes());
+        body.extend_from_slice(json);
+        body.extend_from_slice(data);
+        body
+    }
+
+    #[test]
+    fn メタデータを読み取れる() {
+        let json = br#"[{"fileName":"a.png"}]"#;
+        let body = build_with_metadata(json, &[1, 2]);
+
+        let merged_file = MergedFile::try_from(with_header(2, FLAG_METADATA, &body)).unwrap();
+        assert_eq!(merged_file.metadata().unwrap(), r#"[{"fileName":"a.png"}]"#);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1, 2]);
+    }
+
+    #[test]
+    fn メタデータがutf8でないならエラーを返す() {
+        let body = build_with_metadata(&[0xff, 0xfe], &[1]);
+
+        let result = MergedFile::try_from(with_header(2, FLAG_METADATA, &body));
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::InvalidMetadata(_)
+        ));
+    }
+
+    #[test]
+    fn メタデータが途中で切れているならエラーを返す() {
+        let mut body = build_with_metadata(br#"[{}]"#, &[]);
+        body.truncate(body.len() - 1);
+
+        let result = MergedFile::try_from(with_header(2, FLAG_METADATA, &body));
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedData { .. }
+        ));
+    }
+
+    /// テスト用にチェックサム付きのファイルを組み立てる
+    fn build_with_checksums(entries: &[&[u8]]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&(entries.len() as i32).to_le_bytes());
+        for entry in entries {
+            body.extend_from_slice(&(entry.len() as i32).to_le_bytes());
+        }
+        for entry in entries {
+            body.extend_from_slice(&crc32fast::hash(entry).to_le_bytes());
+        }
+        for entry in entries {
+            body.extend_from_slice(entry);
+        }
+        let mut data = with_header(2, FLAG_CHECKSUMS, &body);
+        let file_checksum = crc32fast::hash(&data);
+        data.extend_from_slice(&file_checksum.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn チェックサムを検証して読み取れる() {
+        let data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        let file_checksum = crc32fast::hash(&data[..data.len() - 4]);
+
+        let merged_file = MergedFile::try_from(data).unwrap();
+        assert_eq!(
+            merged_file.checksums().unwrap(),
+            &[crc32fast::hash(&[1, 2, 3]), crc32fast::hash(&[4, 5])]
+        );
+        assert_eq!(merged_file.file_checksum(), Some(file_checksum));
+        assert_eq!(merged_file.entry(1).unwrap(), &[4, 5]);
+    }
+
+    #[test]
+    fn データが壊れているならファイルのチェックサム不一致エラーを返す() {
+        let mut data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        let len = data.len();
+        data[len - 5] ^= 0xff;
+
+        let result = MergedFile::try_from(data);
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::FileChecksumMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn 画像のチェックサムが一致しないならエラーを返す() {
+        let mut data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        // 2枚目の CRC32 を書き換えてからファイル全体の CRC32 を付け直す
+        let checksum_offset = VERSIONED_HEADER_SIZE + 4 + 8 + 4;
+        data[checksum_offset] ^= 0xff;
+        data.truncate(data.len() - 4);
+        let file_checksum = crc32fast::hash(&data);
+        data.extend_from_slice(&file_checksum.to_le_bytes());
+
+        let result = MergedFile::try_from(data);
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::ChecksumMismatch { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn チェックサムフラグがなければチェックサムはない() {
+        let merged_file = MergedFile::try_from(build(1, &[1], &[1])).unwrap();
+        assert!(merged_file.checksums().is_none());
+        assert!(merged_file.file_checksum().is_none());
+    }
+
+    /// テスト用にデータ位置一覧を持つ v2 のバイナリを組み立てる
+    fn build_with_offsets(sizes: &[i32], offsets: &[i32], data: &[u8]) -> Vec<u8> {
+        let mut body = build(sizes.len() as i32, sizes, &[]);
+        for offset in offsets {
+            body.extend_from_slice(&offset.to_le_bytes());
+        }
+        body.extend_from_slice(data);
+        with_header(2, FLAG_SHARED_DATA, &body)
+    }
+
+    #[test]
+    fn 共有されたデータも各画像のデータとして取り出せる() {
+        let merged_file =
+            MergedFile::try_from(build_with_offsets(&[2, 1, 2], &[0, 2, 0], &[1, 2, 3])).unwrap();
+        assert!(merged_file.has_shared_data());
+        let entries: Vec<&[u8]> = merged_file.entries().collect();
+        assert_eq!(entries, vec![&[1, 2][..], &[3][..], &[1, 2][..]]);
+    }
+
+    #[test]
+    fn 負のデータ位置ならエラーを返す() {
+        let result = MergedFile::try_from(build_with_offsets(&[1, 1], &[0, -1], &[1]));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::NegativeOffset {
+                index: 1,
+                offset: -1
+            }
+        );
+    }
+
+    #[test]
+    fn データ位置がデータ部の外を指すならエラーを返す() {
+        let result = MergedFile::try_from(build_with_offsets(&[2, 2], &[0, 1], &[1, 2]));
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedData { .. }
+        ));
+    }
+
+    #[test]
+    fn 共有フラグがなければデータを共有しない() {
+        let merged_file = MergedFile::try_from(build(1, &[1], &[1])).unwrap();
+        assert!(!merged_file.has_shared_data());
+    }
+
+    #[test]
+    fn v2の本体が壊れているならエラーを返す() {
+        let result = MergedFile::try_from(with_header(2, 0, &build(1, &[3], &[1])));
+        assert!(matches!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedData { .. }
+        ));
+    }
+
+    #[test]
+    fn 正しいバイナリなら各データを取り出せる() {
+        let data = build(2, &[5, 6], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]);
+        let merged_file = MergedFile::try_from(data.as_slice()).unwrap();
+
+        assert_eq!(merged_file.len(), 2);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1, 2, 3, 4, 5]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[6, 7, 8, 9, 10, 11]);
+        assert!(merged_file.entry(2).is_none());
+        assert_eq!(merged_file.entries().count(), 2);
+    }
+
+    #[test]
+    fn サイズ0のデータも取り出せる() {
+        let data = build(2, &[0, 2], &[1, 2]);
+        let merged_file = MergedFile::try_from(data).unwrap();
+
+        assert_eq!(merged_file.entry(0).unwrap(), &[] as &[u8]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[1, 2]);
+    }
+
+    #[test]
+    fn データが空ならエラーを返す() {
+        let result = MergedFile::try_from(&[] as &[u8]);
+        assert_eq!(result.unwrap_err(), MergedFileError::EmptyData);
+    }
+
+    #[test]
+    fn ヘッダーが途中で切れているならエラーを返す() {
+        let result = MergedFile::try_from(&[1u8, 0, 0] as &[u8]);
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedHeader { actual: 3 }
+        );
+    }
+
+    #[test]
+    fn 画像枚数が0以下ならエラーを返す() {
+        let result = MergedFile::try_from(build(0, &[], &[]));
+        assert_eq!(result.unwrap_err(), MergedFileError::InvalidCount(0));
+
+        let result = MergedFile::try_from(build(-1, &[], &[]));
+        assert_eq!(result.unwrap_err(), MergedFileError::InvalidCount(-1));
+    }
+
+    #[test]
+    fn 画像枚数に対してサイズ一覧が足りないならエラーを返す() {
+        let result = MergedFile::try_from(build(3, &[1, 1], &[]));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::CountMismatch {
+                count: 3,
+                available: 2
+            }
+        );
+    }
+
+    #[test]
+    fn 負のサイズならエラーを返す() {
+        let result = MergedFile::try_from(build(2, &[1, -1], &[1]));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::NegativeSize { index: 1, size: -1 }
+        );
+    }
+
+    #[test]
+    fn データが途中で切れているならエラーを返す() {
+        let result = MergedFile::try_from(build(1, &[5], &[1, 2, 3]));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::TruncatedData {
+                expected: 13,
+                actual: 11
+            }
+        );
+    }
+
+    #[test]
+    fn 末尾に余分なデータがあるならエラーを返す() {
+        let result = MergedFile::try_from(build(1, &[2], &[1, 2, 3]));
+        assert_eq!(
+            result.unwrap_err(),
+            MergedFileError::TrailingData {
+                expected: 10,
+                actual: 11
+            }
+        );
+    }
+
+    #[test]
+    fn 壊れていないファイルなら問題なく構造を読み取れる() {
+        let data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        let layout = MergedFileLayout::parse(&data).unwrap();
+
+        assert!(layout.errors.is_empty());
+        assert!(layout.entries.iter().all(|entry| entry.error.is_none()));
+        assert_eq!(layout.version, FormatVersion::V2);
+        assert_eq!(layout.count, 2);
+        assert_eq!(layout.entries.len(), 2);
+        assert_eq!(layout.entries[1].size, 2);
+        let range = layout.entries[1].range.clone().unwrap();
+        assert_eq!(&data[range], &[4, 5]);
+        assert!(layout.entries[1].checksum.unwrap().is_valid());
+        assert!(layout.file_checksum.unwrap().is_valid());
+    }
+
+    #[test]
+    fn 途中で切れたファイルでも読み取れた分の構造と画像ごとの問題を返す() {
+        let mut data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        data.truncate(data.len() - 5);
+        let layout = MergedFileLayout::parse(&data).unwrap();
+
+        assert_eq!(layout.entries.len(), 2);
+        let first = &layout.entries[0];
+        assert_eq!(first.error, None);
+        assert!(first.checksum.unwrap().is_valid());
+        let second = &layout.entries[1];
+        assert_eq!(second.range.as_ref().unwrap().len(), 1);
+        assert!(matches!(
+            second.error,
+            Some(MergedFileError::TruncatedData { .. })
+        ));
+        assert_eq!(second.checksum.unwrap().actual, None);
+        assert_eq!(layout.file_checksum, None);
+        assert!(matches!(
+            layout.errors[..],
+            [MergedFileError::TruncatedData { .. }]
+        ));
+    }
+
+    #[test]
+    fn チェックサムが一致しない画像だけに問題を返す() {
+        let mut data = build_with_checksums(&[&[1, 2, 3], &[4, 5]]);
+        let len = data.len();
+        data[len - 5] ^= 0xff;
+        let layout = MergedFileLayout::parse(&data).unwrap();
+
+        assert_eq!(layout.entries[0].error, None);
+        assert!(matches!(
+            layout.entries[1].error,
+            Some(MergedFileError::ChecksumMismatch { index: 1, .. })
+        ));
+        assert!(!layout.entries[1].checksum.unwrap().is_valid());
+        assert!(!layout.file_checksum.unwrap().is_valid());
+        assert!(matches!(
+            layout.errors[..],
+            [MergedFileError::FileChecksumMismatch { .. }]
+        ));
+    }
+
+    #[test]
+    fn サイズ一覧が途中で切れているなら読み取れた分だけを返す() {
+        let layout = MergedFileLayout::parse(&build(3, &[1], &[])).unwrap();
+
+        assert_eq!(layout.count, 3);
+        assert_eq!(layout.entries.len(), 1);
+        assert_eq!(layout.entries[0].size, 1);
+        assert_eq!(layout.entries[0].range, None);
+        assert_eq!(
+            layout.errors,
+            vec![MergedFileError::CountMismatch {
+                count: 3,
+                available: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn 末尾に余分なデータがあるならファイル全体の問題として返す() {
+        let layout = MergedFileLayout::parse(&build(1, &[2], &[1, 2, 3])).unwrap();
+
+        assert_eq!(layout.entries[0].error, None);
+        assert_eq!(
+            layout.errors,
+            vec![MergedFileError::TrailingData {
+                expected: 10,
+                actual: 11
+            }]
+        );
+    }
+
+    #[test]
+    fn 大きなサイズが続いても負のデータ位置とみなさない() {
+        let layout = MergedFileLayout::parse(&build(3, &[i32::MAX, i32::MAX, 1], &[])).unwrap();
+
+        let third = &layout.entries[2];
+        assert_eq!(
+            third.error,
+            Some(MergedFileError::TruncatedData {
+                expected: 16 + i32::MAX as usize * 2 + 1,
+                actual: 16
+            })
+        );
+        assert_eq!(third.range, Some(16..16));
+    }
+
+    #[test]
+    fn ヘッダーを読み取れないならエラーを返す() {
+        assert_eq!(
+            MergedFileLayout::parse(&[]).unwrap_err(),
+            MergedFileError::EmptyData
+        );
+        assert_eq!(
+            MergedFileLayout::parse(&with_header(2, 0, &[])).unwrap_err(),
+            MergedFileError::TruncatedHeader { actual: 12 }
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use serde_json::Value;
+use std::ops::Range;
+
+use crate::model::error::MetadataError;
+
+/// 画像ごとのメタデータ一覧を表すモデル
+///
+/// `[{"fileName": "a.png"}, {"fileName": "b.jpg", "title": "..."}]` のような
+/// JSON オブジェクトの配列で、要素の順番は画像の順番と対応する
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageMetadata {
+    /// 各画像のメタデータ（JSON オブジェクト）
+    entries: Vec<Value>,
+}
+
+impl ImageMetadata {
+    /// 各画像のメタデータ（JSON オブジェクト）から作る
+    pub fn from_entries(entries: Vec<Value>) -> Result<Self, MetadataError> {
+        if let Some(index) = entries.iter().position(|entry| !entry.is_object()) {
+            return Err(MetadataError::EntryNotObject(index));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// メタデータの件数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 指定した画像のメタデータ
+    pub fn get(&self, index: usize) -> Option<&Value> {
+        self.entries.get(index)
+    }
+
+    /// 指定した範囲の画像のメタデータだけを取り出す
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        Self {
+            entries: self.entries[range].to_vec(),
+        }
+    }
+
+    /// 独自形式に書き込むための JSON 文字列（空白なし）
+    pub fn to_json(&self) -> String {
+        Value::Array(self.entries.clone()).to_string()
+    }
+}
+
+impl std::str::FromStr for ImageMetadata {
+    type Err = MetadataError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value: Value =
+            serde_json::from_str(s).map_err(|e| MetadataError::InvalidJson(e.to_string()))?;
+
+        let Value::Array(entries) = value else {
+            return Err(MetadataError::NotArray);
+        };
+
+        Self::from_entries(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn オブジェクトの配列なら読み取れる() {
+        let metadata =
+            ImageMetadata::from_str(r#"[{"fileName": "a.png"}, {"fileName": "b.jpg", "title": "B"}]"#)
+                .unwrap();
+
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(
+            metadata.to_json(),
+            r#"[{"fileName":"a.png"},{"fileName":"b.jpg","title":"B"}]"#
+        );
+    }
+
+    #[test]
+    fn 範囲を指定して取り出せる() {
+        let metadata = ImageMetadata::from_str(r#"[{"a": 1}, {"b": 2}, {"c": 3}]"#).unwrap();
+        let sliced = metadata.slice(1..3);
+
+        assert_eq!(sliced.len(), 2);
+        assert_eq!(sliced.to_json(), r#"[{"b":2},{"c":3}]"#);
+    }
+
+    #[test]
+    fn jsonでないならエラーを返す() {
+        let result = ImageMetadata::from_str("not json");
+        assert!(matches!(result, Err(MetadataError::InvalidJson(_))));
+    }
+
+    #[test]
+    fn 配列でないならエラーを返す() {
+        let result = ImageMetadata::from_str(r#"{"fileName": "a.png"}"#);
+        assert_eq!(result.unwrap_err(), MetadataError::NotArray);
+    }
+
+    #[test]
+    fn 要素がオブジェクトでないならエラーを返す() {
+        let result = ImageMetadata::from_str(r#"[{"fileName": "a.png"}, "b.jpg"]"#);
+        assert_eq!(result.unwrap_err(), MetadataError::EntryNotObject(1));
+    }
+}
@@ -0,0 +1,164 @@
+use image::imageops::FilterType;
+
+use crate::model::error::MipmapError;
+
+/// ミップマップを縮小するときのフィルター（crunch の `-mipFilter`）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MipFilter {
+    Box,
+    Tent,
+    Lanczos4,
+    Mitchell,
+    /// crunch の既定値
+    #[default]
+    Kaiser,
+}
+
+impl MipFilter {
+    /// フィルターの名前
+    pub fn name(&self) -> &'static str {
+        match self {
+            MipFilter::Box => "box",
+            MipFilter::Tent => "tent",
+            MipFilter::Lanczos4 => "lanczos4",
+            MipFilter::Mitchell => "mitchell",
+            MipFilter::Kaiser => "kaiser",
+        }
+    }
+
+    /// crunch を使わずに縮小する場合のフィルター
+    ///
+    /// image クレートにないフィルターは特性の近いものにする（box は 2x2 の平均になる triangle、mitchell は catmull-rom、kaiser は lanczos3）
+    pub fn resize_filter(&self) -> FilterType {
+        match self {
+            MipFilter::Box | MipFilter::Tent => FilterType::Triangle,
+            MipFilter::Lanczos4 | MipFilter::Kaiser => FilterType::Lanczos3,
+            MipFilter::Mitchell => FilterType::CatmullRom,
+        }
+    }
+}
+
+impl std::str::FromStr for MipFilter {
+    type Err = MipmapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "box" => Ok(MipFilter::Box),
+            "tent" => Ok(MipFilter::Tent),
+            "lanczos4" => Ok(MipFilter::Lanczos4),
+            "mitchell" => Ok(MipFilter::Mitchell),
+            "kaiser" => Ok(MipFilter::Kaiser),
+            _ => Err(MipmapError::UnsupportedFilter(s.to_string())),
+        }
+    }
+}
+
+/// ミップマップを生成する場合の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MipmapOptions {
+    /// 最も小さい段の長辺のピクセル数の下限（crunch の `-mipSmallest`）
+    pub min_size: u32,
+    /// 縮小に使うフィルター
+    pub filter: MipFilter,
+}
+
+impl Default for MipmapOptions {
+    /// 1x1 まで kaiser で縮小する
+    fn default() -> Self {
+        Self {
+            min_size: 1,
+            filter: MipFilter::default(),
+        }
+    }
+}
+
+impl MipmapOptions {
+    /// 最小サイズとフィルターの名前から作る（指定しなければデフォルト）
+    pub fn parse(min_size: Option<&str>, filter: Option<&str>) -> Result<Self, MipmapError> {
+        let mut options = Self::default();
+        if let Some(min_size) = min_size {
+            options.min_size = min_size
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|size| *size > 0)
+                .ok_or_else(|| MipmapError::InvalidMinSize(min_size.to_string()))?;
+        }
+        if let Some(filter) = filter {
+            options.filter = filter.parse()?;
+        }
+        Ok(options)
+    }
+}
+
+/// 変換後のDDSのミップマップの段数（ミップマップなしなら 1）
+///
+/// 縦横を半分（1 未満にはしない）にしながら、長辺が最小サイズ以下になるか 1x1 になるまで段を重ねる
+pub fn mip_count(width: u32, height: u32, mipmaps: Option<&MipmapOptions>) -> u32 {
+    let Some(mipmaps) = mipmaps else {
+        return 1;
+    };
+    let (mut width, mut height) = (width.max(1), height.max(1));
+    let mut count = 1;
+    while width.max(height) > mipmaps.min_size && (width, height) != (1, 1) {
+        width = (width / 2).max(1);
+        height = (height / 2).max(1);
+        count += 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 指定しなければ1x1までkaiserで縮小する() {
+        let options = MipmapOptions::parse(None, None).unwrap();
+        assert_eq!(options, MipmapOptions::default());
+        assert_eq!(options.min_size, 1);
+        assert_eq!(options.filter, MipFilter::Kaiser);
+    }
+
+    #[test]
+    fn 最小サイズとフィルターを指定できる() {
+        let options = MipmapOptions::parse(Some(" 16 "), Some("Box")).unwrap();
+        assert_eq!(options.min_size, 16);
+        assert_eq!(options.filter, MipFilter::Box);
+    }
+
+    #[test]
+    fn 不正な値ならエラーを返す() {
+        assert_eq!(
+            MipmapOptions::parse(Some("0"), None).unwrap_err(),
+            MipmapError::InvalidMinSize("0".to_string())
+        );
+        assert_eq!(
+            MipmapOptions::parse(Some("-4"), None).unwrap_err(),
+            MipmapError::InvalidMinSize("-4".to_string())
+        );
+        assert_eq!(
+            MipmapOptions::parse(None, Some("bilinear")).unwrap_err(),
+            MipmapError::UnsupportedFilter("bilinear".to_string())
+        );
+    }
+
+    #[test]
+    fn 段数を計算できる() {
+        let full = MipmapOptions::default();
+        assert_eq!(mip_count(256, 256, None), 1);
+        // 256, 128, 64, 32, 16, 8, 4, 2, 1
+        assert_eq!(mip_count(256, 256, Some(&full)), 9);
+        // 縦長でも長辺が 1 になるまで: 8x2, 4x1, 2x1, 1x1
+        assert_eq!(mip_count(8, 2, Some(&full)), 4);
+
+        let min_16 = MipmapOptions {
+            min_size: 16,
+            ..Default::default()
+        };
+        // 256, 128, 64, 32, 16
+        assert_eq!(mip_count(256, 256, Some(&min_16)), 5);
+        // 元の画像が最小サイズ以下なら 1 段だけ
+        assert_eq!(mip_count(8, 8, Some(&min_16)), 1);
+    }
+}
@@ -1,5 +1,32 @@
+pub mod atlas;
+pub mod block_compression;
+pub mod conversion;
+pub mod cubemap;
+pub mod dds;
+pub mod dimension_fix;
+pub mod edit_script;
+pub mod encoding;
 pub mod error;
+pub mod flipbook;
 pub mod image;
+pub mod input_format;
+pub mod merged_file;
+pub mod metadata;
+pub mod mipmap;
+pub mod texture_array;
+pub mod texture_format;
 
+pub use atlas::AtlasLayout;
+pub use conversion::{ColorSpace, CompressionOptions, ConversionOptions, ConversionPolicy};
+pub use dds::{DdsFormat, DdsHeader};
+pub use dimension_fix::{DimensionFix, FixedDimensions};
+pub use edit_script::{EditScript, EntrySource};
+pub use encoding::OutputEncoding;
 pub use error::ImageError;
+pub use flipbook::FlipbookLayout;
 pub use image::Image;
+pub use input_format::AllowedFormats;
+pub use merged_file::{EntryDescriptor, FormatVersion, MergedFile, MergedFileLayout};
+pub use metadata::ImageMetadata;
+pub use mipmap::MipmapOptions;
+pub use texture_format::TextureFormat;
@@ -0,0 +1,164 @@
+use crate::model::dds::{texture_array_header, DdsHeader, DDS_HEADER_SIZE};
+use crate::model::error::TextureArrayError;
+use crate::model::Image;
+
+/// テクスチャ配列の最大要素数（D3D11 の上限）
+pub const MAX_TEXTURE_ARRAY_SIZE: usize = 2048;
+
+/// 全ての画像が同じサイズか確認し、(横幅, 高さ) を返す
+pub fn validate_layers(layers: &[Image]) -> Result<(u32, u32), TextureArrayError> {
+    let first = layers.first().ok_or(TextureArrayError::Empty)?;
+    if layers.len() > MAX_TEXTURE_ARRAY_SIZE {
+        return Err(TextureArrayError::TooManyLayers {
+            count: layers.len(),
+            max: MAX_TEXTURE_ARRAY_SIZE,
+        });
+    }
+
+    let (expected_width, expected_height) = (first.width, first.height);
+    if let Some((index, image)) = layers
+        .iter()
+        .enumerate()
+        .find(|(_, image)| (image.width, image.height) != (expected_width, expected_height))
+    {
+        return Err(TextureArrayError::SizeMismatch {
+            index,
+            width: image.width,
+            height: image.height,
+            expected_width,
+            expected_height,
+        });
+    }
+    Ok((expected_width, expected_height))
+}
+
+/// 画像ごとに変換した DDS を、DX10 拡張ヘッダー付きの1つのテクスチャ配列の DDS にまとめる
+///
+/// ヘッダーは最初の画像のものを元にし、その後ろに各画像のデータを入力の順番で並べる
+pub fn build_texture_array_dds(
+    layers: &[Vec<u8>],
+) -> Result<(DdsHeader, Vec<u8>), TextureArrayError> {
+    if layers.is_empty() {
+        return Err(TextureArrayError::Empty);
+    }
+    if layers.len() > MAX_TEXTURE_ARRAY_SIZE {
+        return Err(TextureArrayError::TooManyLayers {
+            count: layers.len(),
+            max: MAX_TEXTURE_ARRAY_SIZE,
+        });
+    }
+
+    let headers = layers
+        .iter()
+        .enumerate()
+        .map(|(index, data)| {
+            DdsHeader::parse(data).map_err(|e| TextureArrayError::InvalidLayer {
+                index,
+                reason: e.to_string(),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = headers[0];
+    if let Some(index) = headers.iter().position(|header| *header != first) {
+        return Err(TextureArrayError::LayerMismatch { index });
+    }
+    let dxgi_format = first
+        .format
+        .dxgi_format()
+        .ok_or_else(|| TextureArrayError::UnsupportedFormat(first.format.name()))?;
+
+    let mut output = texture_array_header(&layers[0], dxgi_format, layers.len() as u32);
+    for data in layers {
+        output.extend_from_slice(&data[DDS_HEADER_SIZE..]);
+    }
+    let header = DdsHeader {
+        array_size: layers.len() as u32,
+        ..first
+    };
+    Ok((header, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::dds::{PixelFormat, DDS_HEADER_DXT10_SIZE};
+    use image::{DynamicImage, RgbaImage};
+
+    fn image(width: u32, height: u32) -> Image {
+        Image::encode(&DynamicImage::ImageRgba8(RgbaImage::new(width, height))).unwrap()
+    }
+
+    /// 要素ごとに異なる値で埋めたデータを持つ DDS
+    fn layer_dds(fill: u8) -> Vec<u8> {
+        let mut data = build_dds(8, 4, 1, b"DXT1");
+        data.extend(std::iter::repeat_n(fill, 16));
+        data
+    }
+
+    #[test]
+    fn 同じサイズなら横幅と高さを返す() {
+        let layers = vec![image(8, 4); 3];
+        assert_eq!(validate_layers(&layers).unwrap(), (8, 4));
+    }
+
+    #[test]
+    fn サイズの異なる画像があるならエラーを返す() {
+        let layers = vec![image(8, 4), image(8, 4), image(4, 8)];
+        assert_eq!(
+            validate_layers(&layers).unwrap_err(),
+            TextureArrayError::SizeMismatch {
+                index: 2,
+                width: 4,
+                height: 8,
+                expected_width: 8,
+                expected_height: 4
+            }
+        );
+    }
+
+    #[test]
+    fn 画像がないならエラーを返す() {
+        assert_eq!(validate_layers(&[]).unwrap_err(), TextureArrayError::Empty);
+        assert_eq!(
+            build_texture_array_dds(&[]).unwrap_err(),
+            TextureArrayError::Empty
+        );
+    }
+
+    #[test]
+    fn dx10拡張ヘッダーの後ろに各要素のデータを順番に並べる() {
+        let layers: Vec<Vec<u8>> = (0..3).map(layer_dds).collect();
+
+        let (header, output) = build_texture_array_dds(&layers).unwrap();
+
+        assert_eq!(header.array_size, 3);
+        assert_eq!(header.format, PixelFormat::Dxt1);
+        let data_offset = DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE;
+        assert_eq!(output.len(), data_offset + 3 * 16);
+        for i in 0..3 {
+            assert_eq!(output[data_offset + i * 16], i as u8);
+        }
+        assert_eq!(DdsHeader::parse(&output).unwrap(), header);
+    }
+
+    #[test]
+    fn 変換後の要素のミップマップ数が異なるならエラーを返す() {
+        let layers = vec![layer_dds(0), build_dds(8, 4, 2, b"DXT1")];
+
+        assert_eq!(
+            build_texture_array_dds(&layers).unwrap_err(),
+            TextureArrayError::LayerMismatch { index: 1 }
+        );
+    }
+
+    #[test]
+    fn 未対応のフォーマットならエラーを返す() {
+        let layers = vec![build_dds(4, 4, 1, b"ATI2"); 2];
+
+        assert_eq!(
+            build_texture_array_dds(&layers).unwrap_err(),
+            TextureArrayError::UnsupportedFormat("ATI2".to_string())
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use crate::model::error::{ImageError, TextureFormatError};
+use crate::model::{DdsFormat, Image};
+
+/// リクエストで指定する変換後のフォーマット
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// 不透明でないピクセルがあれば DXT5、なければ DXT1
+    #[default]
+    Auto,
+    /// 常にこのフォーマットにする
+    Fixed(DdsFormat),
+}
+
+impl TextureFormat {
+    /// 画像に合わせて変換後のフォーマットを決める
+    pub fn resolve(&self, image: &Image) -> Result<DdsFormat, ImageError> {
+        self.resolve_all(std::slice::from_ref(image))
+    }
+
+    /// 全ての画像を同じフォーマットにする場合に、変換後のフォーマットを決める
+    ///
+    /// 自動の場合は、1枚でも不透明でないピクセルを持つ画像があれば DXT5 にする
+    pub fn resolve_all(&self, images: &[Image]) -> Result<DdsFormat, ImageError> {
+        match self {
+            TextureFormat::Fixed(format) => Ok(*format),
+            TextureFormat::Auto => {
+                for image in images {
+                    if image.has_alpha()? {
+                        return Ok(DdsFormat::Dxt5);
+                    }
+                }
+                Ok(DdsFormat::Dxt1)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for TextureFormat {
+    type Err = TextureFormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(TextureFormat::Auto),
+            "dxt1" => Ok(TextureFormat::Fixed(DdsFormat::Dxt1)),
+            "dxt5" => Ok(TextureFormat::Fixed(DdsFormat::Dxt5)),
+            _ => Err(TextureFormatError::UnsupportedFormat(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, Rgb, RgbImage, Rgba, RgbaImage};
+    use std::str::FromStr;
+
+    fn rgba(alpha: u8) -> Image {
+        let img = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, alpha]));
+        Image::encode(&DynamicImage::ImageRgba8(img)).unwrap()
+    }
+
+    #[test]
+    fn 文字列から変換できる() {
+        assert_eq!(
+            TextureFormat::from_str("auto").unwrap(),
+            TextureFormat::Auto
+        );
+        assert_eq!(
+            TextureFormat::from_str(" DXT5 ").unwrap(),
+            TextureFormat::Fixed(DdsFormat::Dxt5)
+        );
+        assert_eq!(
+            TextureFormat::from_str("bc7").unwrap_err(),
+            TextureFormatError::UnsupportedFormat("bc7".to_string())
+        );
+    }
+
+    #[test]
+    fn 自動なら透過のある画像はdxt5にする() {
+        assert_eq!(
+            TextureFormat::Auto.resolve(&rgba(128)).unwrap(),
+            DdsFormat::Dxt5
+        );
+    }
+
+    #[test]
+    fn 自動ならアルファチャンネルがあっても全て不透明ならdxt1にする() {
+        assert_eq!(
+            TextureFormat::Auto.resolve(&rgba(255)).unwrap(),
+            DdsFormat::Dxt1
+        );
+        let rgb = Image::encode(&DynamicImage::ImageRgb8(RgbImage::from_pixel(
+            4,
+            4,
+            Rgb([0, 0, 0]),
+        )))
+        .unwrap();
+        assert_eq!(TextureFormat::Auto.resolve(&rgb).unwrap(), DdsFormat::Dxt1);
+    }
+
+    #[test]
+    fn 自動なら1枚でも透過があれば全てdxt5にする() {
+        let images = [rgba(255), rgba(0)];
+        assert_eq!(
+            TextureFormat::Auto.resolve_all(&images).unwrap(),
+            DdsFormat::Dxt5
+        );
+    }
+
+    #[test]
+    fn 指定したフォーマットは画像によらずそのまま使う() {
+        let format = TextureFormat::Fixed(DdsFormat::Dxt1);
+        assert_eq!(format.resolve(&rgba(0)).unwrap(), DdsFormat::Dxt1);
+    }
+}
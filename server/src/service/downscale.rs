@@ -0,0 +1,174 @@
+use crate::model::dds::dds_file_size;
+use crate::model::mipmap::mip_count;
+use crate::model::{DdsFormat, MipmapOptions};
+use crate::service::error::{ServiceError, ServiceResult};
+
+/// 縮小前後の画像サイズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryDimensions {
+    /// 縮小前の横幅 (px)
+    pub original_width: u32,
+    /// 縮小前の高さ (px)
+    pub original_height: u32,
+    /// 縮小後の横幅 (px)
+    pub width: u32,
+    /// 縮小後の高さ (px)
+    pub height: u32,
+}
+
+impl EntryDimensions {
+    /// 縮小されたか
+    pub fn is_downscaled(&self) -> bool {
+        self.width != self.original_width || self.height != self.original_height
+    }
+
+    /// 現在のサイズで指定したフォーマットに変換したときのDDSのサイズ（ミップマップ込み）
+    fn dds_size(&self, format: DdsFormat, mipmaps: Option<&MipmapOptions>) -> usize {
+        let mip_count = mip_count(self.width, self.height, mipmaps);
+        dds_file_size(self.width, self.height, mip_count, format)
+    }
+}
+
+/// 縦横比を保ったまま一回り（7/8）小さくする
+///
+/// 縦横とも 4 の倍数に切り捨て、4 未満にはしない
+/// これ以上小さくできないなら None
+fn shrink(width: u32, height: u32) -> Option<(u32, u32)> {
+    let scale = |value: u32| ((value * 7 / 8) / 4 * 4).max(4);
+    let next = (scale(width), scale(height));
+    (next != (width, height)).then_some(next)
+}
+
+/// 独自形式が上限に収まるまで、DDS が最も大きくなる画像から順に縮小する計画を立てる
+///
+/// DXT1 / DXT5 のサイズは画像サイズだけで決まるので、crunch で変換する前に計画できる
+/// `formats` には各画像の変換後のフォーマットを、`mipmaps` にはミップマップの設定を、
+/// `output_size` には各DDSのサイズから出力ファイルのサイズを求める関数を渡す
+pub(crate) fn plan_downscale(
+    dimensions: &[(u32, u32)],
+    formats: &[DdsFormat],
+    mipmaps: Option<&MipmapOptions>,
+    limit: usize,
+    output_size: impl Fn(&[usize]) -> usize,
+) -> ServiceResult<Vec<EntryDimensions>> {
+    let mut planned: Vec<EntryDimensions> = dimensions
+        .iter()
+        .map(|&(width, height)| EntryDimensions {
+            original_width: width,
+            original_height: height,
+            width,
+            height,
+        })
+        .collect();
+
+    loop {
+        let dds_sizes: Vec<usize> = planned
+            .iter()
+            .zip(formats)
+            .map(|(entry, format)| entry.dds_size(*format, mipmaps))
+            .collect();
+        if output_size(&dds_sizes) <= limit {
+            return Ok(planned);
+        }
+
+        let Some((largest, _)) = dds_sizes.iter().enumerate().max_by_key(|(_, size)| **size) else {
+            return Ok(planned);
+        };
+        let entry = &mut planned[largest];
+        let Some((width, height)) = shrink(entry.width, entry.height) else {
+            return Err(ServiceError::Validation(
+                "merged data does not fit in 10 MB even after downscaling".to_string(),
+            ));
+        };
+        entry.width = width;
+        entry.height = height;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn 一回り小さくしても4の倍数を保つ() {
+        assert_eq!(shrink(1024, 512), Some((896, 448)));
+        assert_eq!(shrink(12, 8), Some((8, 4)));
+        assert_eq!(shrink(4, 4), None);
+    }
+
+    #[test]
+    fn 上限に収まるなら縮小しない() {
+        let planned = plan_downscale(&[(64, 64)], &[DdsFormat::Dxt1], None, usize::MAX, |sizes| {
+            sizes.iter().sum()
+        })
+        .unwrap();
+
+        assert_eq!(planned[0].width, 64);
+        assert!(!planned[0].is_downscaled());
+    }
+
+    #[test]
+    fn 最も大きい画像から縮小して上限に収める() {
+        let formats = [DdsFormat::Dxt1; 2];
+        let limit =
+            dds_file_size(256, 256, 1, DdsFormat::Dxt1) + dds_file_size(64, 64, 1, DdsFormat::Dxt1);
+        let planned = plan_downscale(&[(64, 64), (512, 256)], &formats, None, limit, |sizes| {
+            sizes.iter().sum()
+        })
+        .unwrap();
+
+        // 小さい画像はそのまま
+        assert!(!planned[0].is_downscaled());
+
+        // 大きい画像は縦横比を保ち、4 の倍数のまま縮小される
+        let resized = planned[1];
+        assert!(resized.is_downscaled());
+        assert_eq!(resized.width % 4, 0);
+        assert_eq!(resized.height % 4, 0);
+        assert!(resized.width.abs_diff(resized.height * 2) <= 4);
+        assert!(
+            planned
+                .iter()
+                .map(|entry| entry.dds_size(DdsFormat::Dxt1, None))
+                .sum::<usize>()
+                <= limit
+        );
+    }
+
+    #[test]
+    fn 縮小しきっても収まらないならエラーを返す() {
+        let result = plan_downscale(&[(64, 64)], &[DdsFormat::Dxt1], None, 10, |sizes| {
+            sizes.iter().sum()
+        });
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn dxt5の画像はdxt1の倍のサイズとして見積もる() {
+        let limit = dds_file_size(64, 64, 1, DdsFormat::Dxt1);
+        let formats = [DdsFormat::Dxt5];
+        let planned = plan_downscale(&[(64, 64)], &formats, None, limit, |sizes| {
+            sizes.iter().sum()
+        })
+        .unwrap();
+
+        // NOTE: DXT5 は DXT1 の倍なので、同じサイズのままでは収まらない
+        assert!(planned[0].is_downscaled());
+        assert!(planned[0].dds_size(DdsFormat::Dxt5, None) <= limit);
+    }
+
+    #[test]
+    fn ミップマップを生成するならその分も含めて見積もる() {
+        let limit = dds_file_size(64, 64, 1, DdsFormat::Dxt1);
+        let formats = [DdsFormat::Dxt1];
+        let mipmaps = MipmapOptions::default();
+        let planned = plan_downscale(&[(64, 64)], &formats, Some(&mipmaps), limit, |sizes| {
+            sizes.iter().sum()
+        })
+        .unwrap();
+
+        // NOTE: ミップマップの分（約 1/3）だけ大きくなるので、同じサイズのままでは収まらない
+        assert!(planned[0].is_downscaled());
+        assert!(planned[0].dds_size(DdsFormat::Dxt1, Some(&mipmaps)) <= limit);
+    }
+}
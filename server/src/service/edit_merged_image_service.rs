@@ -0,0 +1,622 @@
+use async_trait::async_trait;
+use log::{error, info};
+use serde_json::{json, Value};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::infrastructure::{Converter, Storage};
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, DimensionFix,
+    EditScript, EntrySource, FixedDimensions, FormatVersion, ImageMetadata, MergedFile,
+    MipmapOptions, OutputEncoding, TextureFormat,
+};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::{
+    create_merged_format_of, describe_entry, image_model_of, MergedFormatSections,
+    MAX_MERGED_DATA_SIZE,
+};
+
+/// 束ねたファイルの編集時のオプション
+#[derive(Debug, Clone)]
+pub struct EditMergedImageOptions {
+    /// 既存ファイルとアップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 縦横のピクセル数が4の倍数でない場合の補正方法（指定しなければエラーにする）
+    pub dimension_fix: Option<DimensionFix>,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+    /// ミップマップの設定（None ならミップマップなし、デフォルトは 1x1 までのミップマップを生成する）
+    pub mipmaps: Option<MipmapOptions>,
+    /// 変換前に各画像の上下を反転するか（Unity の `LoadRawTextureData` で正しい向きにする）
+    pub flip_y: bool,
+}
+
+impl Default for EditMergedImageOptions {
+    fn default() -> Self {
+        Self {
+            encoding: OutputEncoding::default(),
+            dimension_fix: None,
+            format: TextureFormat::default(),
+            compression: CompressionOptions::default(),
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: false,
+        }
+    }
+}
+
+/// 束ねたファイルの編集結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditMergedImageResult {
+    /// 編集後の各画像の出どころ（編集後の順番）
+    pub sources: Vec<EntrySource>,
+    /// 挿入した各画像の縦横のピクセル数を補正した結果（補正しなかった画像は None）
+    pub fixed_dimensions: Vec<Option<FixedDimensions>>,
+}
+
+#[async_trait]
+pub trait EditMergedImageService: Send + Sync {
+    async fn execute(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        script: &EditScript,
+        images: &[Vec<u8>],
+        options: &EditMergedImageOptions,
+    ) -> ServiceResult<EditMergedImageResult>;
+}
+
+pub struct EditMergedImageServiceImpl {
+    converter: Arc<dyn Converter>,
+    storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
+}
+
+impl EditMergedImageServiceImpl {
+    pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl EditMergedImageService for EditMergedImageServiceImpl {
+    async fn execute(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        script: &EditScript,
+        images: &[Vec<u8>],
+        options: &EditMergedImageOptions,
+    ) -> ServiceResult<EditMergedImageResult> {
+        if download_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "download url must not be empty".to_string(),
+            ));
+        }
+
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        if images.len() != script.insert_count() {
+            return Err(ServiceError::Validation(format!(
+                "image count must match insert operations (images: {}, inserts: {})",
+                images.len(),
+                script.insert_count()
+            )));
+        }
+
+        self.allowed_formats.check_all(images)?;
+
+        // 挿入する画像データをモデルに変換（バリデーション付き）
+        let mut image_models = Vec::with_capacity(images.len());
+        let mut fixed_dimensions = Vec::with_capacity(images.len());
+        for (index, image_bytes) in images.iter().enumerate() {
+            let (image_model, fixed) = image_model_of(
+                index,
+                image_bytes,
+                options.dimension_fix.as_ref(),
+                options.flip_y,
+            )?;
+            image_models.push(image_model);
+            fixed_dimensions.push(fixed);
+        }
+
+        info!(
+            "Starting edit_merged_image_service (inserts: {})",
+            images.len()
+        );
+
+        // 既存の独自形式ファイルを取得して分解
+        let merged_data = self
+            .storage
+            .download_file(download_url, MAX_MERGED_DATA_SIZE)
+            .await
+            .map_err(|e| {
+                error!("Failed to download merged file from storage: {}", e);
+                ServiceError::from_download(e)
+            })?;
+        let merged_data = options.encoding.decode(merged_data).map_err(|e| {
+            ServiceError::Validation(format!("existing merged file is invalid: {}", e))
+        })?;
+        let merged_file = MergedFile::try_from(merged_data).map_err(|e| {
+            ServiceError::Validation(format!("existing merged file is invalid: {}", e))
+        })?;
+        let existing_metadata = merged_file
+            .metadata()
+            .map(ImageMetadata::from_str)
+            .transpose()
+            .map_err(|e| {
+                ServiceError::Validation(format!("existing merged file is invalid: {}", e))
+            })?;
+
+        // 編集後の並びを先に決めてから、挿入する画像だけ変換する
+        let sources = script
+            .apply(merged_file.len())
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+        // NOTE: v1 にはメタデータを書き込む場所がない
+        if merged_file.version() == FormatVersion::V1
+            && script
+                .inserted_metadata()
+                .any(|metadata| metadata.is_some())
+        {
+            return Err(ServiceError::Validation(
+                "metadata requires format version 2".to_string(),
+            ));
+        }
+
+        // NOTE: 途中まで変換してから気付くと無駄になるので、全ての画像の変換の設定を先に確認する
+        let mut conversions = Vec::with_capacity(image_models.len());
+        for image_model in &image_models {
+            let format = options
+                .format
+                .resolve(image_model)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            let conversion = ConversionOptions {
+                mipmaps: options.mipmaps,
+                ..ConversionOptions::new(format, &options.compression)
+            };
+            self.conversion_policy
+                .check(&conversion)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            conversions.push(conversion);
+        }
+
+        let mut inserted_dds_list = Vec::with_capacity(image_models.len());
+        for (image_model, conversion) in image_models.iter().zip(&conversions) {
+            let dds_data = self
+                .converter
+                .jpeg_to_dds(image_model.as_bytes(), conversion)
+                .await
+                .map_err(|e| {
+                    error!("Failed to convert image to dds: {}", e);
+                    ServiceError::from(e)
+                })?;
+            inserted_dds_list.push(dds_data);
+        }
+
+        let dds_data_list: Vec<Vec<u8>> = sources
+            .iter()
+            .map(|source| match *source {
+                EntrySource::Existing(index) => merged_file.entry(index).unwrap().to_vec(),
+                EntrySource::Inserted(index) => inserted_dds_list[index].clone(),
+            })
+            .collect();
+
+        // 既存ファイルが記述子を持つなら挿入した画像の記述子も作る
+        // NOTE: チェックサムは書き込み時に計算し直される
+        let descriptors = match merged_file.descriptors() {
+            Some(existing) => Some(
+                sources
+                    .iter()
+                    .map(|source| match *source {
+                        EntrySource::Existing(index) => Ok(existing[index]),
+                        EntrySource::Inserted(index) => {
+                            describe_entry(&image_models[index], &inserted_dds_list[index])
+                        }
+                    })
+                    .collect::<ServiceResult<Vec<_>>>()?,
+            ),
+            None => None,
+        };
+        let sections = MergedFormatSections {
+            descriptors,
+            metadata: metadata_json_of(&sources, existing_metadata.as_ref(), script)?,
+            checksums: merged_file.checksums().is_some(),
+            shared_data: merged_file.has_shared_data(),
+        };
+
+        // 既存ファイルと同じバージョン・セクション構成の独自形式にまとめ直す
+        let merged_data =
+            create_merged_format_of(merged_file.version(), &dds_data_list, &sections)?;
+        let merged_data = options.encoding.encode(merged_data);
+
+        // エンコード後に 10 MB を超えていたらエラー
+        if merged_data.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "merged data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        // ストレージにアップロード
+        self.storage
+            .upload_file(presigned_url, &merged_data)
+            .await
+            .map_err(|e| {
+                error!("Failed to upload merged file to storage: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        info!("Edit merged image succeeded");
+        Ok(EditMergedImageResult {
+            sources,
+            fixed_dimensions,
+        })
+    }
+}
+
+/// 編集後の並びに合わせたメタデータの JSON 文字列（メタデータセクションを書き込まないなら None）
+///
+/// 既存ファイルがメタデータを持たなくても、挿入時にメタデータを指定したならセクションを作る（v1 なら事前にエラーにする）
+fn metadata_json_of(
+    sources: &[EntrySource],
+    existing: Option<&ImageMetadata>,
+    script: &EditScript,
+) -> ServiceResult<Option<String>> {
+    let inserted: Vec<Option<&Value>> = script.inserted_metadata().collect();
+    if existing.is_none() && inserted.iter().all(Option::is_none) {
+        return Ok(None);
+    }
+
+    let entries = sources
+        .iter()
+        .map(|source| {
+            let entry = match *source {
+                EntrySource::Existing(index) => existing.and_then(|metadata| metadata.get(index)),
+                EntrySource::Inserted(index) => inserted[index],
+            };
+            entry.cloned().unwrap_or_else(|| json!({}))
+        })
+        .collect();
+    let metadata = ImageMetadata::from_entries(entries)
+        .map_err(|e| ServiceError::Validation(format!("metadata is invalid: {}", e)))?;
+    Ok(Some(metadata.to_json()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use std::sync::Mutex;
+    use tokio::fs;
+
+    type Uploaded = Arc<Mutex<Vec<u8>>>;
+
+    fn existing_merged_data(version: FormatVersion, sections: &MergedFormatSections) -> Vec<u8> {
+        create_merged_format_of(version, &[vec![1, 1], vec![2, 2], vec![3, 3]], sections).unwrap()
+    }
+
+    fn recording_storage(existing: Vec<u8>) -> (MockStorage, Uploaded) {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing);
+        (storage, uploaded)
+    }
+
+    fn script(s: &str) -> EditScript {
+        EditScript::from_str(s).unwrap()
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let script = script(r#"[{"op": "delete", "index": 0}]"#);
+        let result = service
+            .execute("", "https://example.com", &script, &[], &Default::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "",
+                &script,
+                &[],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 画像の枚数が挿入操作の件数と異なるならエラーを返す() {
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let script = script(r#"[{"op": "insert", "index": 0}, {"op": "insert", "index": 0}]"#);
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[jpeg_data],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("images: 1, inserts: 2"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 挿入と削除と並べ替えを適用してアップロードする() {
+        let (storage, uploaded) =
+            recording_storage(existing_merged_data(FormatVersion::V1, &Default::default()));
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let script = script(
+            r#"[{"op": "delete", "index": 1}, {"op": "insert", "index": 2}, {"op": "move", "from": 1, "to": 0}]"#,
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[jpeg_data],
+                &Default::default(),
+            )
+            .await
+            .unwrap();
+
+        // [1, 2, 3] -> [1, 3] -> [1, 3, new] -> [3, 1, new]
+        assert_eq!(
+            result.sources,
+            vec![
+                EntrySource::Existing(2),
+                EntrySource::Existing(0),
+                EntrySource::Inserted(0),
+            ]
+        );
+        let merged_file = MergedFile::try_from(uploaded.lock().unwrap().clone()).unwrap();
+        assert_eq!(merged_file.version(), FormatVersion::V1);
+        let entries: Vec<&[u8]> = merged_file.entries().collect();
+        assert_eq!(entries, vec![&[3, 3][..], &[1, 1], &[9, 9]]);
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルの任意セクションを編集後の並びに合わせて書き込む() {
+        use crate::model::dds::test_util::build_dds;
+        use crate::model::{EntryDescriptor, Image};
+
+        let descriptor = |width| EntryDescriptor {
+            width,
+            height: 4,
+            format: 10,
+            mip_count: 1,
+        };
+        let (storage, uploaded) = recording_storage(existing_merged_data(
+            FormatVersion::V2,
+            &MergedFormatSections {
+                descriptors: Some(vec![descriptor(4), descriptor(8), descriptor(12)]),
+                metadata: Some(r#"[{"a":1},{"b":2},{"c":3}]"#.to_string()),
+                checksums: true,
+                shared_data: true,
+            },
+        ));
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(build_dds(8, 8, 1, b"DXT1")))),
+            Arc::new(storage),
+        );
+        let script = script(
+            r#"[{"op": "insert", "index": 0, "metadata": {"new": true}}, {"op": "delete", "index": 2}]"#,
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let image = Image::try_from(jpeg_data.as_slice()).unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[jpeg_data],
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let merged_file = MergedFile::try_from(uploaded.lock().unwrap().clone()).unwrap();
+        assert_eq!(merged_file.len(), 3);
+        assert!(merged_file.checksums().is_some());
+        assert!(merged_file.has_shared_data());
+        let descriptors = merged_file.descriptors().unwrap();
+        assert_eq!(descriptors[0].width, image.width as i32);
+        assert_eq!(descriptors[1], descriptor(4));
+        assert_eq!(descriptors[2], descriptor(12));
+        assert_eq!(
+            merged_file.metadata().unwrap(),
+            r#"[{"new":true},{"a":1},{"c":3}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルにメタデータがなくても挿入時に指定したならv2では書き込む() {
+        let (storage, uploaded) =
+            recording_storage(existing_merged_data(FormatVersion::V2, &Default::default()));
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let script = script(r#"[{"op": "insert", "index": 3, "metadata": {"title": "D"}}]"#);
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[jpeg_data],
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let merged_file = MergedFile::try_from(uploaded.lock().unwrap().clone()).unwrap();
+        assert_eq!(
+            merged_file.metadata().unwrap(),
+            r#"[{},{},{},{"title":"D"}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn v1の既存ファイルに挿入時のメタデータを指定したなら何もアップロードせずエラーを返す() {
+        let (storage, uploaded) =
+            recording_storage(existing_merged_data(FormatVersion::V1, &Default::default()));
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let script = script(r#"[{"op": "insert", "index": 3, "metadata": {"title": "D"}}]"#);
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[jpeg_data],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "metadata requires format version 2");
+        }
+        assert!(uploaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn 範囲外の位置を指定したなら何もアップロードせずエラーを返す() {
+        let (storage, uploaded) =
+            recording_storage(existing_merged_data(FormatVersion::V1, &Default::default()));
+        let service =
+            EditMergedImageServiceImpl::new(Arc::new(MockConverter::succeed()), Arc::new(storage));
+        let script = script(r#"[{"op": "move", "from": 3, "to": 0}]"#);
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("out of range"));
+        }
+        assert!(uploaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルが壊れているならエラーを返す() {
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(vec![1, 2])),
+        );
+        let script = script(r#"[{"op": "delete", "index": 0}]"#);
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn ミップマップと上下反転を指定したなら挿入する画像にも適用する() {
+        use crate::model::Image;
+        use image::{DynamicImage, Rgba, RgbaImage};
+
+        let given = Arc::new(Mutex::new(Vec::new()));
+        let given_clone = given.clone();
+        let converter = MockConverter::with_options(move |image, options| {
+            let decoded = image::load_from_memory(image).unwrap().to_rgba8();
+            let top_row_red = decoded.get_pixel(0, 0) == &Rgba([255, 0, 0, 255]);
+            given_clone
+                .lock()
+                .unwrap()
+                .push((options.mipmaps, top_row_red));
+            Ok(image.to_vec())
+        });
+        let mut img = RgbaImage::from_pixel(4, 8, Rgba([0, 0, 0, 255]));
+        for x in 0..4 {
+            img.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        let png_data = Image::encode(&DynamicImage::ImageRgba8(img)).unwrap().data;
+        let service = EditMergedImageServiceImpl::new(
+            Arc::new(converter),
+            Arc::new(
+                MockStorage::succeed().with_download_data(existing_merged_data(
+                    FormatVersion::V1,
+                    &Default::default(),
+                )),
+            ),
+        );
+        let script = script(r#"[{"op": "insert", "index": 0}]"#);
+        let options = EditMergedImageOptions {
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: true,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                &script,
+                &[png_data],
+                &options,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(
+            *given.lock().unwrap(),
+            vec![(Some(MipmapOptions::default()), false)]
+        );
+    }
+}
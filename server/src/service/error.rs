@@ -1,4 +1,5 @@
 use crate::infrastructure::InfrastructureError;
+use crate::model::error::UnsupportedFormatError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -6,7 +7,19 @@ pub enum ServiceError {
     #[error("validation error: {0}")]
     Validation(String),
     #[error(transparent)]
+    UnsupportedFormat(#[from] UnsupportedFormatError),
+    #[error(transparent)]
     Infrastructure(#[from] InfrastructureError),
 }
 
+impl ServiceError {
+    /// 既存ファイルのダウンロードの失敗を変換する（大きすぎるファイルは入力の誤りとして扱う）
+    pub(crate) fn from_download(e: InfrastructureError) -> Self {
+        match e {
+            InfrastructureError::DownloadTooLarge { .. } => ServiceError::Validation(e.to_string()),
+            e => ServiceError::from(e),
+        }
+    }
+}
+
 pub type ServiceResult<T> = Result<T, ServiceError>;
@@ -0,0 +1,330 @@
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::infrastructure::Storage;
+use crate::model::error::{DdsError, MergedFileError};
+use crate::model::merged_file::ChecksumCheck;
+use crate::model::{DdsHeader, EntryDescriptor, FormatVersion, MergedFileLayout, OutputEncoding};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::MAX_MERGED_DATA_SIZE;
+
+/// 調べる束ねたファイルの取得元
+#[derive(Debug, Clone, Copy)]
+pub enum InspectTarget<'a> {
+    /// ストレージからダウンロードする
+    Url(&'a str),
+    /// アップロードされたファイルをそのまま使う
+    Data(&'a [u8]),
+}
+
+/// 束ねたファイルの1枚分の情報
+#[derive(Debug, PartialEq, Eq)]
+pub struct EntryReport {
+    /// DDSデータの先頭位置（デコード後のファイル先頭からのバイト数、わからなければ None）
+    pub offset: Option<usize>,
+    /// サイズ一覧に書き込まれているDDSデータのサイズ (byte)
+    pub size: i32,
+    /// DDSヘッダーから読み取った情報（位置がわからなければ None、読み取れなければエラー）
+    pub dds: Option<Result<DdsHeader, DdsError>>,
+    /// 書き込まれている記述子（記述子を持つファイルのみ）
+    pub descriptor: Option<EntryDescriptor>,
+    /// CRC32 の検証結果（チェックサムを持つファイルのみ）
+    pub checksum: Option<ChecksumCheck>,
+    /// この画像の問題（切れている、チェックサムが一致しないなど）
+    pub error: Option<MergedFileError>,
+}
+
+/// 束ねたファイルの構造の情報
+#[derive(Debug, PartialEq, Eq)]
+pub struct MergedFileReport {
+    /// フォーマットバージョン
+    pub version: FormatVersion,
+    /// ヘッダーフラグ
+    pub flags: u32,
+    /// ヘッダーに書き込まれている画像枚数
+    pub entry_count: i32,
+    /// 保存されているファイルのサイズ（エンコード後）
+    pub total_size: usize,
+    /// 10 MB の上限までの残り (byte、超えている場合は負)
+    pub headroom: i64,
+    /// メタデータの JSON（メタデータを持つファイルのみ）
+    pub metadata: Option<String>,
+    /// ファイル全体の CRC32 の検証結果（チェックサムを持つファイルのみ）
+    pub file_checksum: Option<ChecksumCheck>,
+    /// サイズ一覧を読み取れた分の各画像の情報
+    pub entries: Vec<EntryReport>,
+    /// 特定の画像によらないファイル全体の問題
+    pub errors: Vec<MergedFileError>,
+}
+
+impl MergedFileReport {
+    /// 問題が1つもないか
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty() && self.entries.iter().all(|entry| entry.error.is_none())
+    }
+}
+
+#[async_trait]
+pub trait InspectMergedImageService: Send + Sync {
+    async fn execute(
+        &self,
+        target: InspectTarget<'_>,
+        encoding: OutputEncoding,
+    ) -> ServiceResult<MergedFileReport>;
+}
+
+pub struct InspectMergedImageServiceImpl {
+    storage: Arc<dyn Storage>,
+}
+
+impl InspectMergedImageServiceImpl {
+    pub fn new(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
+}
+
+#[async_trait]
+impl InspectMergedImageService for InspectMergedImageServiceImpl {
+    async fn execute(
+        &self,
+        target: InspectTarget<'_>,
+        encoding: OutputEncoding,
+    ) -> ServiceResult<MergedFileReport> {
+        let data = match target {
+            InspectTarget::Url(download_url) => {
+                if download_url.trim().is_empty() {
+                    return Err(ServiceError::Validation(
+                        "download url must not be empty".to_string(),
+                    ));
+                }
+                info!("Starting inspect_merged_image_service (download)");
+                self.storage
+                    .download_file(download_url, MAX_MERGED_DATA_SIZE)
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to download merged file from storage: {}", e);
+                        ServiceError::from_download(e)
+                    })?
+            }
+            InspectTarget::Data(data) => {
+                info!("Starting inspect_merged_image_service (upload)");
+                data.to_vec()
+            }
+        };
+
+        let total_size = data.len();
+        let data = encoding
+            .decode(data)
+            .map_err(|e| ServiceError::Validation(format!("merged file is invalid: {}", e)))?;
+        // NOTE: 読み込めないファイルを調べるためのものなので、ヘッダーより後ろの問題ではエラーにしない
+        let layout = MergedFileLayout::parse(&data)
+            .map_err(|e| ServiceError::Validation(format!("merged file is invalid: {}", e)))?;
+
+        let entries = layout
+            .entries
+            .into_iter()
+            .map(|entry| EntryReport {
+                offset: entry.range.as_ref().map(|range| range.start),
+                size: entry.size,
+                dds: entry.range.map(|range| DdsHeader::parse(&data[range])),
+                descriptor: entry.descriptor,
+                checksum: entry.checksum,
+                error: entry.error,
+            })
+            .collect();
+
+        Ok(MergedFileReport {
+            version: layout.version,
+            flags: layout.flags,
+            entry_count: layout.count,
+            total_size,
+            headroom: MAX_MERGED_DATA_SIZE as i64 - total_size as i64,
+            metadata: layout.metadata,
+            file_checksum: layout.file_checksum,
+            entries,
+            errors: layout.errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::MockStorage;
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::dds::PixelFormat;
+    use crate::service::upload_merged_image_service::{
+        create_merged_format_of, MergedFormatSections,
+    };
+
+    #[tokio::test]
+    async fn 各画像の位置とサイズとddsの情報を返す() {
+        let dds = build_dds(8, 4, 2, b"DXT1");
+        let data = create_merged_format_of(
+            FormatVersion::V2,
+            &[dds.clone(), vec![1, 2, 3]],
+            &MergedFormatSections {
+                checksums: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let service = InspectMergedImageServiceImpl::new(Arc::new(MockStorage::succeed()));
+
+        let report = service
+            .execute(InspectTarget::Data(&data), OutputEncoding::Binary)
+            .await
+            .unwrap();
+
+        assert_eq!(report.version, FormatVersion::V2);
+        assert_eq!(report.total_size, data.len());
+        assert_eq!(
+            report.headroom,
+            MAX_MERGED_DATA_SIZE as i64 - data.len() as i64
+        );
+        assert!(report.file_checksum.unwrap().is_valid());
+        assert_eq!(report.entry_count, 2);
+        assert_eq!(report.entries.len(), 2);
+        assert!(report.errors.is_empty());
+
+        let first = &report.entries[0];
+        let offset = first.offset.unwrap();
+        assert_eq!(first.size as usize, dds.len());
+        assert_eq!(&data[offset..offset + dds.len()], &dds[..]);
+        let header = first.dds.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!((header.width, header.height), (8, 4));
+        assert_eq!(header.mip_count, 2);
+        assert_eq!(header.format, PixelFormat::Dxt1);
+        let checksum = first.checksum.unwrap();
+        assert_eq!(checksum.expected, crc32fast::hash(&dds));
+        assert!(checksum.is_valid());
+        assert_eq!(first.error, None);
+
+        let second = &report.entries[1];
+        assert_eq!(second.offset, Some(offset + dds.len()));
+        assert_eq!(second.size, 3);
+        assert_eq!(second.dds, Some(Err(DdsError::TooShort(3))));
+    }
+
+    #[tokio::test]
+    async fn urlを指定したならダウンロードしてデコードしてから調べる() {
+        let data =
+            create_merged_format_of(FormatVersion::V1, &[vec![1, 2, 3]], &Default::default())
+                .unwrap();
+        let encoded = OutputEncoding::Base64.encode(data.clone());
+        let service = InspectMergedImageServiceImpl::new(Arc::new(
+            MockStorage::succeed().with_download_data(encoded.clone()),
+        ));
+
+        let report = service
+            .execute(
+                InspectTarget::Url("https://example.com/download"),
+                OutputEncoding::Base64,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(report.version, FormatVersion::V1);
+        assert_eq!(report.total_size, encoded.len());
+        assert_eq!(report.entries[0].offset, Some(8));
+        assert_eq!(report.entries[0].size, 3);
+    }
+
+    #[tokio::test]
+    async fn 途中で切れたファイルでも読み取れた分の構造と画像ごとの問題を返す() {
+        let mut dds = build_dds(8, 4, 1, b"DXT1");
+        dds.extend_from_slice(&[0; 16]);
+        let mut data = create_merged_format_of(
+            FormatVersion::V2,
+            &[dds.clone(), dds.clone()],
+            &MergedFormatSections {
+                checksums: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        data.truncate(data.len() - 20);
+        let service = InspectMergedImageServiceImpl::new(Arc::new(MockStorage::succeed()));
+
+        let report = service
+            .execute(InspectTarget::Data(&data), OutputEncoding::Binary)
+            .await
+            .unwrap();
+
+        assert_eq!(report.entries.len(), 2);
+        assert_eq!(report.entries[0].error, None);
+        assert!(report.entries[0].checksum.unwrap().is_valid());
+
+        let second = &report.entries[1];
+        assert!(matches!(
+            second.error,
+            Some(MergedFileError::TruncatedData { .. })
+        ));
+        assert_eq!(second.checksum.unwrap().actual, None);
+        // NOTE: 切れていてもヘッダーが残っていれば DDS の情報は読み取れる
+        let header = second.dds.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!((header.width, header.height), (8, 4));
+
+        assert_eq!(report.file_checksum, None);
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn チェックサムが一致しなければ画像ごとの検証結果を返す() {
+        let dds = build_dds(8, 4, 1, b"DXT1");
+        let mut data = create_merged_format_of(
+            FormatVersion::V2,
+            &[dds.clone(), dds.clone()],
+            &MergedFormatSections {
+                checksums: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let len = data.len();
+        data[len - 5] ^= 0xff;
+        let service = InspectMergedImageServiceImpl::new(Arc::new(MockStorage::succeed()));
+
+        let report = service
+            .execute(InspectTarget::Data(&data), OutputEncoding::Binary)
+            .await
+            .unwrap();
+
+        assert!(report.entries[0].checksum.unwrap().is_valid());
+        assert!(!report.entries[1].checksum.unwrap().is_valid());
+        assert!(matches!(
+            report.entries[1].error,
+            Some(MergedFileError::ChecksumMismatch { index: 1, .. })
+        ));
+        assert!(!report.file_checksum.unwrap().is_valid());
+    }
+
+    #[tokio::test]
+    async fn ヘッダーを読み取れないファイルならエラーを返す() {
+        let service = InspectMergedImageServiceImpl::new(Arc::new(MockStorage::succeed()));
+
+        let result = service
+            .execute(
+                InspectTarget::Data(b"VRCB\x02\0\0\0"),
+                OutputEncoding::Binary,
+            )
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("merged file is invalid"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = InspectMergedImageServiceImpl::new(Arc::new(MockStorage::succeed()));
+
+        let result = service
+            .execute(InspectTarget::Url(""), OutputEncoding::Binary)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+}
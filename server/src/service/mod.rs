@@ -1,7 +1,36 @@
+mod downscale;
+mod edit_merged_image_service;
 pub mod error;
+mod inspect_merged_image_service;
+mod update_merged_image_service;
+mod upload_atlas_service;
+mod upload_cubemap_service;
+mod upload_flipbook_service;
 mod upload_merged_image_service;
 mod upload_single_image_service;
 
+pub use edit_merged_image_service::{
+    EditMergedImageOptions, EditMergedImageService, EditMergedImageServiceImpl,
+};
 pub use error::ServiceError;
-pub use upload_merged_image_service::{UploadMergedImageService, UploadMergedImageServiceImpl};
-pub use upload_single_image_service::{UploadSingleImageService, UploadSingleImageServiceImpl};
+pub use inspect_merged_image_service::{
+    InspectMergedImageService, InspectMergedImageServiceImpl, InspectTarget, MergedFileReport,
+};
+pub use update_merged_image_service::{
+    MergedImageReplacement, UpdateMergedImageOptions, UpdateMergedImageService,
+    UpdateMergedImageServiceImpl,
+};
+pub use upload_atlas_service::{UploadAtlasOptions, UploadAtlasService, UploadAtlasServiceImpl};
+pub use upload_cubemap_service::{
+    UploadCubemapOptions, UploadCubemapService, UploadCubemapServiceImpl,
+};
+pub use upload_flipbook_service::{
+    FlipbookSource, UploadFlipbookOptions, UploadFlipbookService, UploadFlipbookServiceImpl,
+};
+pub use upload_merged_image_service::{
+    MergedFileChecksums, UploadMergedImageOptions, UploadMergedImageResult,
+    UploadMergedImageService, UploadMergedImageServiceImpl, UploadSplitMergedImageResult,
+};
+pub use upload_single_image_service::{
+    UploadSingleImageOptions, UploadSingleImageService, UploadSingleImageServiceImpl,
+};
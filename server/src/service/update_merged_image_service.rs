@@ -0,0 +1,907 @@
+use async_trait::async_trait;
+use log::{error, info};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::infrastructure::{Converter, Storage};
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, DimensionFix,
+    FixedDimensions, MergedFile, MipmapOptions, OutputEncoding, TextureFormat,
+};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::{
+    create_merged_format_of, describe_entry, image_model_of, MergedFormatSections,
+    MAX_MERGED_DATA_SIZE,
+};
+
+/// 束ねたファイルの更新時のオプション
+#[derive(Debug, Clone)]
+pub struct UpdateMergedImageOptions {
+    /// 既存ファイルとアップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 縦横のピクセル数が4の倍数でない場合の補正方法（指定しなければエラーにする）
+    pub dimension_fix: Option<DimensionFix>,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+    /// ミップマップの設定（None ならミップマップなし、デフォルトは 1x1 までのミップマップを生成する）
+    pub mipmaps: Option<MipmapOptions>,
+    /// 変換前に各画像の上下を反転するか（Unity の `LoadRawTextureData` で正しい向きにする）
+    pub flip_y: bool,
+}
+
+impl Default for UpdateMergedImageOptions {
+    fn default() -> Self {
+        Self {
+            encoding: OutputEncoding::default(),
+            dimension_fix: None,
+            format: TextureFormat::default(),
+            compression: CompressionOptions::default(),
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: false,
+        }
+    }
+}
+
+/// 束ねたファイルの更新結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateMergedImageResult {
+    /// 縦横のピクセル数を補正した結果（補正した場合のみ）
+    pub fixed_dimensions: Option<FixedDimensions>,
+}
+
+/// 指定枚目を差し替える画像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedImageReplacement {
+    /// 差し替える位置（束ねたファイルの指定枚目）
+    pub index: i32,
+    /// 差し替える画像データ
+    pub image: Vec<u8>,
+}
+
+/// 束ねたファイルの複数枚同時更新の結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UpdateMergedImageBatchResult {
+    /// 各画像の縦横のピクセル数を補正した結果（指定順。補正しなかった画像は None）
+    pub fixed_dimensions: Vec<Option<FixedDimensions>>,
+}
+
+#[async_trait]
+pub trait UpdateMergedImageService: Send + Sync {
+    async fn execute(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        index: i32,
+        image: &[u8],
+        options: &UpdateMergedImageOptions,
+    ) -> ServiceResult<UpdateMergedImageResult>;
+
+    /// 複数枚をまとめて差し替え、1回のアップロードで反映する
+    async fn execute_batch(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        replacements: &[MergedImageReplacement],
+        options: &UpdateMergedImageOptions,
+    ) -> ServiceResult<UpdateMergedImageBatchResult>;
+}
+
+pub struct UpdateMergedImageServiceImpl {
+    converter: Arc<dyn Converter>,
+    storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
+}
+
+impl UpdateMergedImageServiceImpl {
+    pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UpdateMergedImageService for UpdateMergedImageServiceImpl {
+    async fn execute(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        index: i32,
+        image: &[u8],
+        options: &UpdateMergedImageOptions,
+    ) -> ServiceResult<UpdateMergedImageResult> {
+        let replacement = MergedImageReplacement {
+            index,
+            image: image.to_vec(),
+        };
+        let result = self
+            .execute_batch(
+                download_url,
+                presigned_url,
+                std::slice::from_ref(&replacement),
+                options,
+            )
+            .await?;
+
+        Ok(UpdateMergedImageResult {
+            fixed_dimensions: result.fixed_dimensions.into_iter().next().flatten(),
+        })
+    }
+
+    async fn execute_batch(
+        &self,
+        download_url: &str,
+        presigned_url: &str,
+        replacements: &[MergedImageReplacement],
+        options: &UpdateMergedImageOptions,
+    ) -> ServiceResult<UpdateMergedImageBatchResult> {
+        if download_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "download url must not be empty".to_string(),
+            ));
+        }
+
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        if replacements.is_empty() {
+            return Err(ServiceError::Validation(
+                "replacements must not be empty".to_string(),
+            ));
+        }
+
+        let mut indices = HashSet::with_capacity(replacements.len());
+        for replacement in replacements {
+            if replacement.index < 0 {
+                return Err(ServiceError::Validation(format!(
+                    "index must not be negative (index: {})",
+                    replacement.index
+                )));
+            }
+            if !indices.insert(replacement.index) {
+                return Err(ServiceError::Validation(format!(
+                    "index must not be duplicated (index: {})",
+                    replacement.index
+                )));
+            }
+        }
+
+        for replacement in replacements {
+            self.allowed_formats
+                .check(&replacement.image)
+                .map_err(|e| e.at(replacement.index as usize))?;
+        }
+
+        // 画像データをモデルに変換（バリデーション付き）
+        let mut image_models = Vec::with_capacity(replacements.len());
+        let mut fixed_dimensions = Vec::with_capacity(replacements.len());
+        for replacement in replacements {
+            let (image_model, fixed) = image_model_of(
+                replacement.index as usize,
+                &replacement.image,
+                options.dimension_fix.as_ref(),
+                options.flip_y,
+            )?;
+            image_models.push(image_model);
+            fixed_dimensions.push(fixed);
+        }
+
+        info!(
+            "Starting update_merged_image_service (indices: {:?})",
+            replacements
+                .iter()
+                .map(|replacement| replacement.index)
+                .collect::<Vec<_>>()
+        );
+
+        // 既存の独自形式ファイルを取得して分解
+        let merged_data = self
+            .storage
+            .download_file(download_url, MAX_MERGED_DATA_SIZE)
+            .await
+            .map_err(|e| {
+                error!("Failed to download merged file from storage: {}", e);
+                ServiceError::from_download(e)
+            })?;
+        let merged_data = options.encoding.decode(merged_data).map_err(|e| {
+            ServiceError::Validation(format!("existing merged file is invalid: {}", e))
+        })?;
+        let merged_file = MergedFile::try_from(merged_data).map_err(|e| {
+            ServiceError::Validation(format!("existing merged file is invalid: {}", e))
+        })?;
+
+        if let Some(replacement) = replacements
+            .iter()
+            .find(|replacement| replacement.index as usize >= merged_file.len())
+        {
+            return Err(ServiceError::Validation(format!(
+                "index is out of range (index: {}, texture count: {})",
+                replacement.index,
+                merged_file.len()
+            )));
+        }
+        let mut dds_data_list: Vec<Vec<u8>> = merged_file.entries().map(<[u8]>::to_vec).collect();
+
+        // 既存ファイルが記述子を持つなら差し替えた画像の記述子も作り直す
+        // NOTE: チェックサムは書き込み時に計算し直される
+        let mut sections = MergedFormatSections {
+            descriptors: merged_file.descriptors().map(<[_]>::to_vec),
+            metadata: merged_file.metadata().map(str::to_string),
+            checksums: merged_file.checksums().is_some(),
+            shared_data: merged_file.has_shared_data(),
+        };
+        // NOTE: 途中まで変換してから気付くと無駄になるので、全ての画像の変換の設定を先に確認する
+        let mut conversions = Vec::with_capacity(image_models.len());
+        for image_model in &image_models {
+            let format = options
+                .format
+                .resolve(image_model)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            let conversion = ConversionOptions {
+                mipmaps: options.mipmaps,
+                ..ConversionOptions::new(format, &options.compression)
+            };
+            self.conversion_policy
+                .check(&conversion)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            conversions.push(conversion);
+        }
+
+        for ((replacement, image_model), conversion) in
+            replacements.iter().zip(&image_models).zip(&conversions)
+        {
+            let index = replacement.index as usize;
+            let dds_data = self
+                .converter
+                .jpeg_to_dds(image_model.as_bytes(), conversion)
+                .await
+                .map_err(|e| {
+                    error!("Failed to convert image to dds: {}", e);
+                    ServiceError::from(e)
+                })?;
+
+            if let Some(descriptors) = sections.descriptors.as_mut() {
+                descriptors[index] = describe_entry(image_model, &dds_data)?;
+            }
+            dds_data_list[index] = dds_data;
+        }
+
+        // 既存ファイルと同じバージョン・セクション構成の独自形式にまとめ直す
+        let merged_data =
+            create_merged_format_of(merged_file.version(), &dds_data_list, &sections)?;
+        let merged_data = options.encoding.encode(merged_data);
+
+        // エンコード後に 10 MB を超えていたらエラー
+        if merged_data.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "merged data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        // ストレージにアップロード
+        self.storage
+            .upload_file(presigned_url, &merged_data)
+            .await
+            .map_err(|e| {
+                error!("Failed to upload merged file to storage: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        info!("Update merged image succeeded");
+        Ok(UpdateMergedImageBatchResult { fixed_dimensions })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::{FormatVersion, Image};
+    use std::sync::Mutex;
+    use tokio::fs;
+
+    fn existing_merged_data() -> Vec<u8> {
+        create_merged_format_of(
+            FormatVersion::V1,
+            &[vec![1, 2, 3], vec![4, 5, 6, 7]],
+            &Default::default(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn 空のダウンロード用urlならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 負のインデックスならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                -1,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 範囲外のインデックスならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                2,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("index is out of range"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルが壊れているならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(vec![2, 0, 0, 0, 1, 0, 0, 0])),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("existing merged file is invalid"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルが10mbより大きいならバリデーションエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(vec![0; MAX_MERGED_DATA_SIZE + 1])),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("downloaded file is too large"));
+        }
+    }
+
+    #[tokio::test]
+    async fn ダウンロードに失敗したならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+
+    #[tokio::test]
+    async fn 変換に失敗したならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::fail("fail")),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+
+    #[tokio::test]
+    async fn 四の倍数でないサイズの画像ならバリデーションエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/not_4_multiple_width.jpg")
+            .await
+            .unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("dimensions must be multiples of 4"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 指定枚目だけ差し替えてアップロードする() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing_merged_data());
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                1,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(merged_file.len(), 2);
+        assert_eq!(merged_file.entry(0).unwrap(), &[1, 2, 3]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[9, 9]);
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルがv2ならv2のまま差し替える() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let existing = create_merged_format_of(
+            FormatVersion::V2,
+            &[vec![1, 2, 3], vec![4, 5]],
+            &Default::default(),
+        )
+        .unwrap();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing);
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(merged_file.version(), FormatVersion::V2);
+        assert_eq!(merged_file.entry(0).unwrap(), &[9, 9]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[4, 5]);
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルの任意セクションを引き継ぎ記述子は差し替えた画像のものに更新する() {
+        use crate::model::dds::test_util::build_dds;
+        use crate::model::EntryDescriptor;
+
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let old_descriptor = EntryDescriptor {
+            width: 4,
+            height: 4,
+            format: 10,
+            mip_count: 1,
+        };
+        let existing = create_merged_format_of(
+            FormatVersion::V2,
+            &[vec![1, 2, 3], vec![4, 5]],
+            &MergedFormatSections {
+                descriptors: Some(vec![old_descriptor, old_descriptor]),
+                metadata: Some(r#"[{"a":1},{"b":2}]"#.to_string()),
+                checksums: false,
+                shared_data: false,
+            },
+        )
+        .unwrap();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing);
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(build_dds(8, 8, 2, b"DXT5")))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let image = Image::try_from(jpeg_data.as_slice()).unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                1,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        let descriptors = merged_file.descriptors().unwrap();
+        assert_eq!(descriptors[0], old_descriptor);
+        assert_eq!(descriptors[1].width, image.width as i32);
+        assert_eq!(descriptors[1].height, image.height as i32);
+        assert_eq!(descriptors[1].format, 12);
+        assert_eq!(descriptors[1].mip_count, 2);
+        assert_eq!(merged_file.metadata().unwrap(), r#"[{"a":1},{"b":2}]"#);
+    }
+
+    #[tokio::test]
+    async fn base64を指定したなら既存ファイルを戻してからbase64でアップロードする() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(OutputEncoding::Base64.encode(existing_merged_data()));
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UpdateMergedImageOptions {
+            encoding: OutputEncoding::Base64,
+            ..Default::default()
+        };
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &options,
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let decoded = OutputEncoding::Base64.decode(uploaded).unwrap();
+        let merged_file = MergedFile::try_from(decoded).unwrap();
+        assert_eq!(merged_file.entry(0).unwrap(), &[9, 9]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[4, 5, 6, 7]);
+    }
+
+    #[tokio::test]
+    async fn 既存ファイルがチェックサムを持つなら差し替え後のチェックサムで書き込む() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let existing = create_merged_format_of(
+            FormatVersion::V2,
+            &[vec![1, 2, 3], vec![4, 5]],
+            &MergedFormatSections {
+                checksums: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing);
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(
+            merged_file.checksums().unwrap(),
+            &[crc32fast::hash(&[9, 9]), crc32fast::hash(&[4, 5])]
+        );
+    }
+
+    #[tokio::test]
+    async fn ストレージのアップロードに失敗したならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::fail("upload failed").with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &jpeg_data,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+
+    #[tokio::test]
+    async fn 複数枚を指定したならまとめて差し替えて一回だけアップロードする() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let existing = create_merged_format_of(
+            FormatVersion::V1,
+            &[vec![1, 2, 3], vec![4, 5, 6, 7], vec![8]],
+            &Default::default(),
+        )
+        .unwrap();
+        let storage = MockStorage::new(move |_, data| {
+            uploaded_clone.lock().unwrap().push(data.to_vec());
+            Ok(())
+        })
+        .with_download_data(existing);
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![9, 9]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let replacements = [
+            MergedImageReplacement {
+                index: 2,
+                image: jpeg_data.clone(),
+            },
+            MergedImageReplacement {
+                index: 0,
+                image: jpeg_data,
+            },
+        ];
+        let result = service
+            .execute_batch(
+                "https://example.com/download",
+                "https://example.com",
+                &replacements,
+                &Default::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.fixed_dimensions, vec![None, None]);
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        assert_eq!(uploaded.len(), 1);
+        let merged_file = MergedFile::try_from(uploaded[0].clone()).unwrap();
+        assert_eq!(merged_file.len(), 3);
+        assert_eq!(merged_file.entry(0).unwrap(), &[9, 9]);
+        assert_eq!(merged_file.entry(1).unwrap(), &[4, 5, 6, 7]);
+        assert_eq!(merged_file.entry(2).unwrap(), &[9, 9]);
+    }
+
+    #[tokio::test]
+    async fn 同じインデックスを複数回指定したならエラーを返す() {
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let replacements = [
+            MergedImageReplacement {
+                index: 1,
+                image: jpeg_data.clone(),
+            },
+            MergedImageReplacement {
+                index: 1,
+                image: jpeg_data,
+            },
+        ];
+        let result = service
+            .execute_batch(
+                "https://example.com/download",
+                "https://example.com",
+                &replacements,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("index must not be duplicated"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 一枚でも不正なら何もアップロードせずエラーを返す() {
+        let uploaded = Arc::new(Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        })
+        .with_download_data(existing_merged_data());
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let replacements = [
+            MergedImageReplacement {
+                index: 0,
+                image: jpeg_data.clone(),
+            },
+            MergedImageReplacement {
+                index: 2,
+                image: jpeg_data,
+            },
+        ];
+        let result = service
+            .execute_batch(
+                "https://example.com/download",
+                "https://example.com",
+                &replacements,
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("index is out of range"));
+        }
+        assert!(uploaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ミップマップと上下反転を指定したなら差し替える画像にも適用する() {
+        use image::{DynamicImage, Rgba, RgbaImage};
+
+        let given = Arc::new(Mutex::new(Vec::new()));
+        let given_clone = given.clone();
+        let converter = MockConverter::with_options(move |image, options| {
+            let decoded = image::load_from_memory(image).unwrap().to_rgba8();
+            let top_row_red = decoded.get_pixel(0, 0) == &Rgba([255, 0, 0, 255]);
+            given_clone
+                .lock()
+                .unwrap()
+                .push((options.mipmaps, top_row_red));
+            Ok(image.to_vec())
+        });
+        let mut img = RgbaImage::from_pixel(4, 8, Rgba([0, 0, 0, 255]));
+        for x in 0..4 {
+            img.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        let png_data = Image::encode(&DynamicImage::ImageRgba8(img)).unwrap().data;
+        let service = UpdateMergedImageServiceImpl::new(
+            Arc::new(converter),
+            Arc::new(MockStorage::succeed().with_download_data(existing_merged_data())),
+        );
+        let options = UpdateMergedImageOptions {
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: true,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com/download",
+                "https://example.com",
+                0,
+                &png_data,
+                &options,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(
+            *given.lock().unwrap(),
+            vec![(Some(MipmapOptions::default()), false)]
+        );
+    }
+}
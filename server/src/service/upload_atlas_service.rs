@@ -0,0 +1,318 @@
+use async_trait::async_trait;
+use log::{error, info};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::infrastructure::{Converter, Storage};
+use crate::model::atlas::{build_atlases, DEFAULT_ATLAS_MAX_SIZE};
+use crate::model::{
+    AllowedFormats, AtlasLayout, CompressionOptions, ConversionOptions, ConversionPolicy,
+    OutputEncoding, TextureFormat,
+};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::{
+    create_merged_format_v2, describe_entry, MergedFormatSections, MAX_MERGED_DATA_SIZE,
+};
+
+/// アトラスアップロード時のオプション
+#[derive(Debug, Clone)]
+pub struct UploadAtlasOptions {
+    /// アトラスの一辺の最大ピクセル数（4の倍数）
+    pub max_size: u32,
+    /// アップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+}
+
+impl Default for UploadAtlasOptions {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_ATLAS_MAX_SIZE,
+            encoding: OutputEncoding::default(),
+            format: TextureFormat::default(),
+            compression: CompressionOptions::default(),
+        }
+    }
+}
+
+/// アトラスアップロードの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadAtlasResult {
+    /// 各アトラスのサイズと各画像の位置
+    pub layout: AtlasLayout,
+}
+
+#[async_trait]
+pub trait UploadAtlasService: Send + Sync {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        images: &[Vec<u8>],
+        options: &UploadAtlasOptions,
+    ) -> ServiceResult<UploadAtlasResult>;
+}
+
+pub struct UploadAtlasServiceImpl {
+    converter: Arc<dyn Converter>,
+    storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
+}
+
+impl UploadAtlasServiceImpl {
+    pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UploadAtlasService for UploadAtlasServiceImpl {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        images: &[Vec<u8>],
+        options: &UploadAtlasOptions,
+    ) -> ServiceResult<UploadAtlasResult> {
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        info!(
+            "Starting upload_atlas_service (image count: {})",
+            images.len()
+        );
+
+        self.allowed_formats.check_all(images)?;
+
+        // 画像をアトラスに詰め込む
+        let (layout, atlases) = build_atlases(images, options.max_size)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        // NOTE: 途中まで変換してから気付くと無駄になるので、全てのアトラスの変換の設定を先に確認する
+        let mut conversions = Vec::with_capacity(atlases.len());
+        for atlas in &atlases {
+            let format = options
+                .format
+                .resolve(atlas)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            let conversion = ConversionOptions::new(format, &options.compression);
+            self.conversion_policy
+                .check(&conversion)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            conversions.push(conversion);
+        }
+
+        let mut dds_data_list = Vec::with_capacity(atlases.len());
+        let mut descriptors = Vec::with_capacity(atlases.len());
+        for (index, (atlas, conversion)) in atlases.iter().zip(&conversions).enumerate() {
+            let dds_data = self
+                .converter
+                .jpeg_to_dds(atlas.as_bytes(), conversion)
+                .await
+                .map_err(|e| {
+                    error!("Failed to convert atlas {} to dds: {}", index, e);
+                    ServiceError::from(e)
+                })?;
+            descriptors.push(describe_entry(atlas, &dds_data)?);
+            dds_data_list.push(dds_data);
+        }
+
+        // NOTE: Udon 側で位置を引けるように、各アトラスのメタデータに位置の一覧を書き込む
+        let sections = MergedFormatSections {
+            descriptors: Some(descriptors),
+            metadata: Some(rects_metadata_json(&layout)),
+            ..Default::default()
+        };
+        let merged_data = create_merged_format_v2(&dds_data_list, &sections)?;
+        let output = options.encoding.encode(merged_data);
+
+        // エンコード後に 10 MB を超えていたらエラー
+        if output.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "merged data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        self.storage
+            .upload_file(presigned_url, &output)
+            .await
+            .map_err(|e| {
+                error!("Failed to upload atlas to storage: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        info!(
+            "Upload atlas succeeded (atlas count: {})",
+            layout.sizes.len()
+        );
+        Ok(UploadAtlasResult { layout })
+    }
+}
+
+/// 各アトラスに入っている画像の位置の一覧をメタデータの JSON にする
+fn rects_metadata_json(layout: &AtlasLayout) -> String {
+    let mut rects: Vec<Vec<Value>> = vec![Vec::new(); layout.sizes.len()];
+    for (index, rect) in layout.rects.iter().enumerate() {
+        rects[rect.atlas].push(json!({
+            "index": index,
+            "x": rect.x,
+            "y": rect.y,
+            "width": rect.width,
+            "height": rect.height,
+        }));
+    }
+    let entries: Vec<Value> = rects
+        .into_iter()
+        .map(|rects| json!({ "rects": rects }))
+        .collect();
+    Value::Array(entries).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::{FormatVersion, Image, MergedFile};
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])));
+        Image::encode(&img).unwrap().data
+    }
+
+    /// 変換されたアトラスのサイズの DDS を返すコンバーター
+    fn atlas_converter() -> MockConverter {
+        MockConverter::new(|image| {
+            let atlas = Image::try_from(image).unwrap();
+            Ok(build_dds(atlas.width, atlas.height, 1, b"DXT1"))
+        })
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = UploadAtlasServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service.execute("", &[png(4, 4)], &Default::default()).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 画像がないならエラーを返す() {
+        let service = UploadAtlasServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service
+            .execute("https://example.com", &[], &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn アトラスと位置の一覧を束ねてアップロードする() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadAtlasServiceImpl::new(
+            Arc::new(atlas_converter()),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let options = UploadAtlasOptions {
+            max_size: 8,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &[png(8, 8), png(4, 4), png(3, 4)],
+                &options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.layout.sizes, vec![(8, 8), (8, 4)]);
+        assert_eq!(result.layout.rects[2].atlas, 1);
+
+        let merged_file = MergedFile::try_from(uploaded.lock().unwrap().clone()).unwrap();
+        assert_eq!(merged_file.version(), FormatVersion::V2);
+        assert_eq!(merged_file.len(), 2);
+        let descriptors = merged_file.descriptors().unwrap();
+        assert_eq!((descriptors[1].width, descriptors[1].height), (8, 4));
+        let metadata: Value = serde_json::from_str(merged_file.metadata().unwrap()).unwrap();
+        assert_eq!(
+            metadata[1]["rects"],
+            json!([
+                {"index": 1, "x": 0, "y": 0, "width": 4, "height": 4},
+                {"index": 2, "x": 4, "y": 0, "width": 3, "height": 4},
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn 最大サイズより大きい画像があるならエラーを返す() {
+        let service = UploadAtlasServiceImpl::new(
+            Arc::new(atlas_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let options = UploadAtlasOptions {
+            max_size: 4,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &[png(8, 4)], &options)
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("image at index 0 is larger than max size"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 変換に失敗したならエラーを返す() {
+        let service = UploadAtlasServiceImpl::new(
+            Arc::new(MockConverter::fail("conversion failed")),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service
+            .execute("https://example.com", &[png(4, 4)], &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+}
@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::infrastructure::{Converter, Storage};
+use crate::model::cubemap::{build_cubemap_dds, validate_faces, CUBEMAP_FACES};
+use crate::model::error::CubemapError;
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, DdsHeader, Image,
+    OutputEncoding, TextureFormat,
+};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::MAX_MERGED_DATA_SIZE;
+
+/// キューブマップアップロード時のオプション
+#[derive(Debug, Clone, Default)]
+pub struct UploadCubemapOptions {
+    /// アップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルを持つ面があれば DXT5、なければ DXT1）
+    ///
+    /// キューブマップの面は全て同じフォーマットにする
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+}
+
+/// キューブマップアップロードの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadCubemapResult {
+    /// 各面のサイズ・フォーマット・ミップマップ数
+    pub header: DdsHeader,
+}
+
+#[async_trait]
+pub trait UploadCubemapService: Send + Sync {
+    /// 面は +X, -X, +Y, -Y, +Z, -Z の順番で渡す
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        faces: &[Vec<u8>],
+        options: &UploadCubemapOptions,
+    ) -> ServiceResult<UploadCubemapResult>;
+}
+
+pub struct UploadCubemapServiceImpl {
+    converter: Arc<dyn Converter>,
+    storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
+}
+
+impl UploadCubemapServiceImpl {
+    pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UploadCubemapService for UploadCubemapServiceImpl {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        faces: &[Vec<u8>],
+        options: &UploadCubemapOptions,
+    ) -> ServiceResult<UploadCubemapResult> {
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        if faces.len() != CUBEMAP_FACES.len() {
+            return Err(ServiceError::Validation(
+                CubemapError::FaceCount(faces.len()).to_string(),
+            ));
+        }
+
+        self.allowed_formats.check_all(faces)?;
+
+        // 各面を画像モデルに変換し、同じサイズの正方形か確認する
+        let images = CUBEMAP_FACES
+            .into_iter()
+            .zip(faces)
+            .map(|(face, data)| {
+                Image::try_from(data.as_slice())
+                    .map_err(|e| ServiceError::Validation(format!("face {}: {}", face, e)))
+            })
+            .collect::<ServiceResult<Vec<_>>>()?;
+        let size = validate_faces(&images).map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        let format = options
+            .format
+            .resolve_all(&images)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        info!(
+            "Starting upload_cubemap_service (face size: {}, format: {})",
+            size,
+            format.name()
+        );
+
+        // NOTE: 全ての面を同じ設定で変換する
+        let conversion = ConversionOptions::new(format, &options.compression);
+        self.conversion_policy
+            .check(&conversion)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        let mut dds_faces = Vec::with_capacity(images.len());
+        for (face, image) in CUBEMAP_FACES.into_iter().zip(&images) {
+            let dds_data = self
+                .converter
+                .jpeg_to_dds(image.as_bytes(), &conversion)
+                .await
+                .map_err(|e| {
+                    error!("Failed to convert face {} to dds: {}", face, e);
+                    ServiceError::from(e)
+                })?;
+            dds_faces.push(dds_data);
+        }
+
+        // NOTE: 変換結果はコンバーター次第なので、まとめる前に面どうしで揃っているか確認する
+        let (header, dds_data) =
+            build_cubemap_dds(&dds_faces).map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        // Base64 は String Loading で読み込む前提なので、エンコード後のサイズで上限を確認する
+        let output = options.encoding.encode(dds_data);
+        if options.encoding == OutputEncoding::Base64 && output.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "encoded data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        self.storage
+            .upload_file(presigned_url, &output)
+            .await
+            .map_err(|e| {
+                error!("Failed to upload cubemap to storage: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        info!("Upload cubemap succeeded");
+        Ok(UploadCubemapResult { header })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::dds::DDS_HEADER_SIZE;
+    use image::{DynamicImage, Rgba, RgbaImage};
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])));
+        Image::encode(&img).unwrap().data
+    }
+
+    /// 変換された面のサイズの DDS を返すコンバーター
+    fn face_converter() -> MockConverter {
+        MockConverter::new(|image| {
+            let face = Image::try_from(image).unwrap();
+            let mut data = build_dds(face.width, face.height, 1, b"DXT1");
+            data.extend(vec![0; (face.width / 4 * face.height / 4) as usize * 8]);
+            Ok(data)
+        })
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(face_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let faces = vec![png(4, 4); 6];
+
+        let result = service.execute("", &faces, &Default::default()).await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 六面をまとめたキューブマップをアップロードする() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(face_converter()),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let faces = vec![png(8, 8); 6];
+
+        let result = service
+            .execute("https://example.com", &faces, &Default::default())
+            .await
+            .unwrap();
+
+        assert_eq!((result.header.width, result.header.height), (8, 8));
+        let uploaded = uploaded.lock().unwrap();
+        assert_eq!(uploaded.len(), DDS_HEADER_SIZE + 6 * 4 * 8);
+        assert_eq!(
+            u32::from_le_bytes(uploaded[112..116].try_into().unwrap()),
+            0xFE00
+        );
+    }
+
+    #[tokio::test]
+    async fn 面が足りないならエラーを返す() {
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(face_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let faces = vec![png(4, 4); 5];
+
+        let result = service
+            .execute("https://example.com", &faces, &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("cubemap requires exactly 6 faces"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 正方形でない面があるならエラーを返す() {
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(face_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let mut faces = vec![png(8, 8); 6];
+        faces[1] = png(8, 4);
+
+        let result = service
+            .execute("https://example.com", &faces, &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("face negativeX must be square"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 四の倍数でない面があるならエラーを返す() {
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(face_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let mut faces = vec![png(8, 8); 6];
+        faces[4] = png(6, 6);
+
+        let result = service
+            .execute("https://example.com", &faces, &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("face positiveZ: image dimensions must be multiples of 4"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 変換に失敗したならエラーを返す() {
+        let service = UploadCubemapServiceImpl::new(
+            Arc::new(MockConverter::fail("conversion failed")),
+            Arc::new(MockStorage::succeed()),
+        );
+        let faces = vec![png(4, 4); 6];
+
+        let result = service
+            .execute("https://example.com", &faces, &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+}
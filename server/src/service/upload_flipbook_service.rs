@@ -0,0 +1,416 @@
+use async_trait::async_trait;
+use log::{error, info};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::infrastructure::{Converter, Storage};
+use crate::model::flipbook::{
+    build_sprite_sheet, decode_animation, decode_frames, DecodedFrames, DEFAULT_FRAME_DURATION_MS,
+};
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, FlipbookLayout,
+    OutputEncoding, TextureFormat,
+};
+use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::{
+    create_merged_format_v2, describe_entry, MergedFormatSections, MAX_MERGED_DATA_SIZE,
+};
+
+/// スプライトシートにするフレームの取得元
+#[derive(Debug, Clone, Copy)]
+pub enum FlipbookSource<'a> {
+    /// アニメーション GIF または APNG
+    Animation(&'a [u8]),
+    /// 表示順に並べた連番画像
+    Frames(&'a [Vec<u8>]),
+}
+
+/// スプライトシートアップロード時のオプション
+#[derive(Debug, Clone, Default)]
+pub struct UploadFlipbookOptions {
+    /// 各フレームの表示時間 (ms)。1つだけなら全フレームに適用する
+    ///
+    /// 指定しなければ、アニメーションはファイルに記録された値、連番画像は 100 ms にする
+    pub frame_durations: Option<Vec<u32>>,
+    /// アップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+}
+
+/// スプライトシートアップロードの結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UploadFlipbookResult {
+    /// 格子の配置と各フレームの表示時間
+    pub layout: FlipbookLayout,
+}
+
+#[async_trait]
+pub trait UploadFlipbookService: Send + Sync {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        source: FlipbookSource<'_>,
+        options: &UploadFlipbookOptions,
+    ) -> ServiceResult<UploadFlipbookResult>;
+}
+
+pub struct UploadFlipbookServiceImpl {
+    converter: Arc<dyn Converter>,
+    storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    allowed_animation_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
+}
+
+impl UploadFlipbookServiceImpl {
+    pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            allowed_animation_formats: AllowedFormats::animation_default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// アニメーション（`file`）として入力を許可する画像形式を指定する（デフォルトは gif と png）
+    pub fn with_allowed_animation_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_animation_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
+}
+
+#[async_trait]
+impl UploadFlipbookService for UploadFlipbookServiceImpl {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        source: FlipbookSource<'_>,
+        options: &UploadFlipbookOptions,
+    ) -> ServiceResult<UploadFlipbookResult> {
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        info!("Starting upload_flipbook_service");
+
+        // NOTE: アニメーションと連番画像は別の一覧で形式を確認する
+        match source {
+            FlipbookSource::Animation(data) => self.allowed_animation_formats.check(data)?,
+            FlipbookSource::Frames(images) => self.allowed_formats.check_all(images)?,
+        }
+
+        // フレームをデコードする
+        let decoded = match source {
+            FlipbookSource::Animation(data) => decode_animation(data),
+            FlipbookSource::Frames(images) => decode_frames(images).map(|frames| DecodedFrames {
+                durations: vec![DEFAULT_FRAME_DURATION_MS; frames.len()],
+                frames,
+            }),
+        }
+        .map_err(|e| ServiceError::Validation(e.to_string()))?;
+        let durations = durations_of(&decoded, options.frame_durations.as_deref())?;
+
+        // 格子に並べたスプライトシートを作る
+        let (layout, sheet) = build_sprite_sheet(&decoded.frames, durations)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+
+        let format = options
+            .format
+            .resolve(&sheet)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+        let conversion = ConversionOptions::new(format, &options.compression);
+        self.conversion_policy
+            .check(&conversion)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
+        let dds_data = self
+            .converter
+            .jpeg_to_dds(sheet.as_bytes(), &conversion)
+            .await
+            .map_err(|e| {
+                error!("Failed to convert sprite sheet to dds: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        // NOTE: シェーダーに渡せるように、格子の配置と表示時間をメタデータに書き込む
+        let sections = MergedFormatSections {
+            descriptors: Some(vec![describe_entry(&sheet, &dds_data)?]),
+            metadata: Some(json!([layout_metadata(&layout)]).to_string()),
+            ..Default::default()
+        };
+        let merged_data = create_merged_format_v2(&[dds_data], &sections)?;
+        let output = options.encoding.encode(merged_data);
+
+        // エンコード後に 10 MB を超えていたらエラー
+        if output.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "merged data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        self.storage
+            .upload_file(presigned_url, &output)
+            .await
+            .map_err(|e| {
+                error!("Failed to upload sprite sheet to storage: {}", e);
+                ServiceError::from(e)
+            })?;
+
+        info!(
+            "Upload flipbook succeeded (frame count: {})",
+            layout.frame_count
+        );
+        Ok(UploadFlipbookResult { layout })
+    }
+}
+
+/// 指定した表示時間があればそれを、なければデコード時の表示時間を使う
+fn durations_of(decoded: &DecodedFrames, specified: Option<&[u32]>) -> ServiceResult<Vec<u32>> {
+    let frame_count = decoded.frames.len();
+    match specified {
+        None => Ok(decoded.durations.clone()),
+        Some([duration]) => Ok(vec![*duration; frame_count]),
+        Some(durations) if durations.len() == frame_count => Ok(durations.to_vec()),
+        Some(durations) => Err(ServiceError::Validation(format!(
+            "frame duration count must be 1 or match frame count (durations: {}, frames: {})",
+            durations.len(),
+            frame_count
+        ))),
+    }
+}
+
+/// メタデータに書き込む格子の配置と表示時間
+fn layout_metadata(layout: &FlipbookLayout) -> serde_json::Value {
+    json!({
+        "frameCount": layout.frame_count,
+        "columns": layout.columns,
+        "rows": layout.rows,
+        "frameWidth": layout.frame_width,
+        "frameHeight": layout.frame_height,
+        "cellWidth": layout.cell_width,
+        "cellHeight": layout.cell_height,
+        "durations": layout.durations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::flipbook::test_util::build_gif;
+    use crate::model::{Image, MergedFile};
+    use image::{DynamicImage, Rgba, RgbaImage};
+    use serde_json::Value;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let img =
+            DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([255, 0, 0, 255])));
+        Image::encode(&img).unwrap().data
+    }
+
+    /// 変換されたスプライトシートのサイズの DDS を返すコンバーター
+    fn sheet_converter() -> MockConverter {
+        MockConverter::new(|image| {
+            let sheet = Image::try_from(image).unwrap();
+            Ok(build_dds(sheet.width, sheet.height, 1, b"DXT1"))
+        })
+    }
+
+    /// アップロードされたデータを記録するストレージ
+    fn recording_storage() -> (MockStorage, Arc<std::sync::Mutex<Vec<u8>>>) {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |_, data| {
+            *uploaded_clone.lock().unwrap() = data.to_vec();
+            Ok(())
+        });
+        (storage, uploaded)
+    }
+
+    #[tokio::test]
+    async fn 空のurlならエラーを返す() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let frames = [png(4, 4)];
+
+        let result = service
+            .execute("", FlipbookSource::Frames(&frames), &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn アニメーションgifから格子と表示時間を書き込んでアップロードする() {
+        let (storage, uploaded) = recording_storage();
+        let service =
+            UploadFlipbookServiceImpl::new(Arc::new(sheet_converter()), Arc::new(storage));
+        let gif = build_gif(
+            4,
+            4,
+            &[
+                ([255, 0, 0, 255], 40),
+                ([0, 255, 0, 255], 80),
+                ([0, 0, 255, 255], 40),
+            ],
+        );
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Animation(&gif),
+                &Default::default(),
+            )
+            .await
+            .unwrap();
+
+        let layout = result.layout;
+        assert_eq!(layout.frame_count, 3);
+        assert_eq!((layout.columns, layout.rows), (2, 2));
+        assert_eq!(layout.durations, vec![40, 80, 40]);
+
+        let merged_file = MergedFile::try_from(uploaded.lock().unwrap().clone()).unwrap();
+        assert_eq!(merged_file.len(), 1);
+        let descriptor = merged_file.descriptors().unwrap()[0];
+        assert_eq!((descriptor.width, descriptor.height), (8, 8));
+        let metadata: Value = serde_json::from_str(merged_file.metadata().unwrap()).unwrap();
+        assert_eq!(metadata[0]["columns"], 2);
+        assert_eq!(metadata[0]["durations"], json!([40, 80, 40]));
+    }
+
+    #[tokio::test]
+    async fn 許可されていない形式のアニメーションなら形式のエラーを返す() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        )
+        .with_allowed_animation_formats("png".parse().unwrap());
+        let gif = build_gif(4, 4, &[([255, 0, 0, 255], 40), ([0, 255, 0, 255], 40)]);
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Animation(&gif),
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::UnsupportedFormat(_))));
+        if let Err(ServiceError::UnsupportedFormat(e)) = result {
+            assert_eq!(e.index, None);
+            assert_eq!(e.detected, "gif");
+            assert_eq!(e.allowed, vec!["png"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn 連番画像で表示時間を1つだけ指定したなら全フレームに適用する() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let frames = [png(4, 4), png(4, 4)];
+        let options = UploadFlipbookOptions {
+            frame_durations: Some(vec![250]),
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Frames(&frames),
+                &options,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.layout.durations, vec![250, 250]);
+    }
+
+    #[tokio::test]
+    async fn 連番画像で表示時間を指定しなければ既定値にする() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let frames = [png(4, 4), png(4, 4)];
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Frames(&frames),
+                &Default::default(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.layout.durations, vec![DEFAULT_FRAME_DURATION_MS; 2]);
+    }
+
+    #[tokio::test]
+    async fn 表示時間の数がフレーム数と合わないならエラーを返す() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let frames = [png(4, 4), png(4, 4), png(4, 4)];
+        let options = UploadFlipbookOptions {
+            frame_durations: Some(vec![100, 200]),
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Frames(&frames),
+                &options,
+            )
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("frame duration count must be 1 or match frame count"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 連番画像のサイズが異なるならエラーを返す() {
+        let service = UploadFlipbookServiceImpl::new(
+            Arc::new(sheet_converter()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let frames = [png(4, 4), png(8, 8)];
+
+        let result = service
+            .execute(
+                "https://example.com",
+                FlipbookSource::Frames(&frames),
+                &Default::default(),
+            )
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("frame 1 size must match the first frame"));
+        }
+    }
+}
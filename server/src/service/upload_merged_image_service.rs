@@ -1,103 +1,671 @@
 use async_trait::async_trait;
 use log::{error, info};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::sync::Arc;
 
-use crate::infrastructure::{Converter, Storage};
-use crate::model::{Image, ImageError};
+use crate::infrastructure::{Converter, InfrastructureError, Storage};
+use crate::model::merged_file::{
+    DESCRIPTOR_SIZE, FLAG_CHECKSUMS, FLAG_DESCRIPTORS, FLAG_METADATA, FLAG_SHARED_DATA, MAGIC,
+};
+use crate::model::mipmap::mip_count;
+use crate::model::texture_array::{build_texture_array_dds, validate_layers};
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, DdsFormat, DdsHeader,
+    DimensionFix, EntryDescriptor, FixedDimensions, FormatVersion, Image, ImageError,
+    ImageMetadata, MipmapOptions, OutputEncoding, TextureFormat,
+};
+use crate::service::downscale::{plan_downscale, EntryDimensions};
 use crate::service::error::{ServiceError, ServiceResult};
 
+/// 独自形式にまとめたファイルの最大サイズ（VRChat の StringLoading の上限）
+pub(crate) const MAX_MERGED_DATA_SIZE: usize = 10 * 1024 * 1024;
+
+/// 複数画像アップロード時のオプション
+#[derive(Debug, Clone)]
+pub struct UploadMergedImageOptions {
+    /// 出力する独自形式のバージョン（デフォルトは v1）
+    pub format_version: FormatVersion,
+    /// 各画像の記述子一覧を書き込むか（v2 のみ）
+    pub descriptors: bool,
+    /// 各画像のメタデータ（v2 のみ書き込む）
+    pub metadata: Option<ImageMetadata>,
+    /// 画像ごとの CRC32 とファイル全体の CRC32 を書き込むか（v2 のみ）
+    pub checksums: bool,
+    /// 同じ内容の画像のデータを1つにまとめて共有するか（v2 のみ）
+    pub dedup: bool,
+    /// 独自形式の代わりに、DX10 拡張ヘッダー付きのテクスチャ配列の DDS にまとめるか（全ての画像が同じサイズの場合のみ）
+    pub texture_array: bool,
+    /// アップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 10 MB を超える場合に、収まるまで大きい画像から縮小するか
+    pub downscale_to_fit: bool,
+    /// 縦横のピクセル数が4の倍数でない場合の補正方法（指定しなければエラーにする）
+    pub dimension_fix: Option<DimensionFix>,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+    /// ミップマップの設定（None ならミップマップなし、デフォルトは 1x1 までのミップマップを生成する）
+    pub mipmaps: Option<MipmapOptions>,
+    /// 変換前に各画像の上下を反転するか（Unity の `LoadRawTextureData` で正しい向きにする）
+    pub flip_y: bool,
+}
+
+impl Default for UploadMergedImageOptions {
+    fn default() -> Self {
+        Self {
+            format_version: FormatVersion::default(),
+            descriptors: false,
+            metadata: None,
+            checksums: false,
+            dedup: false,
+            texture_array: false,
+            encoding: OutputEncoding::default(),
+            downscale_to_fit: false,
+            dimension_fix: None,
+            format: TextureFormat::default(),
+            compression: CompressionOptions::default(),
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: false,
+        }
+    }
+}
+
+/// 複数画像アップロードの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadMergedImageResult {
+    /// 書き込んだチェックサム（チェックサムを書き込んだ場合のみ）
+    pub checksums: Option<MergedFileChecksums>,
+    /// 各画像の縮小前後のサイズ（縮小を指定した場合のみ）
+    pub dimensions: Option<Vec<EntryDimensions>>,
+    /// 各画像の縦横のピクセル数を補正した結果（補正しなかった画像は None）
+    pub fixed_dimensions: Vec<Option<FixedDimensions>>,
+    /// テクスチャ配列にまとめた場合の、各要素のサイズ・フォーマットと要素数
+    pub texture_array: Option<DdsHeader>,
+    /// 各画像のミップマップの段数（ミップマップを指定した場合のみ）
+    pub mip_counts: Option<Vec<u32>>,
+}
+
+/// 独自形式に書き込んだチェックサム
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergedFileChecksums {
+    /// 各DDSデータの CRC32
+    pub entries: Vec<u32>,
+    /// ファイル全体（トレーラーを除く）の CRC32
+    pub file: u32,
+}
+
+impl MergedFileChecksums {
+    /// チェックサム付きで書き込んだ独自形式のバイナリから取り出す
+    fn of(dds_data_list: &[Vec<u8>], merged_data: &[u8]) -> Self {
+        let trailer = &merged_data[merged_data.len() - 4..];
+        Self {
+            entries: dds_data_list
+                .iter()
+                .map(|dds_data| crc32fast::hash(dds_data))
+                .collect(),
+            file: u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]),
+        }
+    }
+}
+
+/// 複数のファイルに分割してアップロードした結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadSplitMergedImageResult {
+    /// アップロードしたファイル（presigned url の指定順）
+    pub files: Vec<SplitMergedFile>,
+    /// 各画像の縦横のピクセル数を補正した結果（補正しなかった画像は None）
+    pub fixed_dimensions: Vec<Option<FixedDimensions>>,
+    /// 各画像のミップマップの段数（ミップマップを指定した場合のみ）
+    pub mip_counts: Option<Vec<u32>>,
+}
+
+/// 分割してアップロードしたファイル1つ分の情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitMergedFile {
+    /// このファイルに含まれる画像の範囲（入力画像の index）
+    pub range: Range<usize>,
+    /// 書き込んだチェックサム（チェックサムを書き込んだ場合のみ）
+    pub checksums: Option<MergedFileChecksums>,
+}
+
 #[async_trait]
 pub trait UploadMergedImageService: Send + Sync {
-    async fn execute(&self, presigned_url: &str, images: &[Vec<u8>]) -> ServiceResult<()>;
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        images: &[Vec<u8>],
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<UploadMergedImageResult>;
+
+    /// 10 MB に収まるように先頭から順に複数のファイルへ詰めてアップロードする
+    async fn execute_split(
+        &self,
+        presigned_urls: &[String],
+        images: &[Vec<u8>],
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<UploadSplitMergedImageResult>;
 }
 
 pub struct UploadMergedImageServiceImpl {
     converter: Arc<dyn Converter>,
     storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
 }
 
 impl UploadMergedImageServiceImpl {
     pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
-        Self { converter, storage }
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
     }
-}
 
-#[async_trait]
-impl UploadMergedImageService for UploadMergedImageServiceImpl {
-    async fn execute(&self, presigned_url: &str, images: &[Vec<u8>]) -> ServiceResult<()> {
-        if presigned_url.trim().is_empty() {
-            return Err(ServiceError::Validation(
-                "presigned url must not be empty".to_string(),
-            ));
-        }
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
 
-        if images.is_empty() {
-            return Err(ServiceError::Validation(
-                "images must not be empty".to_string(),
-            ));
-        }
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
+    }
 
-        info!(
-            "Starting upload_merged_image_service (image count: {})",
-            images.len()
-        );
+    /// 各画像をモデルに変換してからDDSに変換
+    async fn convert_all(
+        &self,
+        images: &[Vec<u8>],
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<ConvertedEntries> {
+        self.allowed_formats.check_all(images)?;
 
-        // 各画像をモデルに変換してからDDSに変換
-        let mut dds_data_list = Vec::new();
+        // 画像データをモデルに変換（バリデーション付き）
+        let mut image_models = Vec::with_capacity(images.len());
+        let mut fixed_dimensions = Vec::with_capacity(images.len());
         for (index, image_bytes) in images.iter().enumerate() {
-            // 画像データをモデルに変換（バリデーション付き）
-            let image_model = Image::try_from(image_bytes.as_slice()).map_err(|e| {
-                match e {
-                    ImageError::EmptyData => ServiceError::Validation(format!(
-                        "image at index {} is empty",
-                        index
-                    )),
-                    ImageError::DecodeError(msg) => ServiceError::Validation(format!(
-                        "failed to decode image at index {}: {}",
-                        index, msg
-                    )),
-                    ImageError::InvalidDimensions { width, height } => {
+            let (image_model, fixed) = image_model_of(
+                index,
+                image_bytes,
+                options.dimension_fix.as_ref(),
+                options.flip_y,
+            )?;
+            image_models.push(image_model);
+            fixed_dimensions.push(fixed);
+        }
+
+        // NOTE: 変換してから気付くと無駄になるので、テクスチャ配列のサイズは変換前に確認する
+        //       テクスチャ配列の要素は全て同じフォーマットでないといけない
+        let formats = if options.texture_array {
+            validate_layers(&image_models).map_err(|e| ServiceError::Validation(e.to_string()))?;
+            let format = options
+                .format
+                .resolve_all(&image_models)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            vec![format; image_models.len()]
+        } else {
+            image_models
+                .iter()
+                .map(|image_model| options.format.resolve(image_model))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| ServiceError::Validation(e.to_string()))?
+        };
+
+        // 10 MB に収まるように大きい画像から縮小する
+        let dimensions = if options.downscale_to_fit {
+            let planned = plan_downscale_of(&image_models, &formats, options)?;
+            for (index, (image_model, entry)) in image_models.iter_mut().zip(&planned).enumerate() {
+                if entry.is_downscaled() {
+                    info!(
+                        "Downscaling image {} ({}x{} -> {}x{})",
+                        index,
+                        entry.original_width,
+                        entry.original_height,
+                        entry.width,
+                        entry.height
+                    );
+                    *image_model = image_model.resize(entry.width, entry.height).map_err(|e| {
                         ServiceError::Validation(format!(
-                            "image at index {}: dimensions must be multiples of 4 (width: {}, height: {})",
-                            index, width, height
+                            "failed to downscale image at index {}: {}",
+                            index, e
                         ))
-                    }
+                    })?;
                 }
-            })?;
+            }
+            Some(planned)
+        } else {
+            None
+        };
+
+        // NOTE: 途中まで変換してから気付くと無駄になるので、全ての画像の変換の設定を先に確認する
+        let conversions: Vec<ConversionOptions> = formats
+            .into_iter()
+            .map(|format| ConversionOptions {
+                mipmaps: options.mipmaps,
+                ..ConversionOptions::new(format, &options.compression)
+            })
+            .collect();
+        for conversion in &conversions {
+            self.conversion_policy
+                .check(conversion)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+        }
 
+        let mut dds_data_list = Vec::new();
+        let mut descriptors = Vec::new();
+        for (index, (image_model, conversion)) in image_models.iter().zip(&conversions).enumerate()
+        {
             let dds_data = self
                 .converter
-                .jpeg_to_dds(image_model.as_bytes())
+                .jpeg_to_dds(image_model.as_bytes(), conversion)
                 .await
                 .map_err(|e| {
                     error!("Failed to convert image {} to dds: {}", index, e);
                     ServiceError::from(e)
                 })?;
 
+            if options.descriptors {
+                descriptors.push(describe_entry(image_model, &dds_data)?);
+            }
             dds_data_list.push(dds_data);
         }
 
-        // 独自形式にまとめる
-        let merged_data = create_merged_format(&dds_data_list)?;
+        // NOTE: ミップマップの段数は画像サイズとミップマップの設定だけで決まる
+        let mip_counts = options.mipmaps.as_ref().map(|mipmaps| {
+            image_models
+                .iter()
+                .map(|image_model| mip_count(image_model.width, image_model.height, Some(mipmaps)))
+                .collect()
+        });
 
-        // 10 MB を超えていたらエラー
-        if merged_data.len() > 10 * 1024 * 1024 {
-            return Err(ServiceError::Validation(
-                "merged data size must be less than 10 MB".to_string(),
-            ));
-        }
+        Ok(ConvertedEntries {
+            dds_data_list,
+            descriptors,
+            dimensions,
+            fixed_dimensions,
+            mip_counts,
+        })
+    }
 
-        // ストレージにアップロード
+    /// 独自形式にまとめたファイルをストレージにアップロード
+    async fn upload(&self, presigned_url: &str, output: &[u8]) -> ServiceResult<()> {
         self.storage
-            .upload_file(presigned_url, &merged_data)
+            .upload_file(presigned_url, output)
             .await
             .map_err(|e| {
                 error!("Failed to upload merged file to storage: {}", e);
                 ServiceError::from(e)
-            })?;
+            })
+    }
+}
+
+#[async_trait]
+impl UploadMergedImageService for UploadMergedImageServiceImpl {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        images: &[Vec<u8>],
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<UploadMergedImageResult> {
+        if presigned_url.trim().is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned url must not be empty".to_string(),
+            ));
+        }
+
+        validate_request(images, options)?;
+
+        info!(
+            "Starting upload_merged_image_service (image count: {})",
+            images.len()
+        );
+
+        let entries = self.convert_all(images, options).await?;
+
+        // 独自形式にまとめる（テクスチャ配列を指定した場合は DX10 拡張ヘッダー付きの DDS にする）
+        let (output, checksums, texture_array) = if options.texture_array {
+            let (header, dds_data) = build_texture_array_dds(&entries.dds_data_list)
+                .map_err(|e| ServiceError::Validation(e.to_string()))?;
+            (options.encoding.encode(dds_data), None, Some(header))
+        } else {
+            let (output, checksums) = entries.build_output(0..entries.len(), options)?;
+            (output, checksums, None)
+        };
+
+        // エンコード後に 10 MB を超えていたらエラー
+        if output.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "merged data size must be less than 10 MB".to_string(),
+            ));
+        }
+
+        // ストレージにアップロード
+        self.upload(presigned_url, &output).await?;
 
         info!("Upload merged image succeeded");
-        Ok(())
+        Ok(UploadMergedImageResult {
+            checksums,
+            dimensions: entries.dimensions,
+            fixed_dimensions: entries.fixed_dimensions,
+            texture_array,
+            mip_counts: entries.mip_counts,
+        })
+    }
+
+    async fn execute_split(
+        &self,
+        presigned_urls: &[String],
+        images: &[Vec<u8>],
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<UploadSplitMergedImageResult> {
+        if presigned_urls.is_empty() {
+            return Err(ServiceError::Validation(
+                "presigned urls must not be empty".to_string(),
+            ));
+        }
+
+        if let Some(index) = presigned_urls.iter().position(|url| url.trim().is_empty()) {
+            return Err(ServiceError::Validation(format!(
+                "presigned url at index {} must not be empty",
+                index
+            )));
+        }
+
+        validate_request(images, options)?;
+
+        if options.downscale_to_fit {
+            return Err(ServiceError::Validation(
+                "downscale cannot be used with split upload".to_string(),
+            ));
+        }
+
+        if options.texture_array {
+            return Err(ServiceError::Validation(
+                "texture array cannot be used with split upload".to_string(),
+            ));
+        }
+
+        info!(
+            "Starting upload_merged_image_service with split (image count: {}, url count: {})",
+            images.len(),
+            presigned_urls.len()
+        );
+
+        let entries = self.convert_all(images, options).await?;
+
+        // 先頭から順に 10 MB に収まるだけ詰める
+        let ranges = entries.plan_split(options)?;
+        if ranges.len() > presigned_urls.len() {
+            return Err(ServiceError::Validation(format!(
+                "not enough presigned urls to split merged data (required: {}, given: {})",
+                ranges.len(),
+                presigned_urls.len()
+            )));
+        }
+
+        // NOTE: 全ファイルを組み立ててからアップロードし、途中で検証エラーになった場合に何もアップロードしないようにする
+        let outputs = ranges
+            .into_iter()
+            .map(|range| {
+                let (output, checksums) = entries.build_output(range.clone(), options)?;
+                Ok((output, SplitMergedFile { range, checksums }))
+            })
+            .collect::<ServiceResult<Vec<_>>>()?;
+
+        let mut files = Vec::with_capacity(outputs.len());
+        for ((output, file), presigned_url) in outputs.into_iter().zip(presigned_urls) {
+            self.upload(presigned_url, &output).await?;
+            files.push(file);
+        }
+
+        info!(
+            "Upload split merged image succeeded (file count: {})",
+            files.len()
+        );
+        Ok(UploadSplitMergedImageResult {
+            files,
+            fixed_dimensions: entries.fixed_dimensions,
+            mip_counts: entries.mip_counts,
+        })
+    }
+}
+
+/// URL 以外の入力値を検証
+fn validate_request(images: &[Vec<u8>], options: &UploadMergedImageOptions) -> ServiceResult<()> {
+    if images.is_empty() {
+        return Err(ServiceError::Validation(
+            "images must not be empty".to_string(),
+        ));
+    }
+
+    if options.descriptors && options.format_version == FormatVersion::V1 {
+        return Err(ServiceError::Validation(
+            "descriptors require format version 2".to_string(),
+        ));
+    }
+
+    if options.checksums && options.format_version == FormatVersion::V1 {
+        return Err(ServiceError::Validation(
+            "checksums require format version 2".to_string(),
+        ));
+    }
+
+    if options.dedup && options.format_version == FormatVersion::V1 {
+        return Err(ServiceError::Validation(
+            "dedup requires format version 2".to_string(),
+        ));
+    }
+
+    if options.texture_array && options.format_version == FormatVersion::V2 {
+        return Err(ServiceError::Validation(
+            "texture array cannot be used with format version 2".to_string(),
+        ));
+    }
+
+    if options.texture_array && options.downscale_to_fit {
+        return Err(ServiceError::Validation(
+            "downscale cannot be used with texture array".to_string(),
+        ));
+    }
+
+    if let Some(metadata) = &options.metadata {
+        if metadata.len() != images.len() {
+            return Err(ServiceError::Validation(format!(
+                "metadata entry count must match image count (metadata: {}, images: {})",
+                metadata.len(),
+                images.len()
+            )));
+        }
+
+        if options.texture_array {
+            return Err(ServiceError::Validation(
+                "metadata cannot be used with texture array".to_string(),
+            ));
+        }
+
+        if options.format_version == FormatVersion::V1 {
+            return Err(ServiceError::Validation(
+                "metadata requires format version 2".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// 書き込むメタデータの JSON（指定した範囲の画像の分だけ）
+fn metadata_json_of(range: Range<usize>, options: &UploadMergedImageOptions) -> Option<String> {
+    options
+        .metadata
+        .as_ref()
+        .map(|metadata| metadata.slice(range).to_json())
+}
+
+/// index 枚目の画像データをモデルに変換する（バリデーション付き、flip_y なら上下を反転する）
+pub(crate) fn image_model_of(
+    index: usize,
+    image_bytes: &[u8],
+    dimension_fix: Option<&DimensionFix>,
+    flip_y: bool,
+) -> ServiceResult<(Image, Option<FixedDimensions>)> {
+    let (image_model, fixed) =
+        Image::try_from_with_fix(image_bytes, dimension_fix).map_err(|e| match e {
+            ImageError::EmptyData => {
+                ServiceError::Validation(format!("image at index {} is empty", index))
+            }
+            ImageError::DecodeError(msg) => ServiceError::Validation(format!(
+                "failed to decode image at index {}: {}",
+                index, msg
+            )),
+            ImageError::InvalidDimensions { width, height } => ServiceError::Validation(format!(
+                "image at index {}: dimensions must be multiples of 4 (width: {}, height: {})",
+                index, width, height
+            )),
+            ImageError::EncodeError(msg) => ServiceError::Validation(format!(
+                "failed to encode image at index {}: {}",
+                index, msg
+            )),
+        })?;
+
+    // NOTE: 縦横のピクセル数は元の向きで補正してから反転する
+    let image_model = if flip_y {
+        image_model.flip_vertical().map_err(|e| {
+            ServiceError::Validation(format!("failed to flip image at index {}: {}", index, e))
+        })?
+    } else {
+        image_model
+    };
+    Ok((image_model, fixed))
+}
+
+/// 全ての画像を1ファイルにまとめたときに 10 MB に収まるよう縮小する計画を立てる
+fn plan_downscale_of(
+    image_models: &[Image],
+    formats: &[DdsFormat],
+    options: &UploadMergedImageOptions,
+) -> ServiceResult<Vec<EntryDimensions>> {
+    let count = image_models.len();
+    // NOTE: サイズの計算には記述子の数だけが影響するので中身は仮の値でよい
+    //       変換前は重複が分からないので、データを共有する場合も全ての画像の分を見積もる
+    let sections = MergedFormatSections {
+        descriptors: options
+            .descriptors
+            .then(|| vec![EntryDescriptor::default(); count]),
+        metadata: metadata_json_of(0..count, options),
+        checksums: options.checksums,
+        shared_data: options.dedup,
+    };
+    let dimensions: Vec<(u32, u32)> = image_models
+        .iter()
+        .map(|image| (image.width, image.height))
+        .collect();
+
+    plan_downscale(
+        &dimensions,
+        formats,
+        options.mipmaps.as_ref(),
+        MAX_MERGED_DATA_SIZE,
+        |dds_sizes| {
+            let size = merged_format_size(options.format_version, dds_sizes, &sections);
+            options.encoding.encoded_len(size)
+        },
+    )
+}
+
+/// DDSに変換済みの画像一覧
+struct ConvertedEntries {
+    dds_data_list: Vec<Vec<u8>>,
+    /// 記述子を書き込む場合のみ画像と同じ数だけ持つ
+    descriptors: Vec<EntryDescriptor>,
+    /// 縮小を指定した場合のみ、縮小前後の画像サイズ
+    dimensions: Option<Vec<EntryDimensions>>,
+    /// 縦横のピクセル数を補正した結果
+    fixed_dimensions: Vec<Option<FixedDimensions>>,
+    /// ミップマップを指定した場合のみ、各DDSのミップマップの段数
+    mip_counts: Option<Vec<u32>>,
+}
+
+impl ConvertedEntries {
+    fn len(&self) -> usize {
+        self.dds_data_list.len()
+    }
+
+    /// 指定した範囲の画像を独自形式にまとめるときの任意セクション
+    fn sections_of(
+        &self,
+        range: Range<usize>,
+        options: &UploadMergedImageOptions,
+    ) -> MergedFormatSections {
+        MergedFormatSections {
+            descriptors: options
+                .descriptors
+                .then(|| self.descriptors[range.clone()].to_vec()),
+            metadata: metadata_json_of(range, options),
+            checksums: options.checksums,
+            shared_data: options.dedup,
+        }
+    }
+
+    /// 指定した範囲の画像をまとめた独自形式をエンコードした後のサイズ
+    fn output_size(&self, range: Range<usize>, options: &UploadMergedImageOptions) -> usize {
+        let sections = self.sections_of(range.clone(), options);
+        // NOTE: データを共有する場合、2回目以降に現れる同じ内容のデータはサイズに含めない
+        let mut seen = HashSet::new();
+        let dds_sizes: Vec<usize> = self.dds_data_list[range]
+            .iter()
+            .map(|dds_data| {
+                if options.dedup && !seen.insert(dds_data.as_slice()) {
+                    0
+                } else {
+                    dds_data.len()
+                }
+            })
+            .collect();
+        let size = merged_format_size(options.format_version, &dds_sizes, &sections);
+        options.encoding.encoded_len(size)
+    }
+
+    /// 指定した範囲の画像を独自形式にまとめてエンコードする
+    fn build_output(
+        &self,
+        range: Range<usize>,
+        options: &UploadMergedImageOptions,
+    ) -> ServiceResult<(Vec<u8>, Option<MergedFileChecksums>)> {
+        let sections = self.sections_of(range.clone(), options);
+        let dds_data_list = &self.dds_data_list[range];
+        let merged_data =
+            create_merged_format_of(options.format_version, dds_data_list, &sections)?;
+
+        // NOTE: チェックサムはエンコード前のバイナリに対して計算する
+        let checksums = options
+            .checksums
+            .then(|| MergedFileChecksums::of(dds_data_list, &merged_data));
+        Ok((options.encoding.encode(merged_data), checksums))
+    }
+
+    /// 先頭から順に、各ファイルが 10 MB に収まるように画像を振り分ける
+    fn plan_split(&self, options: &UploadMergedImageOptions) -> ServiceResult<Vec<Range<usize>>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for index in 0..self.len() {
+            if self.output_size(index..index + 1, options) > MAX_MERGED_DATA_SIZE {
+                return Err(ServiceError::Validation(format!(
+                    "image at index {} does not fit in 10 MB by itself",
+                    index
+                )));
+            }
+            if self.output_size(start..index + 1, options) > MAX_MERGED_DATA_SIZE {
+                ranges.push(start..index);
+                start = index;
+            }
+        }
+        ranges.push(start..self.len());
+        Ok(ranges)
     }
 }
 
@@ -107,7 +675,7 @@ impl UploadMergedImageService for UploadMergedImageServiceImpl {
 /// - Header: Texture Count (4byte, Int32, Little Endian)
 /// - Index: Data Size List (4byte * N, 各DDSデータのサイズ)
 /// - Data: Concatenated DDS Binaries
-fn create_merged_format(dds_data_list: &[Vec<u8>]) -> ServiceResult<Vec<u8>> {
+pub(crate) fn create_merged_format(dds_data_list: &[Vec<u8>]) -> ServiceResult<Vec<u8>> {
     let count = dds_data_list.len();
 
     if count == 0 {
@@ -134,10 +702,230 @@ fn create_merged_format(dds_data_list: &[Vec<u8>]) -> ServiceResult<Vec<u8>> {
     Ok(result)
 }
 
+/// v2 形式で書き込む任意セクション
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MergedFormatSections {
+    /// 各画像の記述子一覧
+    pub descriptors: Option<Vec<EntryDescriptor>>,
+    /// 画像メタデータの JSON
+    pub metadata: Option<String>,
+    /// 画像ごとの CRC32 とファイル全体の CRC32 を書き込むか
+    pub checksums: bool,
+    /// 同じ内容のDDSデータを1つにまとめ、データ位置一覧で共有するか
+    pub shared_data: bool,
+}
+
+impl MergedFormatSections {
+    /// 書き込むセクションに対応するヘッダーフラグ
+    fn flags(&self) -> u32 {
+        let mut flags = 0;
+        if self.descriptors.is_some() {
+            flags |= FLAG_DESCRIPTORS;
+        }
+        if self.metadata.is_some() {
+            flags |= FLAG_METADATA;
+        }
+        if self.checksums {
+            flags |= FLAG_CHECKSUMS;
+        }
+        if self.shared_data {
+            flags |= FLAG_SHARED_DATA;
+        }
+        flags
+    }
+}
+
+/// 複数のDDSデータを v2 の独自形式にまとめる
+///
+/// フォーマット:
+/// - Magic: "VRCB" (4byte)
+/// - Version: Format Version (4byte, Int32, Little Endian)
+/// - Flags: Header Flags (4byte, UInt32, Little Endian)
+/// - Header: Texture Count (4byte, Int32, Little Endian)
+/// - Index: Data Size List (4byte * N, 各DDSデータのサイズ)
+/// - Offsets: (FLAG_SHARED_DATA) Data Offset List (4byte * N, Int32, Data 先頭からの各DDSデータの位置)
+/// - Descriptors: (FLAG_DESCRIPTORS) Width, Height, Format, Mip Count (4byte * 4 * N)
+/// - Checksums: (FLAG_CHECKSUMS) CRC32 List (4byte * N, UInt32, 各DDSデータの CRC32)
+/// - Metadata: (FLAG_METADATA) JSON Length (4byte, Int32) + UTF-8 JSON
+/// - Data: Concatenated DDS Binaries (FLAG_SHARED_DATA なら同じ内容のデータは1つだけ)
+/// - Trailer: (FLAG_CHECKSUMS) File CRC32 (4byte, UInt32, ここより前の全バイトの CRC32)
+pub(crate) fn create_merged_format_v2(
+    dds_data_list: &[Vec<u8>],
+    sections: &MergedFormatSections,
+) -> ServiceResult<Vec<u8>> {
+    let count = dds_data_list.len();
+
+    if count == 0 {
+        return Err(ServiceError::Validation(
+            "dds data list must not be empty".to_string(),
+        ));
+    }
+
+    // Header Section: Magic, Version, Flags, Texture Count
+    let mut result = Vec::new();
+    result.extend_from_slice(&MAGIC);
+    result.extend_from_slice(&FormatVersion::V2.number().to_le_bytes());
+    result.extend_from_slice(&sections.flags().to_le_bytes());
+    result.extend_from_slice(&(count as i32).to_le_bytes());
+
+    // Index Section: Data Size List (4byte * N)
+    for dds_data in dds_data_list {
+        let size = dds_data.len() as i32;
+        result.extend_from_slice(&size.to_le_bytes());
+    }
+
+    // Offsets Section: Data Offset List (4byte * N)
+    let blocks = if sections.shared_data {
+        let (blocks, offsets) = shared_blocks_of(dds_data_list);
+        for offset in offsets {
+            result.extend_from_slice(&(offset as i32).to_le_bytes());
+        }
+        blocks
+    } else {
+        dds_data_list.iter().map(Vec::as_slice).collect()
+    };
+
+    // Descriptors Section: Width, Height, Format, Mip Count (4byte * 4 * N)
+    if let Some(descriptors) = &sections.descriptors {
+        if descriptors.len() != count {
+            return Err(ServiceError::Validation(format!(
+                "descriptor count must match dds data count (descriptors: {}, dds: {})",
+                descriptors.len(),
+                count
+            )));
+        }
+        for descriptor in descriptors {
+            result.extend_from_slice(&descriptor.to_le_bytes());
+        }
+    }
+
+    // Checksums Section: CRC32 List (4byte * N)
+    if sections.checksums {
+        for dds_data in dds_data_list {
+            result.extend_from_slice(&crc32fast::hash(dds_data).to_le_bytes());
+        }
+    }
+
+    // Metadata Section: JSON Length (4byte) + UTF-8 JSON
+    if let Some(metadata) = &sections.metadata {
+        result.extend_from_slice(&(metadata.len() as i32).to_le_bytes());
+        result.extend_from_slice(metadata.as_bytes());
+    }
+
+    // Data Section: Concatenated DDS Binaries
+    for block in blocks {
+        result.extend_from_slice(block);
+    }
+
+    // Trailer: File CRC32 (4byte)
+    if sections.checksums {
+        let file_checksum = crc32fast::hash(&result);
+        result.extend_from_slice(&file_checksum.to_le_bytes());
+    }
+
+    Ok(result)
+}
+
+/// 同じ内容のDDSデータを1つにまとめ、(書き込むデータ一覧, 各DDSデータの位置) を返す
+///
+/// データは最初に現れた順に並べ、位置は Data セクション先頭からのバイト数
+fn shared_blocks_of(dds_data_list: &[Vec<u8>]) -> (Vec<&[u8]>, Vec<usize>) {
+    let mut blocks = Vec::new();
+    let mut positions: HashMap<&[u8], usize> = HashMap::new();
+    let mut data_size = 0;
+    let offsets = dds_data_list
+        .iter()
+        .map(|dds_data| {
+            *positions.entry(dds_data.as_slice()).or_insert_with(|| {
+                let offset = data_size;
+                blocks.push(dds_data.as_slice());
+                data_size += dds_data.len();
+                offset
+            })
+        })
+        .collect();
+    (blocks, offsets)
+}
+
+/// 指定したバージョンの独自形式にまとめる
+pub(crate) fn create_merged_format_of(
+    version: FormatVersion,
+    dds_data_list: &[Vec<u8>],
+    sections: &MergedFormatSections,
+) -> ServiceResult<Vec<u8>> {
+    match version {
+        FormatVersion::V1 => {
+            if sections.flags() != 0 {
+                return Err(ServiceError::Validation(
+                    "optional sections require format version 2".to_string(),
+                ));
+            }
+            create_merged_format(dds_data_list)
+        }
+        FormatVersion::V2 => create_merged_format_v2(dds_data_list, sections),
+    }
+}
+
+/// 指定したバージョンの独自形式にまとめたときのサイズ（組み立てずに計算する）
+///
+/// データを共有する場合は、共有されて書き込まれないDDSデータのサイズを 0 として渡す
+pub(crate) fn merged_format_size(
+    version: FormatVersion,
+    dds_sizes: &[usize],
+    sections: &MergedFormatSections,
+) -> usize {
+    let count = dds_sizes.len();
+
+    // Header + Index + Data
+    let mut size = 4 + count * 4 + dds_sizes.iter().sum::<usize>();
+    if version == FormatVersion::V2 {
+        // Magic + Version + Flags
+        size += MAGIC.len() + 8;
+    }
+    if sections.shared_data {
+        size += count * 4;
+    }
+    if sections.descriptors.is_some() {
+        size += count * DESCRIPTOR_SIZE;
+    }
+    if sections.checksums {
+        // Checksums + Trailer
+        size += count * 4 + 4;
+    }
+    if let Some(metadata) = &sections.metadata {
+        size += 4 + metadata.len();
+    }
+    size
+}
+
+/// 変換後のDDSデータのヘッダーを読み取る
+fn converted_header_of(dds_data: &[u8]) -> ServiceResult<DdsHeader> {
+    DdsHeader::parse(dds_data).map_err(|e| {
+        error!("Failed to parse converted dds header: {}", e);
+        ServiceError::from(InfrastructureError::Converter(format!(
+            "converter output is not a valid dds: {}",
+            e
+        )))
+    })
+}
+
+/// 変換前の画像と変換後のDDSデータから記述子を作る
+pub(crate) fn describe_entry(image: &Image, dds_data: &[u8]) -> ServiceResult<EntryDescriptor> {
+    let header = converted_header_of(dds_data)?;
+
+    Ok(EntryDescriptor {
+        width: image.width as i32,
+        height: image.height as i32,
+        format: header.format.unity_texture_format(),
+        mip_count: header.mip_count as i32,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::MergedFile;
     use tokio::fs;
 
     #[tokio::test]
@@ -149,7 +937,9 @@ mod tests {
         let jpeg_data = fs::read("resources/4_multiple_size.jpg")
             .await
             .unwrap();
-        let result = service.execute("", &[jpeg_data]).await;
+        let result = service
+            .execute("", &[jpeg_data], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
     }
 
@@ -159,7 +949,9 @@ mod tests {
             Arc::new(MockConverter::succeed()),
             Arc::new(MockStorage::succeed()),
         );
-        let result = service.execute("https://example.com", &[]).await;
+        let result = service
+            .execute("https://example.com", &[], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
     }
 
@@ -172,7 +964,9 @@ mod tests {
         let jpeg_data = fs::read("resources/4_multiple_size.jpg")
             .await
             .unwrap();
-        let result = service.execute("https://example.com", &[jpeg_data]).await;
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
     }
 
@@ -189,7 +983,11 @@ mod tests {
             .await
             .unwrap();
         let result = service
-            .execute("https://example.com", &[jpeg_data1, jpeg_data2])
+            .execute(
+                "https://example.com",
+                &[jpeg_data1, jpeg_data2],
+                &Default::default(),
+            )
             .await;
         assert!(result.is_ok());
     }
@@ -203,7 +1001,9 @@ mod tests {
         let jpeg_data = fs::read("resources/4_multiple_size.jpg")
             .await
             .unwrap();
-        let result = service.execute("https://example.com", &[jpeg_data]).await;
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
     }
 
@@ -236,20 +1036,894 @@ mod tests {
     }
 
     #[test]
-    fn 空のリストならエラーを返す() {
-        let result = create_merged_format(&[]);
-        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    fn 生成したバイナリをモデルで読み戻せる() {
+        let dds_data_list = vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10, 11]];
+        let merged_data = create_merged_format(&dds_data_list).unwrap();
+
+        let merged_file = MergedFile::try_from(merged_data).unwrap();
+        let entries: Vec<Vec<u8>> = merged_file.entries().map(<[u8]>::to_vec).collect();
+        assert_eq!(entries, dds_data_list);
     }
 
-    #[tokio::test]
-    async fn 空の画像データならバリデーションエラーを返す() {
-        let service = UploadMergedImageServiceImpl::new(
-            Arc::new(MockConverter::succeed()),
-            Arc::new(MockStorage::succeed()),
-        );
-        let result = service
-            .execute("https://example.com", &[vec![]])
-            .await;
+    #[test]
+    fn v2形式のバイナリが正しく生成される() {
+        let dds_data_list = vec![vec![1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10, 11]];
+
+        let result = create_merged_format_v2(&dds_data_list, &Default::default()).unwrap();
+
+        // Magic: "VRCB"
+        assert_eq!(result[0..4], *b"VRCB");
+
+        // Version: 2 (4 bytes, little endian)
+        assert_eq!(result[4..8], [2, 0, 0, 0]);
+
+        // Flags: 0 (4 bytes, little endian)
+        assert_eq!(result[8..12], [0, 0, 0, 0]);
+
+        // 以降は v1 と同じ
+        assert_eq!(result[12..], create_merged_format(&dds_data_list).unwrap()[..]);
+
+        let merged_file = MergedFile::try_from(result).unwrap();
+        assert_eq!(merged_file.version(), FormatVersion::V2);
+        assert_eq!(merged_file.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn v2を指定したならv2形式でアップロードする() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        assert_eq!(uploaded[0..4], *b"VRCB");
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(merged_file.version(), FormatVersion::V2);
+    }
+
+    #[test]
+    fn 記述子付きのv2形式のバイナリが正しく生成される() {
+        let dds_data_list = vec![vec![1, 2], vec![3]];
+        let descriptors = vec![
+            EntryDescriptor {
+                width: 8,
+                height: 4,
+                format: 10,
+                mip_count: 1,
+            },
+            EntryDescriptor {
+                width: 4,
+                height: 4,
+                format: 12,
+                mip_count: 3,
+            },
+        ];
+        let sections = MergedFormatSections {
+            descriptors: Some(descriptors.clone()),
+            ..Default::default()
+        };
+
+        let result = create_merged_format_v2(&dds_data_list, &sections).unwrap();
+
+        // Flags: FLAG_DESCRIPTORS
+        assert_eq!(result[8..12], [1, 0, 0, 0]);
+
+        // Descriptors: count(4) + sizes(8) の後ろ
+        assert_eq!(result[24..40], descriptors[0].to_le_bytes());
+        assert_eq!(result[40..56], descriptors[1].to_le_bytes());
+
+        // Data
+        assert_eq!(result[56..], [1, 2, 3]);
+
+        let merged_file = MergedFile::try_from(result).unwrap();
+        assert_eq!(merged_file.descriptors().unwrap(), descriptors.as_slice());
+    }
+
+    #[test]
+    fn 記述子の数が合わないならエラーを返す() {
+        let sections = MergedFormatSections {
+            descriptors: Some(vec![]),
+            ..Default::default()
+        };
+        let result = create_merged_format_v2(&[vec![1]], &sections);
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn v1で任意セクションを指定したならエラーを返す() {
+        let sections = MergedFormatSections {
+            descriptors: Some(vec![]),
+            ..Default::default()
+        };
+        let result = create_merged_format_of(FormatVersion::V1, &[vec![1]], &sections);
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 記述子を指定したなら画像とddsの情報を書き込む() {
+        use crate::model::dds::test_util::build_dds;
+
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(build_dds(16, 16, 5, b"DXT1")))),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let image = Image::try_from(jpeg_data.as_slice()).unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            descriptors: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        let descriptor = merged_file.descriptors().unwrap()[0];
+        assert_eq!(descriptor.width, image.width as i32);
+        assert_eq!(descriptor.height, image.height as i32);
+        assert_eq!(descriptor.format, 10);
+        assert_eq!(descriptor.mip_count, 5);
+    }
+
+    #[tokio::test]
+    async fn v1で記述子を指定したならエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V1,
+            descriptors: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 変換結果がddsでないなら記述子を作れずエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            descriptors: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
+    }
+
+    #[test]
+    fn メタデータ付きのv2形式のバイナリが正しく生成される() {
+        let sections = MergedFormatSections {
+            metadata: Some(r#"[{"a":1}]"#.to_string()),
+            ..Default::default()
+        };
+
+        let result = create_merged_format_v2(&[vec![1, 2]], &sections).unwrap();
+
+        // Flags: FLAG_METADATA
+        assert_eq!(result[8..12], [2, 0, 0, 0]);
+
+        // Metadata: count(4) + sizes(4) の後ろ
+        assert_eq!(result[20..24], [9, 0, 0, 0]);
+        assert_eq!(result[24..33], *br#"[{"a":1}]"#);
+
+        // Data
+        assert_eq!(result[33..], [1, 2]);
+    }
+
+    #[tokio::test]
+    async fn メタデータを指定したならv2形式に埋め込む() {
+        use std::str::FromStr;
+
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            metadata: Some(ImageMetadata::from_str(r#"[{"fileName": "a.jpg"}]"#).unwrap()),
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(merged_file.metadata().unwrap(), r#"[{"fileName":"a.jpg"}]"#);
+    }
+
+    #[tokio::test]
+    async fn メタデータの件数が画像の枚数と異なるならエラーを返す() {
+        use std::str::FromStr;
+
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            metadata: Some(
+                ImageMetadata::from_str(r#"[{"fileName": "a.jpg"}, {"fileName": "b.jpg"}]"#)
+                    .unwrap(),
+            ),
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("metadata entry count must match image count"));
+        }
+    }
+
+    #[tokio::test]
+    async fn v1でメタデータを指定したならエラーを返す() {
+        use std::str::FromStr;
+
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            metadata: Some(ImageMetadata::from_str(r#"[{"fileName": "a.jpg"}]"#).unwrap()),
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "metadata requires format version 2");
+        }
+    }
+
+    #[tokio::test]
+    async fn テクスチャ配列でメタデータを指定したならエラーを返す() {
+        use std::str::FromStr;
+
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            metadata: Some(ImageMetadata::from_str(r#"[{"fileName": "a.jpg"}]"#).unwrap()),
+            texture_array: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "metadata cannot be used with texture array");
+        }
+    }
+
+    #[test]
+    fn チェックサム付きのv2形式のバイナリが正しく生成される() {
+        let sections = MergedFormatSections {
+            checksums: true,
+            ..Default::default()
+        };
+
+        let result = create_merged_format_v2(&[vec![1, 2], vec![3]], &sections).unwrap();
+
+        // Flags: FLAG_CHECKSUMS
+        assert_eq!(result[8..12], [4, 0, 0, 0]);
+
+        // Checksums: count(4) + sizes(8) の後ろ
+        assert_eq!(result[24..28], crc32fast::hash(&[1, 2]).to_le_bytes());
+        assert_eq!(result[28..32], crc32fast::hash(&[3]).to_le_bytes());
+
+        // Data
+        assert_eq!(result[32..35], [1, 2, 3]);
+
+        // Trailer: ここより前の全バイトの CRC32
+        assert_eq!(result[35..], crc32fast::hash(&result[..35]).to_le_bytes());
+    }
+
+    #[tokio::test]
+    async fn チェックサムを指定したなら書き込んだチェックサムを返す() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            checksums: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", std::slice::from_ref(&jpeg_data), &options)
+            .await
+            .unwrap();
+
+        let checksums = result.checksums.unwrap();
+        assert_eq!(checksums.entries, vec![crc32fast::hash(&jpeg_data)]);
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let merged_file = MergedFile::try_from(uploaded).unwrap();
+        assert_eq!(merged_file.checksums().unwrap(), checksums.entries.as_slice());
+        assert_eq!(merged_file.file_checksum(), Some(checksums.file));
+    }
+
+    #[tokio::test]
+    async fn チェックサムを指定しなければチェックサムを返さない() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &Default::default())
+            .await
+            .unwrap();
+        assert!(result.checksums.is_none());
+    }
+
+    #[tokio::test]
+    async fn v1でチェックサムを指定したならエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            checksums: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn 同じ内容のデータを共有するv2形式のバイナリが正しく生成される() {
+        let sections = MergedFormatSections {
+            shared_data: true,
+            ..Default::default()
+        };
+
+        let result =
+            create_merged_format_v2(&[vec![1, 2], vec![3], vec![1, 2]], &sections).unwrap();
+
+        // Flags: FLAG_SHARED_DATA
+        assert_eq!(result[8..12], [8, 0, 0, 0]);
+
+        // Offsets: count(4) + sizes(12) の後ろ
+        assert_eq!(result[28..32], 0i32.to_le_bytes());
+        assert_eq!(result[32..36], 2i32.to_le_bytes());
+        assert_eq!(result[36..40], 0i32.to_le_bytes());
+
+        // Data: 同じ内容のデータは1つだけ
+        assert_eq!(result[40..], [1, 2, 3]);
+
+        assert_eq!(
+            merged_format_size(FormatVersion::V2, &[2, 1, 0], &sections),
+            result.len()
+        );
+    }
+
+    #[tokio::test]
+    async fn dedupを指定したなら同じ画像のデータを1つだけ書き込む() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::new(move |_, data| {
+                uploaded_clone.lock().unwrap().push(data.to_vec());
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let images = vec![jpeg_data.clone(), jpeg_data.clone(), jpeg_data.clone()];
+        for dedup in [false, true] {
+            let options = UploadMergedImageOptions {
+                format_version: FormatVersion::V2,
+                dedup,
+                ..Default::default()
+            };
+            service
+                .execute("https://example.com", &images, &options)
+                .await
+                .unwrap();
+        }
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        assert_eq!(uploaded[0].len() - uploaded[1].len(), jpeg_data.len() * 2 - 12);
+
+        let merged_file = MergedFile::try_from(uploaded[1].clone()).unwrap();
+        assert!(merged_file.has_shared_data());
+        let entries: Vec<&[u8]> = merged_file.entries().collect();
+        assert_eq!(entries, vec![jpeg_data.as_slice(); 3]);
+    }
+
+    #[tokio::test]
+    async fn v1でdedupを指定したならエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            dedup: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn テクスチャ配列を指定したならdx10ヘッダー付きのddsをアップロードする() {
+        use crate::model::dds::test_util::build_dds;
+        use crate::model::dds::{DDS_HEADER_DXT10_SIZE, DDS_HEADER_SIZE};
+
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| {
+                let mut data = build_dds(16, 16, 1, b"DXT1");
+                data.extend([0; 128]);
+                Ok(data)
+            })),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            texture_array: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &vec![jpeg_data; 3], &options)
+            .await
+            .unwrap();
+
+        let header = result.texture_array.unwrap();
+        assert_eq!(header.array_size, 3);
+        let uploaded = uploaded.lock().unwrap().clone();
+        assert_eq!(
+            uploaded.len(),
+            DDS_HEADER_SIZE + DDS_HEADER_DXT10_SIZE + 3 * 128
+        );
+        assert_eq!(DdsHeader::parse(&uploaded).unwrap(), header);
+    }
+
+    #[tokio::test]
+    async fn テクスチャ配列でサイズの異なる画像があるならエラーを返す() {
+        use image::{DynamicImage, RgbaImage};
+
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let png = |width, height| {
+            Image::encode(&DynamicImage::ImageRgba8(RgbaImage::new(width, height)))
+                .unwrap()
+                .data
+        };
+        let options = UploadMergedImageOptions {
+            texture_array: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[png(8, 8), png(8, 4)], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("texture array requires all images to be the same size"));
+            assert!(msg.contains("image at index 1: 8x4, first image: 8x8"));
+        }
+    }
+
+    #[tokio::test]
+    async fn v2でテクスチャ配列を指定したならエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            texture_array: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn base64を指定したならbase64でアップロードする() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![1, 2, 3]))),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            encoding: OutputEncoding::Base64,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(result.is_ok());
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        let decoded = OutputEncoding::Base64.decode(uploaded).unwrap();
+        assert_eq!(decoded, create_merged_format(&[vec![1, 2, 3]]).unwrap());
+    }
+
+    #[tokio::test]
+    async fn base64でエンコード後に10mbを超えるならバリデーションエラーを返す() {
+        // NOTE: 8 MB のバイナリは Base64 にすると約 10.7 MB になる
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0; 8 * 1024 * 1024]))),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            encoding: OutputEncoding::Base64,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn 組み立てずに計算したサイズが実際のサイズと一致する() {
+        let dds_data_list = vec![vec![1, 2, 3], vec![4, 5]];
+        let sections = MergedFormatSections {
+            descriptors: Some(vec![
+                EntryDescriptor {
+                    width: 4,
+                    height: 4,
+                    format: 10,
+                    mip_count: 1,
+                };
+                2
+            ]),
+            metadata: Some(r#"[{"a":1},{"b":2}]"#.to_string()),
+            checksums: true,
+            shared_data: true,
+        };
+
+        let v1 = create_merged_format(&dds_data_list).unwrap();
+        assert_eq!(
+            merged_format_size(FormatVersion::V1, &[3, 2], &Default::default()),
+            v1.len()
+        );
+
+        let v2 = create_merged_format_v2(&dds_data_list, &sections).unwrap();
+        assert_eq!(
+            merged_format_size(FormatVersion::V2, &[3, 2], &sections),
+            v2.len()
+        );
+    }
+
+    /// アップロード先の URL とアップロードされたデータの記録
+    type Uploaded = Arc<std::sync::Mutex<Vec<(String, Vec<u8>)>>>;
+
+    /// アップロード先の URL ごとにアップロードされたデータを記録するストレージ
+    fn recording_storage() -> (MockStorage, Uploaded) {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let storage = MockStorage::new(move |url, data| {
+            uploaded_clone
+                .lock()
+                .unwrap()
+                .push((url.to_string(), data.to_vec()));
+            Ok(())
+        });
+        (storage, uploaded)
+    }
+
+    #[tokio::test]
+    async fn 分割を指定したなら10mbに収まるように先頭から詰めてアップロードする() {
+        use std::str::FromStr;
+
+        // NOTE: 4 MB の画像は2枚までなら 10 MB に収まる
+        let (storage, uploaded) = recording_storage();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0; 4 * 1024 * 1024]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            format_version: FormatVersion::V2,
+            metadata: Some(
+                ImageMetadata::from_str(r#"[{"a": 0}, {"a": 1}, {"a": 2}]"#).unwrap(),
+            ),
+            checksums: true,
+            ..Default::default()
+        };
+        let urls = vec![
+            "https://example.com/0".to_string(),
+            "https://example.com/1".to_string(),
+            "https://example.com/2".to_string(),
+        ];
+        let result = service
+            .execute_split(&urls, &vec![jpeg_data; 3], &options)
+            .await
+            .unwrap();
+
+        assert_eq!(result.files.len(), 2);
+        assert_eq!(result.files[0].range, 0..2);
+        assert_eq!(result.files[1].range, 2..3);
+
+        let uploaded = uploaded.lock().unwrap().clone();
+        assert_eq!(uploaded.len(), 2);
+        assert_eq!(uploaded[0].0, "https://example.com/0");
+        assert_eq!(uploaded[1].0, "https://example.com/1");
+
+        let first = MergedFile::try_from(uploaded[0].1.clone()).unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first.metadata().unwrap(), r#"[{"a":0},{"a":1}]"#);
+        let second = MergedFile::try_from(uploaded[1].1.clone()).unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second.metadata().unwrap(), r#"[{"a":2}]"#);
+        assert_eq!(
+            result.files[1].checksums.as_ref().unwrap().file,
+            second.file_checksum().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn 分割に必要なurlが足りないなら何もアップロードせずエラーを返す() {
+        let (storage, uploaded) = recording_storage();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0; 4 * 1024 * 1024]))),
+            Arc::new(storage),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let urls = vec!["https://example.com/0".to_string()];
+        let result = service
+            .execute_split(&urls, &vec![jpeg_data; 3], &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("not enough presigned urls"));
+        }
+        assert!(uploaded.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn 一枚で10mbを超える画像があるなら分割できずエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0; 11 * 1024 * 1024]))),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let urls = vec!["https://example.com/0".to_string()];
+        let result = service
+            .execute_split(&urls, &[jpeg_data], &Default::default())
+            .await;
+
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("image at index 0 does not fit in 10 MB"));
+        }
+    }
+
+    #[tokio::test]
+    async fn 空のurlが含まれるなら分割でエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let urls = vec!["https://example.com/0".to_string(), " ".to_string()];
+        let result = service
+            .execute_split(&urls, &[jpeg_data], &Default::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 縮小を指定したなら10mbに収まるまで大きい画像から縮小する() {
+        // NOTE: 2160x3840 の DXT1 はミップマップ込みで約 5.3 MB なので2枚だと 10 MB を超える
+        let converted = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let converted_clone = converted.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(move |image| {
+                let image = Image::try_from(image).unwrap();
+                converted_clone
+                    .lock()
+                    .unwrap()
+                    .push((image.width, image.height));
+                Ok(vec![0; 16])
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            downscale_to_fit: true,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &vec![jpeg_data; 2], &options)
+            .await
+            .unwrap();
+
+        let dimensions = result.dimensions.unwrap();
+        assert!(!dimensions[0].is_downscaled());
+        assert_eq!((dimensions[0].width, dimensions[0].height), (2160, 3840));
+        assert!(dimensions[1].is_downscaled());
+        assert_eq!(
+            (dimensions[1].original_width, dimensions[1].original_height),
+            (2160, 3840)
+        );
+        assert_eq!(dimensions[1].width % 4, 0);
+        assert_eq!(dimensions[1].height % 4, 0);
+        assert!(dimensions[1].height < 3840);
+
+        // 縮小した画像が変換に渡される
+        let converted = converted.lock().unwrap().clone();
+        assert_eq!(
+            converted,
+            vec![
+                (2160, 3840),
+                (dimensions[1].width, dimensions[1].height)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn 縮小を指定しなければ縮小結果を返さない() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let result = service
+            .execute("https://example.com", &[jpeg_data], &Default::default())
+            .await
+            .unwrap();
+        assert!(result.dimensions.is_none());
+    }
+
+    #[tokio::test]
+    async fn 分割と縮小は同時に指定できない() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            downscale_to_fit: true,
+            ..Default::default()
+        };
+        let urls = vec!["https://example.com/0".to_string()];
+        let result = service.execute_split(&urls, &[jpeg_data], &options).await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[test]
+    fn 空のリストならエラーを返す() {
+        let result = create_merged_format(&[]);
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn 空の画像データならバリデーションエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let result = service
+            .execute("https://example.com", &[vec![]], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
             assert!(msg.contains("image at index 0 is empty"));
@@ -264,7 +1938,7 @@ mod tests {
         );
         let invalid_data = vec![vec![0, 1, 2, 3, 4, 5]];
         let result = service
-            .execute("https://example.com", &invalid_data)
+            .execute("https://example.com", &invalid_data, &Default::default())
             .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
@@ -272,6 +1946,174 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn ミップマップを指定したなら各画像の段数を返す() {
+        use crate::model::dds::test_util::build_dds;
+        use crate::model::mipmap::mip_count;
+
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::with_options(|image, options| {
+                let image = Image::try_from(image).unwrap();
+                let mip_count = mip_count(image.width, image.height, options.mipmaps.as_ref());
+                Ok(build_dds(image.width, image.height, mip_count, b"DXT1"))
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+        let png = |size| {
+            Image::encode(&image::DynamicImage::new_rgb8(size, size))
+                .unwrap()
+                .data
+        };
+        let options = UploadMergedImageOptions {
+            mipmaps: Some(MipmapOptions {
+                min_size: 4,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &[png(16), png(4)], &options)
+            .await
+            .unwrap();
+        // 16x16, 8x8, 4x4 と 4x4 のみ
+        assert_eq!(result.mip_counts, Some(vec![3, 1]));
+    }
+
+    #[tokio::test]
+    async fn flip_yを指定したなら全ての画像の上下を反転してから変換する() {
+        use image::{DynamicImage, Rgba, RgbaImage};
+
+        let top_row_red = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let top_row_red_clone = top_row_red.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(move |image| {
+                let decoded = image::load_from_memory(image).unwrap().to_rgba8();
+                let red = decoded.get_pixel(0, 0) == &Rgba([255, 0, 0, 255]);
+                top_row_red_clone.lock().unwrap().push(red);
+                Ok(image.to_vec())
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+        let mut img = RgbaImage::from_pixel(4, 8, Rgba([0, 0, 0, 255]));
+        for x in 0..4 {
+            img.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        let png_data = Image::encode(&DynamicImage::ImageRgba8(img)).unwrap().data;
+        let options = UploadMergedImageOptions {
+            flip_y: true,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &[png_data.clone(), png_data],
+                &options,
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*top_row_red.lock().unwrap(), vec![false, false]);
+    }
+
+    #[tokio::test]
+    async fn 許可されていない圧縮の設定ならアップロードせずにバリデーションエラーを返す() {
+        let uploaded = Arc::new(std::sync::Mutex::new(false));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::new(move |_, _| {
+                *uploaded_clone.lock().unwrap() = true;
+                Ok(())
+            })),
+        )
+        .with_conversion_policy(
+            ConversionPolicy::parse("dxt1,dxt5", "0-255", "srgb", true).unwrap(),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UploadMergedImageOptions {
+            compression: CompressionOptions {
+                color_space: crate::model::ColorSpace::Linear,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &[jpeg_data.clone(), jpeg_data],
+                &options,
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "color space linear is not allowed (allowed: srgb)");
+        }
+        assert!(!*uploaded.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn 後ろの画像の変換の設定が許可されていないなら1枚も変換せずにエラーを返す() {
+        let converted = Arc::new(std::sync::Mutex::new(0));
+        let converted_clone = converted.clone();
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::new(move |image| {
+                *converted_clone.lock().unwrap() += 1;
+                Ok(image.to_vec())
+            })),
+            Arc::new(MockStorage::succeed()),
+        )
+        .with_conversion_policy(ConversionPolicy::parse("dxt1", "0-255", "srgb", true).unwrap());
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        // NOTE: 半透明の画像は DXT5 に変換するので許可されていない
+        let transparent_png = Image::encode(&image::DynamicImage::ImageRgba8(
+            image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 128])),
+        ))
+        .unwrap()
+        .data;
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &[jpeg_data, transparent_png],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "texture format dxt5 is not allowed (allowed: dxt1)");
+        }
+        assert_eq!(*converted.lock().unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn 許可されていない形式の画像があるなら形式のエラーを返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        )
+        .with_allowed_formats("jpeg".parse().unwrap());
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let png_data = Image::encode(&image::DynamicImage::new_rgba8(4, 4))
+            .unwrap()
+            .data;
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &[jpeg_data, png_data],
+                &Default::default(),
+            )
+            .await;
+        assert!(matches!(result, Err(ServiceError::UnsupportedFormat(_))));
+        if let Err(ServiceError::UnsupportedFormat(e)) = result {
+            assert_eq!(e.index, Some(1));
+            assert_eq!(e.detected, "png");
+            assert_eq!(e.allowed, vec!["jpeg"]);
+        }
+    }
+
     #[tokio::test]
     async fn 四の倍数でないサイズの画像ならバリデーションエラーを返す() {
         let service = UploadMergedImageServiceImpl::new(
@@ -284,7 +2126,7 @@ mod tests {
             .unwrap();
 
         let result = service
-            .execute("https://example.com", &[jpeg_data])
+            .execute("https://example.com", &[jpeg_data], &Default::default())
             .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
@@ -293,6 +2135,39 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn 補正方法を指定したなら四の倍数でない画像だけ補正して結果を返す() {
+        let service = UploadMergedImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let valid_data = fs::read("resources/4_multiple_size.jpg")
+            .await
+            .unwrap();
+        let invalid_data = fs::read("resources/not_4_multiple_width.jpg")
+            .await
+            .unwrap();
+        let options = UploadMergedImageOptions {
+            dimension_fix: Some(DimensionFix::Pad {
+                color: [255, 255, 255, 255],
+            }),
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &[valid_data, invalid_data], &options)
+            .await
+            .unwrap();
+        assert_eq!(result.fixed_dimensions.len(), 2);
+        assert_eq!(result.fixed_dimensions[0], None);
+        let fixed = result.fixed_dimensions[1].unwrap();
+        assert_ne!(fixed.original_width % 4, 0);
+        assert_eq!(fixed.width % 4, 0);
+        assert!(fixed.width > fixed.original_width);
+        assert_eq!(fixed.height, fixed.original_height);
+    }
+
     #[tokio::test]
     async fn 複数の画像のうち一つが無効ならエラーを返す() {
         let service = UploadMergedImageServiceImpl::new(
@@ -309,7 +2184,11 @@ mod tests {
             .unwrap();
 
         let result = service
-            .execute("https://example.com", &[valid_image_data, invalid_image_data])
+            .execute(
+                "https://example.com",
+                &[valid_image_data, invalid_image_data],
+                &Default::default(),
+            )
             .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
@@ -335,7 +2214,9 @@ mod tests {
             count += 1;
         }
 
-        let result = service.execute("https://example.com", &images).await;
+        let result = service
+            .execute("https://example.com", &images, &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
     }
 }
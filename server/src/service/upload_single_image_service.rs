@@ -3,36 +3,115 @@ use log::{error, info};
 use std::sync::Arc;
 
 use crate::infrastructure::{Converter, Storage};
-use crate::model::{Image, ImageError};
+use crate::model::mipmap::mip_count;
+use crate::model::{
+    AllowedFormats, CompressionOptions, ConversionOptions, ConversionPolicy, DimensionFix,
+    FixedDimensions, Image, ImageError, MipmapOptions, OutputEncoding, TextureFormat,
+};
 use crate::service::error::{ServiceError, ServiceResult};
+use crate::service::upload_merged_image_service::MAX_MERGED_DATA_SIZE;
+
+/// 1枚画像アップロード時のオプション
+#[derive(Debug, Clone)]
+pub struct UploadSingleImageOptions {
+    /// アップロードするファイルのエンコーディング（デフォルトはバイナリ）
+    pub encoding: OutputEncoding,
+    /// 縦横のピクセル数が4の倍数でない場合の補正方法（指定しなければエラーにする）
+    pub dimension_fix: Option<DimensionFix>,
+    /// 変換後のフォーマット（デフォルトは不透明でないピクセルがあれば DXT5、なければ DXT1）
+    pub format: TextureFormat,
+    /// 圧縮の品質・色空間・ディザリング（デフォルトは最高品質、sRGB、ディザリングなし）
+    pub compression: CompressionOptions,
+    /// ミップマップの設定（None ならミップマップなし、デフォルトは 1x1 までのミップマップを生成する）
+    pub mipmaps: Option<MipmapOptions>,
+    /// 変換前に上下を反転するか（Unity の `LoadRawTextureData` で正しい向きにする）
+    pub flip_y: bool,
+}
+
+impl Default for UploadSingleImageOptions {
+    fn default() -> Self {
+        Self {
+            encoding: OutputEncoding::default(),
+            dimension_fix: None,
+            format: TextureFormat::default(),
+            compression: CompressionOptions::default(),
+            mipmaps: Some(MipmapOptions::default()),
+            flip_y: false,
+        }
+    }
+}
+
+/// 1枚画像アップロードの結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UploadSingleImageResult {
+    /// 縦横のピクセル数を補正した結果（補正した場合のみ）
+    pub fixed_dimensions: Option<FixedDimensions>,
+    /// ミップマップの段数（ミップマップを指定した場合のみ）
+    pub mip_count: Option<u32>,
+}
 
 #[async_trait]
 pub trait UploadSingleImageService: Send + Sync {
-    async fn execute(&self, presigned_url: &str, image: &[u8]) -> ServiceResult<()>;
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        image: &[u8],
+        options: &UploadSingleImageOptions,
+    ) -> ServiceResult<UploadSingleImageResult>;
 }
 
 pub struct UploadSingleImageServiceImpl {
     converter: Arc<dyn Converter>,
     storage: Arc<dyn Storage>,
+    allowed_formats: AllowedFormats,
+    conversion_policy: ConversionPolicy,
 }
 
 impl UploadSingleImageServiceImpl {
     pub fn new(converter: Arc<dyn Converter>, storage: Arc<dyn Storage>) -> Self {
-        Self { converter, storage }
+        Self {
+            converter,
+            storage,
+            allowed_formats: AllowedFormats::default(),
+            conversion_policy: ConversionPolicy::default(),
+        }
+    }
+
+    /// 入力を許可する画像形式を指定する（デフォルトは jpeg と png）
+    pub fn with_allowed_formats(mut self, allowed_formats: AllowedFormats) -> Self {
+        self.allowed_formats = allowed_formats;
+        self
+    }
+
+    /// 許可する変換の設定を指定する（デフォルトは全て許可する）
+    pub fn with_conversion_policy(mut self, conversion_policy: ConversionPolicy) -> Self {
+        self.conversion_policy = conversion_policy;
+        self
     }
 }
 
 #[async_trait]
 impl UploadSingleImageService for UploadSingleImageServiceImpl {
-    async fn execute(&self, presigned_url: &str, image: &[u8]) -> ServiceResult<()> {
+    async fn execute(
+        &self,
+        presigned_url: &str,
+        image: &[u8],
+        options: &UploadSingleImageOptions,
+    ) -> ServiceResult<UploadSingleImageResult> {
         if presigned_url.trim().is_empty() {
             return Err(ServiceError::Validation(
                 "presigned url must not be empty".to_string(),
             ));
         }
 
+        self.allowed_formats.check(image)?;
+
         // 画像データをモデルに変換（バリデーション付き）
-        let image_model = Image::try_from(image).map_err(|e| match e {
+        let (mut image_model, fixed_dimensions) = Image::try_from_with_fix(
+            image,
+            options.dimension_fix.as_ref(),
+        )
+        .map_err(|e| match e {
             ImageError::EmptyData => {
                 ServiceError::Validation("image bytes must not be empty".to_string())
             }
@@ -43,28 +122,69 @@ impl UploadSingleImageService for UploadSingleImageServiceImpl {
                 "image dimensions must be multiples of 4 (width: {}, height: {})",
                 width, height
             )),
+            ImageError::EncodeError(msg) => {
+                ServiceError::Validation(format!("failed to encode image: {}", msg))
+            }
         })?;
 
-        info!("Starting upload_single_image_service");
+        // NOTE: 縦横のピクセル数は元の向きで補正してから反転する
+        if options.flip_y {
+            image_model = image_model
+                .flip_vertical()
+                .map_err(|e| ServiceError::Validation(format!("failed to flip image: {}", e)))?;
+        }
+
+        let format = options
+            .format
+            .resolve(&image_model)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
 
+        info!(
+            "Starting upload_single_image_service (format: {})",
+            format.name()
+        );
+
+        let conversion = ConversionOptions {
+            mipmaps: options.mipmaps,
+            ..ConversionOptions::new(format, &options.compression)
+        };
+        self.conversion_policy
+            .check(&conversion)
+            .map_err(|e| ServiceError::Validation(e.to_string()))?;
         let dds_data = self
             .converter
-            .jpeg_to_dds(image_model.as_bytes())
+            .jpeg_to_dds(image_model.as_bytes(), &conversion)
             .await
             .map_err(|e| {
                 error!("Failed to convert image to dds: {}", e);
                 ServiceError::from(e)
             })?;
 
+        let mip_count = options
+            .mipmaps
+            .as_ref()
+            .map(|mipmaps| mip_count(image_model.width, image_model.height, Some(mipmaps)));
+
+        // Base64 は String Loading で読み込む前提なので、エンコード後のサイズで上限を確認する
+        let output = options.encoding.encode(dds_data);
+        if options.encoding == OutputEncoding::Base64 && output.len() > MAX_MERGED_DATA_SIZE {
+            return Err(ServiceError::Validation(
+                "encoded data size must be less than 10 MB".to_string(),
+            ));
+        }
+
         self.storage
-            .upload_file(presigned_url, &dds_data)
+            .upload_file(presigned_url, &output)
             .await
             .map_err(|e| {
                 error!("Failed to upload file to storage: {}", e);
                 ServiceError::from(e)
             })?;
 
-        Ok(())
+        Ok(UploadSingleImageResult {
+            fixed_dimensions,
+            mip_count,
+        })
     }
 }
 
@@ -72,6 +192,10 @@ impl UploadSingleImageService for UploadSingleImageServiceImpl {
 mod tests {
     use super::*;
     use crate::mock::infrastructure::{MockConverter, MockStorage};
+    use crate::model::dds::test_util::build_dds;
+    use crate::model::mipmap::mip_count;
+    use crate::model::{ColorSpace, DdsFormat};
+    use image::{DynamicImage, Rgba, RgbaImage};
     use tokio::fs;
 
     #[tokio::test]
@@ -80,10 +204,8 @@ mod tests {
             Arc::new(MockConverter::succeed()),
             Arc::new(MockStorage::succeed()),
         );
-        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
-            .await
-            .unwrap();
-        let result = service.execute("", &jpeg_data).await;
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service.execute("", &jpeg_data, &Default::default()).await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
     }
 
@@ -93,10 +215,10 @@ mod tests {
             Arc::new(MockConverter::fail("fail")),
             Arc::new(MockStorage::succeed()),
         );
-        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
-            .await
-            .unwrap();
-        let result = service.execute("https://example.com", &jpeg_data).await;
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute("https://example.com", &jpeg_data, &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
     }
 
@@ -106,10 +228,10 @@ mod tests {
             Arc::new(MockConverter::succeed()),
             Arc::new(MockStorage::succeed()),
         );
-        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
-            .await
-            .unwrap();
-        let result = service.execute("https://example.com", &jpeg_data).await;
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute("https://example.com", &jpeg_data, &Default::default())
+            .await;
         assert!(result.is_ok());
     }
 
@@ -119,10 +241,10 @@ mod tests {
             Arc::new(MockConverter::succeed()),
             Arc::new(MockStorage::fail("upload failed")),
         );
-        let jpeg_data = fs::read("resources/4_multiple_size.jpg")
-            .await
-            .unwrap();
-        let result = service.execute("https://example.com", &jpeg_data).await;
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute("https://example.com", &jpeg_data, &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Infrastructure(_))));
     }
 
@@ -132,7 +254,9 @@ mod tests {
             Arc::new(MockConverter::succeed()),
             Arc::new(MockStorage::succeed()),
         );
-        let result = service.execute("https://example.com", &[]).await;
+        let result = service
+            .execute("https://example.com", &[], &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
             assert!(msg.contains("image bytes must not be empty"));
@@ -146,13 +270,146 @@ mod tests {
             Arc::new(MockStorage::succeed()),
         );
         let invalid_data = vec![0, 1, 2, 3, 4, 5];
-        let result = service.execute("https://example.com", &invalid_data).await;
+        let result = service
+            .execute("https://example.com", &invalid_data, &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
             assert!(msg.contains("failed to decode image"));
         }
     }
 
+    #[tokio::test]
+    async fn 指定しなければ1x1までのミップマップをコンバーターに渡して段数を返す() {
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::with_options(|image, options| {
+                assert_eq!(options.mipmaps, Some(MipmapOptions::default()));
+                let image = Image::try_from(image).unwrap();
+                let mip_count = mip_count(image.width, image.height, options.mipmaps.as_ref());
+                Ok(build_dds(image.width, image.height, mip_count, b"DXT1"))
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &transparent_png(),
+                &Default::default(),
+            )
+            .await
+            .unwrap();
+        // 4x4, 2x2, 1x1
+        assert_eq!(result.mip_count, Some(3));
+    }
+
+    #[tokio::test]
+    async fn ミップマップなしを指定したなら段数を返さない() {
+        let mipmaps_given = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mipmaps_clone = mipmaps_given.clone();
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::with_options(move |image, options| {
+                mipmaps_clone.lock().unwrap().push(options.mipmaps);
+                Ok(image.to_vec())
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let options = UploadSingleImageOptions {
+            mipmaps: None,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &transparent_png(), &options)
+            .await
+            .unwrap();
+        assert_eq!(result.mip_count, None);
+        assert_eq!(*mipmaps_given.lock().unwrap(), vec![None]);
+    }
+
+    /// 先頭の行だけ赤い PNG
+    fn top_row_red_png() -> Vec<u8> {
+        let mut img = RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255]));
+        for x in 0..4 {
+            img.put_pixel(x, 0, Rgba([255, 0, 0, 255]));
+        }
+        Image::encode(&DynamicImage::ImageRgba8(img)).unwrap().data
+    }
+
+    /// 変換前の画像の先頭の行が赤いかを記録するコンバーター
+    fn top_row_converter(top_row_red: Arc<std::sync::Mutex<Vec<bool>>>) -> MockConverter {
+        MockConverter::new(move |image| {
+            let decoded = image::load_from_memory(image).unwrap().to_rgba8();
+            let red = decoded.get_pixel(0, 0) == &Rgba([255, 0, 0, 255]);
+            top_row_red.lock().unwrap().push(red);
+            Ok(image.to_vec())
+        })
+    }
+
+    #[tokio::test]
+    async fn flip_yを指定したなら上下を反転してから変換する() {
+        let top_row_red = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(top_row_converter(top_row_red.clone())),
+            Arc::new(MockStorage::succeed()),
+        );
+        let options = UploadSingleImageOptions {
+            flip_y: true,
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &top_row_red_png(), &options)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*top_row_red.lock().unwrap(), vec![false]);
+    }
+
+    #[tokio::test]
+    async fn flip_yを指定しなければ反転しない() {
+        let top_row_red = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(top_row_converter(top_row_red.clone())),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &top_row_red_png(),
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*top_row_red.lock().unwrap(), vec![true]);
+    }
+
+    #[tokio::test]
+    async fn 許可されていない形式の画像なら形式のエラーを返す() {
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+        let mut bmp_data = Vec::new();
+        DynamicImage::new_rgb8(4, 4)
+            .write_to(
+                &mut std::io::Cursor::new(&mut bmp_data),
+                image::ImageFormat::Bmp,
+            )
+            .unwrap();
+
+        let result = service
+            .execute("https://example.com", &bmp_data, &Default::default())
+            .await;
+        assert!(matches!(result, Err(ServiceError::UnsupportedFormat(_))));
+        if let Err(ServiceError::UnsupportedFormat(e)) = result {
+            assert_eq!(e.index, None);
+            assert_eq!(e.detected, "bmp");
+            assert_eq!(e.allowed, vec!["jpeg", "png"]);
+        }
+    }
+
     #[tokio::test]
     async fn 四の倍数でないサイズの画像ならバリデーションエラーを返す() {
         let service = UploadSingleImageServiceImpl::new(
@@ -163,10 +420,242 @@ mod tests {
         let jpeg_data = fs::read("resources/not_4_multiple_height.jpg")
             .await
             .unwrap();
-        let result = service.execute("https://example.com", &jpeg_data).await;
+        let result = service
+            .execute("https://example.com", &jpeg_data, &Default::default())
+            .await;
         assert!(matches!(result, Err(ServiceError::Validation(_))));
         if let Err(ServiceError::Validation(msg)) = result {
             assert!(msg.contains("dimensions must be multiples of 4"));
         }
     }
+
+    #[tokio::test]
+    async fn 補正方法を指定したなら四の倍数に補正して結果を返す() {
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let jpeg_data = fs::read("resources/not_4_multiple_height.jpg")
+            .await
+            .unwrap();
+        let options = UploadSingleImageOptions {
+            dimension_fix: Some(DimensionFix::Crop),
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &jpeg_data, &options)
+            .await
+            .unwrap();
+        let fixed = result.fixed_dimensions.unwrap();
+        assert_eq!(fixed.strategy, DimensionFix::Crop);
+        assert_ne!(fixed.original_height % 4, 0);
+        assert_eq!(fixed.width % 4, 0);
+        assert_eq!(fixed.height % 4, 0);
+        assert!(fixed.height < fixed.original_height);
+    }
+
+    #[tokio::test]
+    async fn 四の倍数のサイズの画像なら補正結果を返さない() {
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UploadSingleImageOptions {
+            dimension_fix: Some(DimensionFix::Resize),
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &jpeg_data, &options)
+            .await
+            .unwrap();
+        assert_eq!(result.fixed_dimensions, None);
+    }
+
+    #[tokio::test]
+    async fn base64を指定したならbase64でアップロードする() {
+        let uploaded = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let uploaded_clone = uploaded.clone();
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0, 1, 2, 0xfe, 0xff]))),
+            Arc::new(MockStorage::new(move |_, data| {
+                *uploaded_clone.lock().unwrap() = data.to_vec();
+                Ok(())
+            })),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UploadSingleImageOptions {
+            encoding: OutputEncoding::Base64,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &jpeg_data, &options)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*uploaded.lock().unwrap(), b"AAEC/v8=");
+    }
+
+    #[tokio::test]
+    async fn base64でエンコード後に10mbを超えるならバリデーションエラーを返す() {
+        // NOTE: 8 MB のバイナリは Base64 にすると約 10.7 MB になる
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::new(|_| Ok(vec![0; 8 * 1024 * 1024]))),
+            Arc::new(MockStorage::succeed()),
+        );
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UploadSingleImageOptions {
+            encoding: OutputEncoding::Base64,
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &jpeg_data, &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert!(msg.contains("encoded data size must be less than 10 MB"));
+        }
+    }
+
+    fn transparent_png() -> Vec<u8> {
+        let img = RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 128]));
+        Image::encode(&DynamicImage::ImageRgba8(img)).unwrap().data
+    }
+
+    /// 受け取ったフォーマットを記録するコンバーター
+    #[tokio::test]
+    async fn 圧縮の設定をコンバーターに渡す() {
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::with_options(move |image, options| {
+                received_clone.lock().unwrap().push(*options);
+                Ok(image.to_vec())
+            })),
+            Arc::new(MockStorage::succeed()),
+        );
+        let input = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let options = UploadSingleImageOptions {
+            compression: CompressionOptions {
+                quality: 128,
+                color_space: ColorSpace::Linear,
+                dithering: true,
+            },
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &input, &options)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![ConversionOptions {
+                format: DdsFormat::Dxt1,
+                quality: 128,
+                color_space: ColorSpace::Linear,
+                dithering: true,
+                mipmaps: Some(MipmapOptions::default()),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn 許可されていない圧縮の設定ならバリデーションエラーを返す() {
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(MockConverter::succeed()),
+            Arc::new(MockStorage::succeed()),
+        )
+        .with_conversion_policy(ConversionPolicy::parse("dxt1", "0-128", "srgb", false).unwrap());
+        let input = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+
+        let options = UploadSingleImageOptions {
+            compression: CompressionOptions {
+                quality: 200,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &input, &options)
+            .await;
+        assert!(matches!(result, Err(ServiceError::Validation(_))));
+        if let Err(ServiceError::Validation(msg)) = result {
+            assert_eq!(msg, "quality 200 is not allowed (allowed: 0-128)");
+        }
+
+        let options = UploadSingleImageOptions {
+            compression: CompressionOptions {
+                quality: 64,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = service
+            .execute("https://example.com", &input, &options)
+            .await;
+        assert!(result.is_ok());
+    }
+
+    fn recording_converter(formats: Arc<std::sync::Mutex<Vec<DdsFormat>>>) -> MockConverter {
+        MockConverter::with_options(move |image, options| {
+            formats.lock().unwrap().push(options.format);
+            Ok(image.to_vec())
+        })
+    }
+
+    #[tokio::test]
+    async fn 透過のあるpng画像ならdxt5で変換する() {
+        let formats = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(recording_converter(formats.clone())),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let result = service
+            .execute(
+                "https://example.com",
+                &transparent_png(),
+                &Default::default(),
+            )
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*formats.lock().unwrap(), vec![DdsFormat::Dxt5]);
+    }
+
+    #[tokio::test]
+    async fn 透過のないjpeg画像ならdxt1で変換する() {
+        let formats = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(recording_converter(formats.clone())),
+            Arc::new(MockStorage::succeed()),
+        );
+
+        let jpeg_data = fs::read("resources/4_multiple_size.jpg").await.unwrap();
+        let result = service
+            .execute("https://example.com", &jpeg_data, &Default::default())
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*formats.lock().unwrap(), vec![DdsFormat::Dxt1]);
+    }
+
+    #[tokio::test]
+    async fn フォーマットを指定したなら透過があってもそのフォーマットで変換する() {
+        let formats = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let service = UploadSingleImageServiceImpl::new(
+            Arc::new(recording_converter(formats.clone())),
+            Arc::new(MockStorage::succeed()),
+        );
+        let options = UploadSingleImageOptions {
+            format: TextureFormat::Fixed(DdsFormat::Dxt1),
+            ..Default::default()
+        };
+
+        let result = service
+            .execute("https://example.com", &transparent_png(), &options)
+            .await;
+        assert!(result.is_ok());
+        assert_eq!(*formats.lock().unwrap(), vec![DdsFormat::Dxt1]);
+    }
 }